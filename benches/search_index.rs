@@ -0,0 +1,73 @@
+// Compares finding a word through the trigram index with scanning every element's text, over a
+// notebook large enough for the difference to show. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use scribble::search_index::SearchIndex;
+
+const PAGES: usize = 200;
+const ELEMENTS_PER_PAGE: usize = 50;
+const RUNS: u32 = 100;
+
+const WORDS: [&str; 12] = [
+    "meeting", "Budget", "diagram", "follow-up", "Sketch", "notes",
+    "review", "Timeline", "question", "draft", "outline", "summary",
+];
+
+fn notebook() -> Vec<Vec<String>> {
+    (0..PAGES)
+        .map(|page| {
+            (0..ELEMENTS_PER_PAGE)
+                .map(|element| {
+                    let word = |offset: usize| WORDS[(page * 7 + element * 3 + offset) % WORDS.len()];
+                    format!("{} {} {} #{}-{}", word(0), word(5), word(9), page, element)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn time(label: &str, mut run: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        run();
+    }
+    let average: Duration = start.elapsed() / RUNS;
+    println!("{:<28} {:>10.1?}", label, average);
+}
+
+fn main() {
+    let pages = notebook();
+    let build = || {
+        let mut index = SearchIndex::new();
+        for (page_index, page) in pages.iter().enumerate() {
+            index.sync_page(page_index, page.iter().map(String::as_str));
+        }
+        index
+    };
+    time("build index", || {
+        black_box(build());
+    });
+    
+    let mut index = build();
+    let query = "timeline";
+    time("indexed find", || {
+        black_box(index.find(black_box(query)));
+    });
+    time("linear scan", || {
+        let found: Vec<(usize, usize)> = pages.iter().enumerate()
+            .flat_map(|(page_index, page)| page.iter().enumerate().map(move |(element_index, text)| (page_index, element_index, text)))
+            .filter(|(_, _, text)| text.to_lowercase().contains(query))
+            .map(|(page_index, element_index, _)| (page_index, element_index))
+            .collect();
+        black_box(found);
+    });
+    
+    // Re-syncing after a one-element edit only re-indexes that element
+    let mut edited = pages[0].clone();
+    time("sync after an edit", || {
+        edited[0].push('x');
+        index.sync_page(0, edited.iter().map(String::as_str));
+    });
+}
@@ -0,0 +1,1291 @@
+// PNG and SVG rendering of a single page, shared by the GUI and the headless CLI
+
+use crate::scribble_core::{self, Page, Stroke, TextElement, TextOrientation};
+use std::borrow::Cow;
+use image::{imageops, ImageBuffer, Pixel, Rgb, RgbImage, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+// Outline thickness relative to the font size
+pub const TEXT_OUTLINE_RATIO: f32 = 0.06;
+
+// Solid strokes at least this wide are drawn as filled outlines so corners keep their thickness
+pub const OUTLINE_STROKE_MIN_WIDTH: f32 = 6.0;
+
+// How far a sharp corner's miter may reach, relative to half the stroke width
+const MITER_LIMIT: f32 = 4.0;
+
+// Whether a stroke takes the filled-outline render path instead of a plain polyline
+pub fn is_outlined(stroke: &Stroke) -> bool {
+    stroke.width >= OUTLINE_STROKE_MIN_WIDTH && stroke.colors.is_empty() && stroke.pressures.is_empty() && stroke.is_drawable()
+}
+
+// Left and right edge points for each centerline point, offset by half the width with mitered joins
+pub fn stroke_outline_sides(points: &[[f32; 2]], width: f32) -> Vec<([f32; 2], [f32; 2])> {
+    let half_width = width / 2.0;
+    let normal = |a: [f32; 2], b: [f32; 2]| {
+        let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length > f32::EPSILON { Some([-dy / length, dx / length]) } else { None }
+    };
+    let segment_normals: Vec<Option<[f32; 2]>> = points.windows(2).map(|w| normal(w[0], w[1])).collect();
+    
+    let mut sides = Vec::with_capacity(points.len());
+    let mut last_normal = segment_normals.iter().flatten().next().copied().unwrap_or([0.0, 1.0]);
+    for (i, &[x, y]) in points.iter().enumerate() {
+        let before = i.checked_sub(1).and_then(|j| segment_normals[j]);
+        let after = segment_normals.get(i).copied().flatten();
+        let offset = match (before, after) {
+            (Some(n0), Some(n1)) => {
+                // The miter direction bisects the two normals; its length grows as the corner sharpens
+                let (mx, my) = (n0[0] + n1[0], n0[1] + n1[1]);
+                let length = (mx * mx + my * my).sqrt();
+                if length > f32::EPSILON {
+                    let miter = [mx / length, my / length];
+                    let scale = (half_width / (miter[0] * n1[0] + miter[1] * n1[1])).min(half_width * MITER_LIMIT);
+                    [miter[0] * scale, miter[1] * scale]
+                } else {
+                    [n1[0] * half_width, n1[1] * half_width]
+                }
+            }
+            (Some(n), None) | (None, Some(n)) => [n[0] * half_width, n[1] * half_width],
+            (None, None) => [last_normal[0] * half_width, last_normal[1] * half_width],
+        };
+        if let Some(n) = after.or(before) {
+            last_normal = n;
+        }
+        sides.push(([x + offset[0], y + offset[1]], [x - offset[0], y - offset[1]]));
+    }
+    sides
+}
+
+// Closed polygon around a stroke: down the left edge and back up the right
+pub fn stroke_outline(points: &[[f32; 2]], width: f32) -> Vec<[f32; 2]> {
+    let sides = stroke_outline_sides(points, width);
+    sides.iter().map(|(left, _)| *left).chain(sides.iter().rev().map(|(_, right)| *right)).collect()
+}
+
+// Blank space left around the content unless configured otherwise
+pub const DEFAULT_EXPORT_MARGIN: f32 = 20.0;
+
+// Layout choices shared by every exporter
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    // Blank space on every side of the content
+    pub margin: f32,
+    // Wrap text lines longer than this (in canvas units) at word boundaries; None widens the export instead
+    pub wrap_width: Option<f32>,
+    // Resample strokes along a Catmull-Rom curve with points at most this far apart; None keeps the stored points
+    pub curve_spacing: Option<f32>,
+    // How PNG exports stand in for text until glyphs are rasterized; SVG and HTML keep the real text
+    pub png_text: PngTextStyle,
+    // Write plain strokes to SVG as fitted cubic Bézier curves; false keeps a straight segment per captured point
+    pub svg_curves: bool,
+    // Footer or header text drawn over the finished export; None leaves the export unmarked
+    pub watermark: Option<Watermark>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PngTextStyle {
+    Outline, // Just the border of each line's box
+    #[default]
+    Blocks,  // A filled box per character, shaped like the letter's extent
+    Pattern, // The old dotted fill over each line's box
+}
+
+impl PngTextStyle {
+    pub const ALL: [PngTextStyle; 3] = [PngTextStyle::Outline, PngTextStyle::Blocks, PngTextStyle::Pattern];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            PngTextStyle::Outline => "Line outlines",
+            PngTextStyle::Blocks => "Letter blocks",
+            PngTextStyle::Pattern => "Dotted boxes",
+        }
+    }
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            margin: DEFAULT_EXPORT_MARGIN,
+            wrap_width: None,
+            curve_spacing: None,
+            png_text: PngTextStyle::default(),
+            svg_curves: true,
+            watermark: None,
+        }
+    }
+}
+
+// Text stamped into a corner of an export. `{date}` becomes today's date (UTC, YYYY-MM-DD) and
+// `{page}` the page's name.
+#[derive(Clone, Debug)]
+pub struct Watermark {
+    pub text: String,
+    pub position: WatermarkPosition,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomCenter,
+    #[default]
+    BottomRight,
+}
+
+impl WatermarkPosition {
+    pub const ALL: [WatermarkPosition; 5] = [
+        WatermarkPosition::TopLeft,
+        WatermarkPosition::TopRight,
+        WatermarkPosition::BottomLeft,
+        WatermarkPosition::BottomCenter,
+        WatermarkPosition::BottomRight,
+    ];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            WatermarkPosition::TopLeft => "Top left",
+            WatermarkPosition::TopRight => "Top right",
+            WatermarkPosition::BottomLeft => "Bottom left",
+            WatermarkPosition::BottomCenter => "Bottom center",
+            WatermarkPosition::BottomRight => "Bottom right",
+        }
+    }
+}
+
+// Watermark size and distance from the export's edges, in canvas units so it scales with PNG exports
+const WATERMARK_FONT_SIZE: f32 = 12.0;
+const WATERMARK_INSET: f32 = 4.0;
+const WATERMARK_COLOR: [u8; 3] = [120, 120, 120];
+const WATERMARK_OPACITY: f32 = 0.8;
+
+// The watermark as one line, with its placeholders filled in for this page
+fn watermark_line(watermark: &Watermark, page: &Page) -> String {
+    watermark.text
+        .replace("{date}", &scribble_core::format_date(scribble_core::unix_now()))
+        .replace("{page}", &page.name)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Top-left corner of a watermark line of the given size inside a frame of the given size
+fn watermark_origin(position: WatermarkPosition, [line_width, font_size]: [f32; 2], [frame_width, frame_height]: [f32; 2], inset: f32) -> [f32; 2] {
+    let x = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::BottomLeft => inset,
+        WatermarkPosition::BottomCenter => (frame_width - line_width) / 2.0,
+        WatermarkPosition::TopRight | WatermarkPosition::BottomRight => frame_width - inset - line_width,
+    };
+    let y = match position {
+        WatermarkPosition::TopLeft | WatermarkPosition::TopRight => inset,
+        _ => frame_height - inset - font_size,
+    };
+    [x, y]
+}
+
+// Advance of one character in the monospace export font, in ems. Wide (CJK, emoji) characters
+// take two columns and combining marks none; `str::len` counts bytes, which overestimates non-ASCII text.
+fn char_advance(c: char) -> f32 {
+    match c as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0.0,
+        0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x1F300..=0x1FAFF | 0x20000..=0x3FFFD => 1.2,
+        _ if c == '\t' => 2.4,
+        _ => 0.6,
+    }
+}
+
+// Estimated width of a line of text in the monospace export font
+pub fn text_line_width(line: &str, font_size: f32) -> f32 {
+    line.chars().map(char_advance).sum::<f32>() * font_size
+}
+
+// The lines a text element is exported as, after wrapping when a wrap width is set
+pub fn layout_text_lines(text: &str, font_size: f32, wrap_width: Option<f32>) -> Vec<String> {
+    let Some(wrap_width) = wrap_width.filter(|width| *width > 0.0) else {
+        return text.lines().map(str::to_string).collect();
+    };
+    
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let mut current = String::new();
+        for word in line.split_inclusive(' ') {
+            if !current.is_empty() && text_line_width(current.trim_end(), font_size) + text_line_width(word.trim_end(), font_size) > wrap_width {
+                lines.push(current.trim_end().to_string());
+                current.clear();
+            }
+            // A single word wider than the wrap width is broken between characters
+            for c in word.chars() {
+                if !current.is_empty() && c != ' ' && text_line_width(&current, font_size) + char_advance(c) * font_size > wrap_width {
+                    lines.push(current.trim_end().to_string());
+                    current.clear();
+                }
+                current.push(c);
+            }
+        }
+        lines.push(current.trim_end().to_string());
+    }
+    lines
+}
+
+// Point on the Catmull-Rom segment from p1 to p2 at t in 0..=1
+fn catmull_rom(p0: [f32; 2], p1: [f32; 2], p2: [f32; 2], p3: [f32; 2], t: f32) -> [f32; 2] {
+    let (t2, t3) = (t * t, t * t * t);
+    let axis = |i: usize| 0.5 * (2.0 * p1[i]
+        + (p2[i] - p0[i]) * t
+        + (2.0 * p0[i] - 5.0 * p1[i] + 4.0 * p2[i] - p3[i]) * t2
+        + (3.0 * p1[i] - p0[i] - 3.0 * p2[i] + p3[i]) * t3);
+    [axis(0), axis(1)]
+}
+
+// The stroke redrawn through a Catmull-Rom curve, adding points until none are more than `spacing`
+// apart. The curve passes through every original point; inserted points take their segment's color
+// and a blended pressure so gradients and pressure widths still line up. The curve doesn't bend
+// across corners, so sharp turns like an arrow tip stay where they were drawn.
+fn resample_stroke(stroke: &Stroke, spacing: f32) -> Stroke {
+    let points = &stroke.points;
+    if points.len() < 3 || !spacing.is_finite() || spacing <= 0.0 {
+        return stroke.clone();
+    }
+    let corners = corner_points(points);
+    let mut resampled = Stroke { points: Vec::new(), colors: Vec::new(), pressures: Vec::new(), ..stroke.clone() };
+    let last = points.len() - 1;
+    for i in 0..last {
+        let (p1, p2) = (points[i], points[i + 1]);
+        let p0 = if corners[i] { p1 } else { points[i - 1] };
+        let p3 = if corners[i + 1] { p2 } else { points[i + 2] };
+        let length = ((p2[0] - p1[0]).powi(2) + (p2[1] - p1[1]).powi(2)).sqrt();
+        let steps = ((length / spacing).ceil() as usize).max(1);
+        for step in 0..steps {
+            let t = step as f32 / steps as f32;
+            resampled.points.push(catmull_rom(p0, p1, p2, p3, t));
+            if let Some(&color) = stroke.colors.get(i) {
+                resampled.colors.push(color);
+            }
+            if let (Some(&from), Some(&to)) = (stroke.pressures.get(i), stroke.pressures.get(i + 1)) {
+                resampled.pressures.push(from + (to - from) * t);
+            }
+        }
+    }
+    resampled.points.push(points[last]);
+    resampled.colors.extend(stroke.colors.get(last));
+    resampled.pressures.extend(stroke.pressures.get(last));
+    resampled
+}
+
+// How far a fitted SVG curve may leave out a captured point, in canvas units
+pub const SVG_CURVE_TOLERANCE: f32 = 0.5;
+
+// The points that keep a polyline within `tolerance` of the original (Ramer-Douglas-Peucker).
+// The ends are always kept.
+fn simplify_polyline(points: &[[f32; 2]], tolerance: f32) -> Vec<[f32; 2]> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut spans = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = spans.pop() {
+        let ([x0, y0], [x1, y1]) = (points[first], points[last]);
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length = (dx * dx + dy * dy).sqrt();
+        let distance = |[x, y]: [f32; 2]| if length > 0.0 {
+            ((x - x0) * dy - (y - y0) * dx).abs() / length
+        } else {
+            ((x - x0).powi(2) + (y - y0).powi(2)).sqrt()
+        };
+        let farthest = (first + 1..last).max_by(|&a, &b| distance(points[a]).total_cmp(&distance(points[b])));
+        if let Some(index) = farthest
+            && distance(points[index]) > tolerance
+        {
+            keep[index] = true;
+            spans.push((first, index));
+            spans.push((index, last));
+        }
+    }
+    points.iter().zip(keep).filter(|(_, kept)| *kept).map(|(point, _)| *point).collect()
+}
+
+// A coordinate for SVG path data, to two decimals without trailing zeros
+fn svg_number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+// Cosine of the largest turn between a polyline's segments that still counts as smooth (60°).
+// Sharper turns, like the retraced tip of an arrow, are kept as corners rather than rounded.
+const SHARP_TURN_COS: f32 = 0.5;
+
+// Which points of a polyline are corners: the ends, and every point where the path turns by more
+// than `SHARP_TURN_COS` allows or doubles back on itself
+fn corner_points(points: &[[f32; 2]]) -> Vec<bool> {
+    (0..points.len())
+        .map(|i| {
+            if i == 0 || i + 1 == points.len() {
+                return true;
+            }
+            let ([x0, y0], [x1, y1], [x2, y2]) = (points[i - 1], points[i], points[i + 1]);
+            let (incoming, outgoing) = ([x1 - x0, y1 - y0], [x2 - x1, y2 - y1]);
+            let lengths = incoming[0].hypot(incoming[1]) * outgoing[0].hypot(outgoing[1]);
+            lengths <= f32::EPSILON || (incoming[0] * outgoing[0] + incoming[1] * outgoing[1]) / lengths < SHARP_TURN_COS
+        })
+        .collect()
+}
+
+// Path data through a stroke's points as cubic Bézier curves: the points that don't change its
+// shape are dropped, and the rest are joined by a Catmull-Rom curve that passes through each one.
+// Corners are left sharp, and control points stay within the bounds of the points around them,
+// so the curve never swings past the stroke.
+fn bezier_path_data(points: &[[f32; 2]]) -> String {
+    let points = simplify_polyline(points, SVG_CURVE_TOLERANCE);
+    let corners = corner_points(&points);
+    let point = |[x, y]: [f32; 2]| format!("{},{}", svg_number(x), svg_number(y));
+    let mut data = format!("M{}", point(points[0]));
+    let last = points.len() - 1;
+    for i in 0..last {
+        let (p1, p2) = (points[i], points[i + 1]);
+        if corners[i] && corners[i + 1] {
+            data.push_str(&format!(" L{}", point(p2)));
+            continue;
+        }
+        // A tangent doesn't reach across a corner
+        let p0 = if corners[i] { p1 } else { points[i - 1] };
+        let p3 = if corners[i + 1] { p2 } else { points[i + 2] };
+        let clamp = |[x, y]: [f32; 2]| {
+            let xs = [p0[0], p1[0], p2[0], p3[0]];
+            let ys = [p0[1], p1[1], p2[1], p3[1]];
+            [
+                x.clamp(xs.into_iter().fold(f32::INFINITY, f32::min), xs.into_iter().fold(f32::NEG_INFINITY, f32::max)),
+                y.clamp(ys.into_iter().fold(f32::INFINITY, f32::min), ys.into_iter().fold(f32::NEG_INFINITY, f32::max)),
+            ]
+        };
+        let control_1 = clamp([p1[0] + (p2[0] - p0[0]) / 6.0, p1[1] + (p2[1] - p0[1]) / 6.0]);
+        let control_2 = clamp([p2[0] - (p3[0] - p1[0]) / 6.0, p2[1] - (p3[1] - p1[1]) / 6.0]);
+        data.push_str(&format!(" C{} {} {}", point(control_1), point(control_2), point(p2)));
+    }
+    data
+}
+
+// The page's strokes as they should be exported, resampled only when asked
+fn export_strokes<'a>(page: &'a Page, options: &ExportOptions) -> Vec<Cow<'a, Stroke>> {
+    page.strokes.iter()
+        .map(|stroke| match options.curve_spacing {
+            Some(spacing) => Cow::Owned(resample_stroke(stroke, spacing)),
+            None => Cow::Borrowed(stroke),
+        })
+        .collect()
+}
+
+// Each non-empty line of a text element with its box as [x, y, width, height] relative to the
+// element's position. Horizontal lines stack downward; vertical text has a column per line.
+fn text_boxes(text_element: &TextElement, wrap_width: Option<f32>) -> Vec<(String, [f32; 4])> {
+    let font_size = text_element.font_size;
+    if text_element.orientation.is_vertical() {
+        let column_width = font_size * scribble_core::VERTICAL_COLUMN_RATIO;
+        let columns = text_element.text.lines().count();
+        return text_element.text.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(line_idx, line)| {
+                let column = if text_element.orientation == TextOrientation::VerticalRl { columns - 1 - line_idx } else { line_idx };
+                let height = line.chars().count() as f32 * font_size * scribble_core::VERTICAL_ADVANCE_RATIO;
+                (line.to_string(), [column as f32 * column_width + (column_width - font_size) / 2.0, 0.0, font_size, height])
+            })
+            .collect();
+    }
+    layout_text_lines(&text_element.text, font_size, wrap_width).into_iter().enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_idx, line)| {
+            let width = text_line_width(&line, font_size);
+            (line, [0.0, line_idx as f32 * font_size * 1.2, width, font_size])
+        })
+        .collect()
+}
+
+// The part of a character's cell its letter covers, as [left, top, right, bottom] fractions of the
+// cell, with the baseline at 0.8. None for characters that leave no mark.
+fn glyph_block(c: char) -> Option<[f32; 4]> {
+    match c {
+        _ if c.is_whitespace() || char_advance(c) == 0.0 => None,
+        '.' | ',' | ':' | ';' => Some([0.35, 0.6, 0.65, 0.85]),
+        '\'' | '"' | '`' | '^' => Some([0.35, 0.1, 0.65, 0.35]),
+        '-' | '~' | '=' | '+' | '*' => Some([0.15, 0.4, 0.85, 0.6]),
+        '_' => Some([0.05, 0.75, 0.95, 0.85]),
+        'g' | 'j' | 'p' | 'q' | 'y' => Some([0.15, 0.35, 0.85, 0.95]),
+        'b' | 'd' | 'f' | 'h' | 'k' | 'l' | 't' | 'i' => Some([0.15, 0.1, 0.85, 0.8]),
+        _ if c.is_lowercase() => Some([0.15, 0.35, 0.85, 0.8]),
+        _ if char_advance(c) > 1.0 => Some([0.05, 0.05, 0.95, 0.95]),
+        _ => Some([0.15, 0.1, 0.85, 0.8]),
+    }
+}
+
+// Canvas rectangles as [x, y, width, height] marking a line of text in a PNG export: one per
+// character in letter-block style, or the line's whole box otherwise
+fn text_marks(line: &str, line_box: [f32; 4], text_element: &TextElement, style: PngTextStyle) -> Vec<[f32; 4]> {
+    if style != PngTextStyle::Blocks {
+        return vec![line_box];
+    }
+    let [x, y, _, _] = line_box;
+    let font_size = text_element.font_size;
+    let vertical = text_element.orientation.is_vertical();
+    let mut offset = 0.0;
+    let mut marks = Vec::new();
+    for c in line.chars() {
+        // Vertical text steps down a fixed advance per character; horizontal text by its width
+        let (cell, advance) = if vertical {
+            let advance = font_size * scribble_core::VERTICAL_ADVANCE_RATIO;
+            ([x, y + offset, font_size, advance], advance)
+        } else {
+            let advance = char_advance(c) * font_size;
+            ([x + offset, y, advance, font_size], advance)
+        };
+        if let Some([left, top, right, bottom]) = glyph_block(c) {
+            let [cell_x, cell_y, cell_width, cell_height] = cell;
+            marks.push([cell_x + left * cell_width, cell_y + top * cell_height, (right - left) * cell_width, (bottom - top) * cell_height]);
+        }
+        offset += advance;
+    }
+    marks
+}
+
+// Paint one text mark in the given export style; `rect` is in image pixels as [x, y, width, height]
+fn paint_text_mark(img: &mut RgbImage, rect: [i32; 4], style: PngTextStyle, color: [u8; 3], opacity: f32) {
+    let [rect_x, rect_y, rect_width, rect_height] = rect;
+    let (width, height) = (img.width() as i32, img.height() as i32);
+    // Even tiny letters get a pixel, so short words don't vanish from small exports
+    let (rect_width, rect_height) = (rect_width.max(1), rect_height.max(1));
+    for x in rect_x.max(0)..(rect_x + rect_width).min(width) {
+        for y in rect_y.max(0)..(rect_y + rect_height).min(height) {
+            let marked = match style {
+                PngTextStyle::Blocks => true,
+                PngTextStyle::Outline => x == rect_x || y == rect_y || x == rect_x + rect_width - 1 || y == rect_y + rect_height - 1,
+                PngTextStyle::Pattern => (x + y) % 4 == 0,
+            };
+            if marked {
+                blend_pixel(img, x as u32, y as u32, color, opacity);
+            }
+        }
+    }
+}
+
+// Polyline of every connector on the page, joining its elements where they end up in the export
+fn connector_polylines(page: &Page, options: &ExportOptions, min_x: f32, min_y: f32) -> Vec<Vec<[f32; 2]>> {
+    let element_box = |idx: usize| {
+        let text_element = page.text_elements.get(idx)?;
+        let [text_x, text_y] = export_text_position(text_element.position, text_element.pinned, min_x, min_y);
+        text_boxes(text_element, options.wrap_width).into_iter()
+            .map(|(_, [x, y, width, height])| [text_x + x, text_y + y, text_x + x + width, text_y + y + height])
+            .reduce(|a, b| [a[0].min(b[0]), a[1].min(b[1]), a[2].max(b[2]), a[3].max(b[3])])
+            .map(|[x0, y0, x1, y1]| [x0, y0, x1 - x0, y1 - y0])
+    };
+    page.connectors.iter()
+        .filter_map(|connector| Some(scribble_core::connector_points(element_box(connector.from)?, element_box(connector.to)?, connector.arrow)))
+        .filter(|points| !points.is_empty())
+        .collect()
+}
+
+// Calculate content bounds for export as (min_x, min_y, width, height), with the margin on every side
+pub fn content_bounds(page: &Page, options: &ExportOptions) -> (f32, f32, f32, f32) {
+    let mut min_x = f32::INFINITY;
+    let mut min_y = f32::INFINITY;
+    let mut max_x = f32::NEG_INFINITY;
+    let mut max_y = f32::NEG_INFINITY;
+    
+    // Check stroke bounds; curves can swing slightly past their stored points. Strokes too short
+    // to draw don't count.
+    for stroke in export_strokes(page, options).iter().filter(|stroke| stroke.is_drawable()) {
+        for &[x, y] in &stroke.points {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+    
+    // Check text element bounds; pinned text is placed relative to the result instead
+    for text_element in page.text_elements.iter().filter(|t| !t.pinned) {
+        let [text_x, text_y] = text_element.position;
+        for (_, [x, y, width, height]) in text_boxes(text_element, options.wrap_width) {
+            min_x = min_x.min(text_x + x);
+            min_y = min_y.min(text_y + y);
+            max_x = max_x.max(text_x + x + width);
+            max_y = max_y.max(text_y + y + height);
+        }
+    }
+    
+    // If no content, return default canvas size
+    if min_x == f32::INFINITY {
+        return (0.0, 0.0, 800.0, 600.0);
+    }
+    
+    // Add padding around content
+    min_x -= options.margin;
+    min_y -= options.margin;
+    max_x += options.margin;
+    max_y += options.margin;
+    
+    // Ensure minimum size
+    let width = (max_x - min_x).max(400.0);
+    let height = (max_y - min_y).max(300.0);
+    
+    (min_x, min_y, width, height)
+}
+
+pub fn render_svg(page: &Page, options: &ExportOptions) -> String {
+    let mut svg = String::new();
+    
+    // Calculate content bounds
+    let (min_x, min_y, width, height) = content_bounds(page, options);
+    
+    // SVG header with calculated dimensions and viewBox; a turned page swaps its sides and
+    // draws everything through a transform into the turned frame
+    let quarter_turns = page.camera.quarter_turns % 4;
+    if quarter_turns == 0 {
+        svg.push_str(&format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="{:.0} {:.0} {:.0} {:.0}">"#,
+            width, height, min_x, min_y, width, height
+        ));
+        svg.push('\n');
+    } else {
+        let (turned_width, turned_height) = if quarter_turns % 2 == 1 { (height, width) } else { (width, height) };
+        let (max_x, max_y) = (min_x + width, min_y + height);
+        let [a, b, c, d, e, f] = match quarter_turns {
+            1 => [0.0, 1.0, -1.0, 0.0, max_y, -min_x],
+            2 => [-1.0, 0.0, 0.0, -1.0, max_x, max_y],
+            _ => [0.0, -1.0, 1.0, 0.0, -min_y, max_x],
+        };
+        svg.push_str(&format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.0} {:.0}">"#,
+            turned_width, turned_height, turned_width, turned_height
+        ));
+        svg.push('\n');
+        svg.push_str(&format!(r#"<g transform="matrix({} {} {} {} {} {})">"#, a, b, c, d, e, f));
+        svg.push('\n');
+    }
+    
+    // Background
+    svg.push_str(&format!(
+        r#"<rect x="{:.0}" y="{:.0}" width="{:.0}" height="{:.0}" fill="rgb(245,245,245)"/>"#,
+        min_x, min_y, width, height
+    ));
+    svg.push('\n');
+    
+    // Each stroke is its own group, identified and annotated so editors can pick it apart
+    for (stroke_idx, stroke) in export_strokes(page, options).iter().enumerate() {
+        if stroke.is_drawable() {
+            let [r, g, b] = stroke.color;
+            svg.push_str(&format!(
+                r##"<g id="stroke-{}" data-color="#{:02x}{:02x}{:02x}" data-width="{}"{}{}>"##,
+                stroke_idx, r, g, b,
+                stroke.width,
+                svg_opacity("data-opacity", stroke.opacity),
+                svg_tag(&stroke.tag)
+            ));
+            svg.push('\n');
+            
+            // Gradient strokes reference a linear gradient defined just before the path
+            let stroke_paint = if !stroke.colors.is_empty() {
+                let gradient_id = format!("stroke-gradient-{}", stroke_idx);
+                svg.push_str(&svg_linear_gradient(&gradient_id, stroke));
+                format!("url(#{})", gradient_id)
+            } else {
+                svg_rgb(stroke.color)
+            };
+            
+            if is_outlined(stroke) {
+                let outline: Vec<String> = stroke_outline(&stroke.points, stroke.width).iter()
+                    .map(|[x, y]| format!("{},{}", x, y))
+                    .collect();
+                svg.push_str(&format!(
+                    r#"<polygon points="{}" fill="{}"{}/>"#,
+                    outline.join(" "),
+                    stroke_paint,
+                    svg_opacity("fill-opacity", stroke.opacity)
+                ));
+            } else if !stroke.pressures.is_empty() {
+                // A path has one width, so pressure strokes become a line per segment
+                for (i, segment) in stroke.points.windows(2).enumerate() {
+                    let segment_paint = svg_rgb(stroke.colors.get(i).copied().unwrap_or(stroke.color));
+                    svg.push_str(&format!(
+                        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"{} stroke-linecap="round"/>"#,
+                        segment[0][0], segment[0][1], segment[1][0], segment[1][1],
+                        segment_paint,
+                        stroke.segment_width(i),
+                        svg_opacity("stroke-opacity", stroke.opacity)
+                    ));
+                }
+            } else if options.svg_curves {
+                svg.push_str(&format!(
+                    r#"<path d="{}" stroke="{}" stroke-width="{}"{} fill="none" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                    bezier_path_data(&stroke.points),
+                    stroke_paint,
+                    stroke.width,
+                    svg_opacity("stroke-opacity", stroke.opacity)
+                ));
+            } else {
+                svg.push_str(&format!(
+                    r#"<path d="M{},{}"#,
+                    stroke.points[0][0], stroke.points[0][1]
+                ));
+                
+                for [x, y] in &stroke.points[1..] {
+                    svg.push_str(&format!(" L{},{}", x, y));
+                }
+                
+                svg.push_str(&format!(
+                    r#"" stroke="{}" stroke-width="{}"{} fill="none" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                    stroke_paint,
+                    stroke.width,
+                    svg_opacity("stroke-opacity", stroke.opacity)
+                ));
+            }
+            svg.push('\n');
+            svg.push_str("</g>\n");
+        }
+    }
+    
+    // Connectors sit between the strokes and the text they join
+    for (connector_idx, points) in connector_polylines(page, options, min_x, min_y).into_iter().enumerate() {
+        let points: Vec<String> = points.iter().map(|[x, y]| format!("{},{}", x, y)).collect();
+        svg.push_str(&format!(
+            r#"<polyline id="connector-{}" points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+            connector_idx,
+            points.join(" "),
+            svg_rgb(scribble_core::CONNECTOR_COLOR),
+            scribble_core::CONNECTOR_WIDTH
+        ));
+        svg.push('\n');
+    }
+    
+    // Export text elements, one group per element holding a <text> per line
+    for (text_idx, text_element) in page.text_elements.iter().enumerate() {
+        let [text_x, text_y] = export_text_position(text_element.position, text_element.pinned, min_x, min_y);
+        svg.push_str(&format!(
+            r#"<g id="text-{}" data-font-size="{}"{}>"#,
+            text_idx,
+            text_element.font_size,
+            svg_tag(&text_element.tag)
+        ));
+        svg.push('\n');
+        
+        // Handle multiline text; vertical columns start at the top with glyphs centered on x
+        let writing_mode = match text_element.orientation {
+            TextOrientation::Horizontal => "",
+            TextOrientation::VerticalRl => r#" writing-mode="vertical-rl""#,
+            TextOrientation::VerticalLr => r#" writing-mode="vertical-lr""#,
+        };
+        for (line, [x, y, width, _]) in text_boxes(text_element, options.wrap_width) {
+            let (line_x, line_y) = if text_element.orientation.is_vertical() {
+                (text_x + x + width / 2.0, text_y + y)
+            } else {
+                (text_x + x, text_y + y + text_element.font_size)
+            };
+            // The shadow is a plain copy of the line, offset and placed before it
+            if let Some(shadow) = text_element.shadow {
+                svg.push_str(&format!(
+                    r#"<text x="{}" y="{}" font-size="{}" font-family="monospace" fill="{}"{}{}>{}</text>"#,
+                    line_x + shadow.offset[0],
+                    line_y + shadow.offset[1],
+                    text_element.font_size,
+                    svg_rgb(shadow.color),
+                    svg_opacity("fill-opacity", shadow.opacity),
+                    writing_mode,
+                    html_escape(&line)
+                ));
+                svg.push('\n');
+            }
+            // The outline is painted as a stroke behind the fill
+            let outline_attributes = match text_element.outline {
+                Some(outline) => format!(
+                    r#" stroke="{}" stroke-width="{}"{} stroke-linejoin="round" paint-order="stroke""#,
+                    svg_rgb(outline),
+                    text_element.font_size * TEXT_OUTLINE_RATIO * 2.0,
+                    svg_opacity("stroke-opacity", text_element.outline_opacity)
+                ),
+                None => String::new(),
+            };
+            svg.push_str(&format!(
+                r#"<text x="{}" y="{}" font-size="{}" font-family="monospace" fill="black"{}{}>{}</text>"#,
+                line_x,
+                line_y,
+                text_element.font_size,
+                writing_mode,
+                outline_attributes,
+                html_escape(&line)
+            ));
+            svg.push('\n');
+        }
+        svg.push_str("</g>\n");
+    }
+    
+    if quarter_turns != 0 {
+        svg.push_str("</g>\n");
+    }
+    
+    // The watermark goes on last, over the content and upright in the exported frame
+    if let Some(watermark) = &options.watermark {
+        let line = watermark_line(watermark, page);
+        if !line.is_empty() {
+            let (origin_x, origin_y) = if quarter_turns == 0 { (min_x, min_y) } else { (0.0, 0.0) };
+            let frame = if quarter_turns % 2 == 1 { [height, width] } else { [width, height] };
+            let line_size = [text_line_width(&line, WATERMARK_FONT_SIZE), WATERMARK_FONT_SIZE];
+            let [x, y] = watermark_origin(watermark.position, line_size, frame, WATERMARK_INSET);
+            svg.push_str(&format!(
+                r#"<text id="watermark" x="{}" y="{}" font-size="{}" font-family="monospace" fill="{}"{}>{}</text>"#,
+                origin_x + x,
+                origin_y + y + WATERMARK_FONT_SIZE,
+                WATERMARK_FONT_SIZE,
+                svg_rgb(WATERMARK_COLOR),
+                svg_opacity("fill-opacity", WATERMARK_OPACITY),
+                html_escape(&line)
+            ));
+            svg.push('\n');
+        }
+    }
+    
+    svg.push_str("</svg>");
+    svg
+}
+
+// A single self-contained HTML file showing one page at a time, with buttons and arrow keys to flip pages
+pub fn render_html(title: &str, pages: &[Page], options: &ExportOptions) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", html_escape(title)));
+    html.push_str(concat!(
+        "<style>\n",
+        "body { margin: 0; font-family: sans-serif; background: #ddd; }\n",
+        "nav { position: sticky; top: 0; display: flex; gap: 12px; align-items: center; justify-content: center; padding: 8px; background: #fff; border-bottom: 1px solid #bbb; }\n",
+        ".page { display: none; text-align: center; padding: 16px; }\n",
+        ".page.current { display: block; }\n",
+        ".page svg { max-width: 100%; height: auto; background: #fff; box-shadow: 0 1px 4px rgba(0,0,0,0.3); }\n",
+        "</style>\n",
+    ));
+    html.push_str("</head>\n<body>\n");
+    html.push_str("<nav><button id=\"prev\">&larr; Previous</button><span id=\"label\"></span><button id=\"next\">Next &rarr;</button></nav>\n");
+    
+    // The script below reveals the first page once loaded
+    for page in pages {
+        html.push_str(&format!(
+            "<section class=\"page\" data-name=\"{}\">\n",
+            html_escape(&page.name)
+        ));
+        html.push_str(&render_svg(page, options));
+        html.push_str("\n</section>\n");
+    }
+    
+    html.push_str(concat!(
+        "<script>\n",
+        "const pages = document.querySelectorAll('.page');\n",
+        "let current = 0;\n",
+        "function show(index) {\n",
+        "  if (pages.length === 0) return;\n",
+        "  current = (index + pages.length) % pages.length;\n",
+        "  pages.forEach((page, i) => page.classList.toggle('current', i === current));\n",
+        "  document.getElementById('label').textContent = `${pages[current].dataset.name} (${current + 1} / ${pages.length})`;\n",
+        "}\n",
+        "document.getElementById('prev').onclick = () => show(current - 1);\n",
+        "document.getElementById('next').onclick = () => show(current + 1);\n",
+        "document.addEventListener('keydown', (event) => {\n",
+        "  if (event.key === 'ArrowLeft' || event.key === 'PageUp') show(current - 1);\n",
+        "  if (event.key === 'ArrowRight' || event.key === 'PageDown') show(current + 1);\n",
+        "});\n",
+        "show(0);\n",
+        "</script>\n",
+    ));
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+// Largest PNG side length allowed unless configured otherwise
+pub const DEFAULT_MAX_EXPORT_DIMENSION: u32 = 8192;
+
+// Scale that keeps the PNG of a page within `max_dimension` on both sides, 1.0 if it already fits
+pub fn png_scale(page: &Page, options: &ExportOptions, max_dimension: u32) -> f32 {
+    bounds_scale(content_bounds(page, options), max_dimension)
+}
+
+fn bounds_scale((_, _, width, height): (f32, f32, f32, f32), max_dimension: u32) -> f32 {
+    let largest = width.max(height);
+    if largest.is_finite() && largest <= max_dimension as f32 {
+        1.0
+    } else if largest.is_finite() {
+        max_dimension as f32 / largest
+    } else {
+        // Non-finite bounds can't be scaled down sensibly; draw nothing rather than allocate
+        0.0
+    }
+}
+
+// Render a page at the given scale; pass the result of `png_scale` to bound the image size
+pub fn render_png(page: &Page, options: &ExportOptions, scale: f32) -> RgbImage {
+    render_png_within(page, options, scale, content_bounds(page, options))
+}
+
+// One canvas area covering every page's content bounds, so pages rendered into it all come out
+// the same size with their content where it sits on the canvas. Blank pages don't widen it.
+pub fn shared_bounds(pages: &[Page], options: &ExportOptions) -> (f32, f32, f32, f32) {
+    pages.iter()
+        .filter(|page| !page.strokes.is_empty() || !page.text_elements.is_empty())
+        .map(|page| content_bounds(page, options))
+        .reduce(|(ax, ay, aw, ah), (bx, by, bw, bh)| {
+            let (min_x, min_y) = (ax.min(bx), ay.min(by));
+            (min_x, min_y, (ax + aw).max(bx + bw) - min_x, (ay + ah).max(by + bh) - min_y)
+        })
+        .unwrap_or_else(|| content_bounds(&Page::new(""), options))
+}
+
+// Every page as a PNG within `max_dimension`, rendered one at a time as the iterator is advanced
+// so only one page's image is held at once. Uniform pages share one size and scale, padding
+// pages with less content; otherwise each page is fitted to its own content.
+pub fn render_pages_png<'a>(pages: &'a [Page], options: &'a ExportOptions, max_dimension: u32, uniform: bool) -> impl Iterator<Item = RgbImage> + 'a {
+    let shared = uniform.then(|| {
+        let bounds = shared_bounds(pages, options);
+        (bounds, bounds_scale(bounds, max_dimension))
+    });
+    pages.iter().map(move |page| match shared {
+        Some((bounds, scale)) => render_png_within(page, options, scale, bounds),
+        None => render_png(page, options, png_scale(page, options, max_dimension)),
+    })
+}
+
+// Frames of the page being drawn: strokes appear in the order they were drawn, then text in the
+// order it was placed, spread evenly over `frame_count` frames that all share the finished page's size.
+// Each frame is rendered as the iterator reaches it, so encoders can write frames as they come.
+pub fn render_animation_frames<'a>(page: &'a Page, options: &'a ExportOptions, scale: f32, frame_count: usize) -> impl ExactSizeIterator<Item = RgbImage> + 'a {
+    let bounds = content_bounds(page, options);
+    let total = page.strokes.len() + page.text_elements.len();
+    let frame_count = frame_count.max(2);
+    (0..frame_count).map(move |frame| {
+        let revealed = (total * frame).div_ceil(frame_count - 1);
+        let mut partial = page.clone();
+        partial.strokes.truncate(revealed);
+        partial.text_elements.truncate(revealed.saturating_sub(page.strokes.len()));
+        render_png_within(&partial, options, scale, bounds)
+    })
+}
+
+// Render into the given (min_x, min_y, width, height) canvas area rather than the page's own bounds
+fn render_png_within(page: &Page, options: &ExportOptions, scale: f32, bounds: (f32, f32, f32, f32)) -> RgbImage {
+    let (min_x, min_y, width_f, height_f) = bounds;
+    let width = ((width_f * scale) as u32).max(1);
+    let height = ((height_f * scale) as u32).max(1);
+    if scale <= 0.0 {
+        return ImageBuffer::from_pixel(width, height, Rgb([245u8, 245u8, 245u8]));
+    }
+    
+    // Create image buffer with light grey background
+    let mut img: RgbImage = ImageBuffer::new(width, height);
+    let bg_color = Rgb([245u8, 245u8, 245u8]); // Light grey background
+    
+    // Fill background
+    for pixel in img.pixels_mut() {
+        *pixel = bg_color;
+    }
+    
+    // Draw strokes
+    for stroke in &export_strokes(page, options) {
+        if stroke.opacity >= 1.0 {
+            draw_stroke_on_image(&mut img, stroke, [min_x, min_y], scale, Rgb);
+        } else {
+            composite_translucent_stroke(&mut img, stroke, [min_x, min_y], scale);
+        }
+    }
+    
+    // Draw connectors between the strokes and the text they join
+    for points in connector_polylines(page, options, min_x, min_y) {
+        for segment in points.windows(2) {
+            draw_line_on_image(
+                &mut img,
+                ((segment[0][0] - min_x) * scale) as i32,
+                ((segment[0][1] - min_y) * scale) as i32,
+                ((segment[1][0] - min_x) * scale) as i32,
+                ((segment[1][1] - min_y) * scale) as i32,
+                Rgb(scribble_core::CONNECTOR_COLOR),
+                ((scribble_core::CONNECTOR_WIDTH * scale) as u32).max(1),
+            );
+        }
+    }
+    
+    // Stand in for text with boxes in the chosen style, since the PNG path has no font rasterizer
+    for text_element in &page.text_elements {
+        let [text_element_x, text_element_y] = export_text_position(text_element.position, text_element.pinned, min_x, min_y);
+        let to_image = |[x, y, box_width, box_height]: [f32; 4], offset: [f32; 2]| [
+            ((x - min_x + offset[0]) * scale).round() as i32,
+            ((y - min_y + offset[1]) * scale).round() as i32,
+            (box_width * scale).round() as i32,
+            (box_height * scale).round() as i32,
+        ];
+        
+        for (line, [x, y, box_width, box_height]) in text_boxes(text_element, options.wrap_width) {
+            let line_box = [text_element_x + x, text_element_y + y, box_width, box_height];
+            let marks = text_marks(&line, line_box, text_element, options.png_text);
+            
+            // The shadow's marks go down first, offset from the text
+            if let Some(shadow) = text_element.shadow {
+                for &mark in &marks {
+                    paint_text_mark(&mut img, to_image(mark, shadow.offset), options.png_text, shadow.color, shadow.opacity);
+                }
+            }
+            
+            // Then the outline as a solid halo around each mark
+            if let Some(outline_color) = text_element.outline {
+                let radius = (text_element.font_size * scale * TEXT_OUTLINE_RATIO).ceil().max(1.0) as i32;
+                for &mark in &marks {
+                    let [mark_x, mark_y, mark_width, mark_height] = to_image(mark, [0.0, 0.0]);
+                    let halo = [mark_x - radius, mark_y - radius, mark_width.max(1) + 2 * radius, mark_height.max(1) + 2 * radius];
+                    paint_text_mark(&mut img, halo, PngTextStyle::Blocks, outline_color, text_element.outline_opacity);
+                }
+            }
+            
+            for &mark in &marks {
+                paint_text_mark(&mut img, to_image(mark, [0.0, 0.0]), options.png_text, [0, 0, 0], 1.0);
+            }
+        }
+    }
+    
+    // Turn the finished image the way the page is viewed
+    let mut img = match page.camera.quarter_turns % 4 {
+        1 => imageops::rotate90(&img),
+        2 => imageops::rotate180(&img),
+        3 => imageops::rotate270(&img),
+        _ => img,
+    };
+    
+    // Then mark the watermark upright over everything, in the text style of the rest of the export
+    if let Some(watermark) = &options.watermark {
+        let line = watermark_line(watermark, page);
+        let mut line_element = TextElement::new([0.0, 0.0], line.as_str());
+        line_element.font_size = WATERMARK_FONT_SIZE * scale;
+        let line_size = [text_line_width(&line, line_element.font_size), line_element.font_size];
+        let frame = [img.width() as f32, img.height() as f32];
+        let [x, y] = watermark_origin(watermark.position, line_size, frame, WATERMARK_INSET * scale);
+        for [mark_x, mark_y, mark_width, mark_height] in text_marks(&line, [x, y, line_size[0], line_size[1]], &line_element, options.png_text) {
+            let rect = [mark_x.round() as i32, mark_y.round() as i32, mark_width.round() as i32, mark_height.round() as i32];
+            paint_text_mark(&mut img, rect, options.png_text, WATERMARK_COLOR, WATERMARK_OPACITY);
+        }
+    }
+    img
+}
+
+// Draw a stroke's pixels in full color; `origin` is the canvas point at the image's top-left
+fn draw_stroke_on_image<P: Pixel<Subpixel = u8>>(
+    img: &mut ImageBuffer<P, Vec<u8>>,
+    stroke: &Stroke,
+    origin: [f32; 2],
+    scale: f32,
+    pixel: impl Fn([u8; 3]) -> P,
+) {
+    let [min_x, min_y] = origin;
+    if is_outlined(stroke) {
+        // Fill the outline as two triangles per segment
+        let to_image = |[x, y]: [f32; 2]| [(x - min_x) * scale, (y - min_y) * scale];
+        let sides = stroke_outline_sides(&stroke.points, stroke.width);
+        for pair in sides.windows(2) {
+            let ((l0, r0), (l1, r1)) = (pair[0], pair[1]);
+            let (l0, r0, l1, r1) = (to_image(l0), to_image(r0), to_image(l1), to_image(r1));
+            fill_triangle_on_image(img, [l0, r0, l1], pixel(stroke.color));
+            fill_triangle_on_image(img, [r0, r1, l1], pixel(stroke.color));
+        }
+    } else if stroke.is_drawable() {
+        for i in 0..stroke.points.len() - 1 {
+            let [start_x, start_y] = stroke.points[i];
+            let [end_x, end_y] = stroke.points[i + 1];
+            let segment_color = stroke.colors.get(i).copied().unwrap_or(stroke.color);
+            
+            // Adjust coordinates relative to content bounds
+            draw_line_on_image(
+                img,
+                ((start_x - min_x) * scale) as i32,
+                ((start_y - min_y) * scale) as i32,
+                ((end_x - min_x) * scale) as i32,
+                ((end_y - min_y) * scale) as i32,
+                pixel(segment_color),
+                ((stroke.segment_width(i) * scale) as u32).max(1),
+            );
+        }
+    }
+}
+
+// Translucent strokes are drawn opaque onto a layer covering just the stroke, then blended in once,
+// so a stroke never darkens itself where its own segments overlap but does darken other strokes
+fn composite_translucent_stroke(img: &mut RgbImage, stroke: &Stroke, origin: [f32; 2], scale: f32) {
+    let (img_width, img_height) = img.dimensions();
+    let [min_x, min_y] = origin;
+    let reach = stroke.width * scale / 2.0 + 2.0;
+    let (mut left, mut top, mut right, mut bottom) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for &[x, y] in &stroke.points {
+        let (x, y) = ((x - min_x) * scale, (y - min_y) * scale);
+        left = left.min(x - reach);
+        top = top.min(y - reach);
+        right = right.max(x + reach);
+        bottom = bottom.max(y + reach);
+    }
+    let left = left.floor().clamp(0.0, img_width as f32) as u32;
+    let top = top.floor().clamp(0.0, img_height as f32) as u32;
+    let right = right.ceil().clamp(0.0, img_width as f32) as u32;
+    let bottom = bottom.ceil().clamp(0.0, img_height as f32) as u32;
+    if right <= left || bottom <= top {
+        return;
+    }
+    
+    let mut layer: RgbaImage = ImageBuffer::new(right - left, bottom - top);
+    let layer_origin = [min_x + left as f32 / scale, min_y + top as f32 / scale];
+    draw_stroke_on_image(&mut layer, stroke, layer_origin, scale, |[r, g, b]| Rgba([r, g, b, 255]));
+    
+    let alpha = stroke.opacity.clamp(0.0, 1.0);
+    for (x, y, layer_pixel) in layer.enumerate_pixels() {
+        let Rgba([r, g, b, coverage]) = *layer_pixel;
+        if coverage == 0 {
+            continue;
+        }
+        blend_pixel(img, left + x, top + y, [r, g, b], alpha);
+    }
+}
+
+// Pinned text is stored relative to the view's top-left, so it goes at the export's top-left
+fn export_text_position(position: [f32; 2], pinned: bool, min_x: f32, min_y: f32) -> [f32; 2] {
+    if pinned {
+        [min_x + position[0], min_y + position[1]]
+    } else {
+        position
+    }
+}
+
+// Helper function to draw lines on image buffer
+fn draw_line_on_image<P: Pixel<Subpixel = u8>>(
+    img: &mut ImageBuffer<P, Vec<u8>>,
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+    color: P,
+    width: u32,
+) {
+    let (width_i, height_i) = img.dimensions();
+    let (img_width, img_height) = (width_i as i32, height_i as i32);
+    
+    // Bresenham's line algorithm
+    let dx = (x1 - x0).abs();
+    let dy = (y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx - dy;
+    
+    let mut x = x0;
+    let mut y = y0;
+    
+    loop {
+        // Draw a circle for line thickness
+        for offset_x in -(width as i32 / 2)..=(width as i32 / 2) {
+            for offset_y in -(width as i32 / 2)..=(width as i32 / 2) {
+                let px = x + offset_x;
+                let py = y + offset_y;
+                
+                // Check if pixel is within stroke radius and image bounds
+                if offset_x * offset_x + offset_y * offset_y <= (width as i32 / 2).pow(2) &&
+                   px >= 0 && px < img_width && py >= 0 && py < img_height {
+                    img.put_pixel(px as u32, py as u32, color);
+                }
+            }
+        }
+        
+        if x == x1 && y == y1 {
+            break;
+        }
+        
+        let e2 = 2 * err;
+        if e2 > -dy {
+            err -= dy;
+            x += sx;
+        }
+        if e2 < dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+// Fill every pixel whose center lies inside a triangle
+fn fill_triangle_on_image<P: Pixel<Subpixel = u8>>(img: &mut ImageBuffer<P, Vec<u8>>, corners: [[f32; 2]; 3], color: P) {
+    let (img_width, img_height) = img.dimensions();
+    let [a, b, c] = corners;
+    let edge = |p: [f32; 2], q: [f32; 2], x: f32, y: f32| (q[0] - p[0]) * (y - p[1]) - (q[1] - p[1]) * (x - p[0]);
+    let area = edge(a, b, c[0], c[1]);
+    if area.abs() <= f32::EPSILON {
+        return;
+    }
+    
+    let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+    let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+    let max_x = (a[0].max(b[0]).max(c[0]).ceil().max(0.0) as u32).min(img_width);
+    let max_y = (a[1].max(b[1]).max(c[1]).ceil().max(0.0) as u32).min(img_height);
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            // Inside when all three edge tests agree with the triangle's winding
+            let w0 = edge(b, c, px, py) * area.signum();
+            let w1 = edge(c, a, px, py) * area.signum();
+            let w2 = edge(a, b, px, py) * area.signum();
+            if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
+                img.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+// An sRGB color as SVG paint
+fn svg_rgb([r, g, b]: [u8; 3]) -> String {
+    format!("rgb({},{},{})", r, g, b)
+}
+
+// Paint a pixel with a color at some opacity over what is already there
+fn blend_pixel(img: &mut RgbImage, x: u32, y: u32, color: [u8; 3], opacity: f32) {
+    let Rgb(background) = *img.get_pixel(x, y);
+    img.put_pixel(x, y, Rgb(scribble_core::blend_rgb(color, background, opacity)));
+}
+
+// Opacity attribute for translucent strokes, empty when fully opaque
+fn svg_opacity(attribute: &str, opacity: f32) -> String {
+    if opacity < 1.0 {
+        format!(r#" {}="{}""#, attribute, opacity)
+    } else {
+        String::new()
+    }
+}
+
+// Tag carried through as a data attribute, empty when untagged
+fn svg_tag(tag: &Option<String>) -> String {
+    match tag {
+        Some(tag) => format!(r#" data-tag="{}""#, html_escape(tag)),
+        None => String::new(),
+    }
+}
+
+// Linear gradient approximating a stroke's per-point colors along its start-to-end axis
+fn svg_linear_gradient(id: &str, stroke: &Stroke) -> String {
+    let mut start = stroke.points[0];
+    let mut end = stroke.points[stroke.points.len() - 1];
+    
+    // Closed or nearly closed strokes run the gradient across their bounding box instead
+    if (end[0] - start[0]).hypot(end[1] - start[1]) < stroke.width.max(1.0) {
+        start = [f32::INFINITY, f32::INFINITY];
+        end = [f32::NEG_INFINITY, f32::NEG_INFINITY];
+        for &[x, y] in &stroke.points {
+            start = [start[0].min(x), start[1].min(y)];
+            end = [end[0].max(x), end[1].max(y)];
+        }
+    }
+    
+    let mut svg = format!(
+        r#"<defs><linearGradient id="{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+        id, start[0], start[1], end[0], end[1]
+    );
+    
+    // Sample a bounded number of stops so long strokes don't bloat the file
+    let max_stops = 32;
+    let last_index = stroke.colors.len() - 1;
+    let step = (last_index / max_stops).max(1);
+    let mut indices: Vec<usize> = (0..=last_index).step_by(step).collect();
+    if indices.last() != Some(&last_index) {
+        indices.push(last_index);
+    }
+    
+    for index in indices {
+        let color = svg_rgb(stroke.colors[index]);
+        let offset = if last_index == 0 { 0.0 } else { index as f32 / last_index as f32 };
+        svg.push_str(&format!(
+            r#"<stop offset="{:.3}" stop-color="{}"/>"#,
+            offset, color
+        ));
+    }
+    
+    svg.push_str("</linearGradient></defs>\n");
+    svg
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Every coordinate pair in SVG path data, control points included
+    fn path_points(data: &str) -> Vec<[f32; 2]> {
+        data.split_whitespace()
+            .map(|token| token.trim_start_matches(['M', 'L', 'C']))
+            .map(|pair| {
+                let (x, y) = pair.split_once(',').expect("coordinate pair");
+                [x.parse().expect("x"), y.parse().expect("y")]
+            })
+            .collect()
+    }
+    
+    fn bounds(points: &[[f32; 2]]) -> [f32; 4] {
+        points.iter().fold([f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY], |[x0, y0, x1, y1], &[x, y]| {
+            [x0.min(x), y0.min(y), x1.max(x), y1.max(y)]
+        })
+    }
+    
+    fn assert_within(points: &[[f32; 2]], input: &[[f32; 2]]) {
+        let [min_x, min_y, max_x, max_y] = bounds(input);
+        for &[x, y] in points {
+            assert!(
+                (min_x - 0.01..=max_x + 0.01).contains(&x) && (min_y - 0.01..=max_y + 0.01).contains(&y),
+                "({}, {}) leaves the input bounds {:?}", x, y, [min_x, min_y, max_x, max_y]
+            );
+        }
+    }
+    
+    #[test]
+    fn curves_keep_arrow_tips_sharp() {
+        let arrow = [[0.0, 0.0], [100.0, 0.0], [88.0, -6.0], [100.0, 0.0], [88.0, 6.0]];
+        let data = bezier_path_data(&arrow);
+        assert_within(&path_points(&data), &arrow);
+        assert!(data.contains("L100,0"), "tip should be a corner: {}", data);
+    }
+    
+    #[test]
+    fn curve_control_points_stay_within_input_bounds() {
+        let wave: Vec<[f32; 2]> = (0..60).map(|i| [i as f32 * 5.0, (i as f32 * 0.4).sin() * 40.0]).collect();
+        let zigzag = [[0.0, -10.0], [10.0, 0.0], [20.0, -1.0], [30.0, 8.0], [40.0, -3.0], [35.0, 20.0]];
+        for input in [&wave[..], &zigzag[..]] {
+            assert_within(&path_points(&bezier_path_data(input)), input);
+        }
+    }
+    
+    #[test]
+    fn resampling_keeps_arrow_tips_in_place() {
+        let arrow = vec![[0.0, 0.0], [100.0, 0.0], [88.0, -6.0], [100.0, 0.0], [88.0, 6.0]];
+        let resampled = resample_stroke(&Stroke::new(arrow.clone()), 2.0);
+        assert_within(&resampled.points, &arrow);
+        for point in &arrow {
+            assert!(resampled.points.contains(point), "{:?} should be kept", point);
+        }
+    }
+    
+    #[test]
+    fn translucent_strokes_blend_with_what_is_under_them() {
+        let mut page = Page::new("");
+        page.add_stroke(Stroke::new(vec![[50.0, 0.0], [50.0, 100.0]]).with_width(10.0));
+        page.add_stroke(Stroke::new(vec![[0.0, 50.0], [100.0, 50.0]]).with_color([0, 0, 255]).with_width(10.0).with_opacity(0.4));
+        let options = ExportOptions::default();
+        let (min_x, min_y, _, _) = content_bounds(&page, &options);
+        let image = render_png(&page, &options, 1.0);
+        let pixel = |x: f32, y: f32| image.get_pixel((x - min_x) as u32, (y - min_y) as u32).0;
+        
+        assert_eq!(pixel(20.0, 20.0), [245, 245, 245], "untouched background");
+        assert_eq!(pixel(20.0, 50.0), [147, 147, 249], "40% blue over the light grey background");
+        assert_eq!(pixel(50.0, 50.0), [0, 0, 102], "40% blue over the black stroke");
+        assert_eq!(pixel(50.0, 20.0), [0, 0, 0], "the opaque stroke is untouched away from the crossing");
+    }
+}
@@ -0,0 +1,4 @@
+// Scribble's document model and file format, usable without the GUI
+pub mod scribble_core;
+pub mod export;
+pub mod search_index;
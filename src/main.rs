@@ -1,680 +1,4292 @@
 use eframe::egui;
 use regex::Regex;
 use arboard::Clipboard;
+use ab_glyph::Font;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
-use std::path::Path;
-use image::{ImageBuffer, Rgb, RgbImage};
+use std::path::{Path, PathBuf};
+use scribble::export::{self, TEXT_OUTLINE_RATIO};
+use scribble::scribble_core::{self, TextOrientation};
+use scribble::search_index::{SearchIndex, TextLocation};
 
 fn main() -> Result<(), eframe::Error> {
+    // Export flags render a file and exit without opening a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = run_cli_export(&args) {
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            eprintln!("{}", CLI_USAGE);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
+    // A plain path argument, e.g. from the OS file manager, is opened on startup
+    let startup_file = args.first().map(PathBuf::from);
+    
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([800.0, 600.0]),
         ..Default::default()
     };
     eframe::run_native(
-        "Scribble - Drawing App",
+        WINDOW_TITLE,
         options,
-        Box::new(|_cc| Ok(Box::new(ScribbleApp::default()))),
+        Box::new(move |_cc| {
+            let mut app = ScribbleApp::with_settings(AppSettings::load());
+            if let Some(path) = startup_file {
+                app.open_file(&path);
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
 
+const WINDOW_TITLE: &str = "Scribble - Drawing App";
+
+const CLI_USAGE: &str = "Usage: scribble [--export-png | --export-svg | --export-html] <input.scribble> <output> [--page N] [--max-size PX] [--margin PX] [--wrap PX] [--curves PX] [--png-text outline|blocks|pattern] [--svg-lines] [--watermark TEXT] [--watermark-position top-left|top-right|bottom-left|bottom-center|bottom-right]";
+
+enum ExportFormat {
+    Png,
+    Svg,
+    Html, // Every page, in one file
+}
+
+// Headless export for scripts and CI. Returns None when no export flag was given,
+// so the GUI starts as usual.
+fn run_cli_export(args: &[String]) -> Option<Result<(), Box<dyn std::error::Error>>> {
+    let mut format = None;
+    let mut page_number = None;
+    let mut max_size = None;
+    let mut margin = None;
+    let mut wrap = None;
+    let mut curves = None;
+    let mut png_text = None;
+    let mut svg_lines = false;
+    let mut watermark = None;
+    let mut watermark_position = None;
+    let mut paths = Vec::new();
+    
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--export-png" => format = Some(ExportFormat::Png),
+            "--export-svg" => format = Some(ExportFormat::Svg),
+            "--export-html" => format = Some(ExportFormat::Html),
+            "--page" => page_number = args_iter.next(),
+            "--max-size" => max_size = args_iter.next(),
+            "--margin" => margin = args_iter.next(),
+            "--wrap" => wrap = args_iter.next(),
+            "--curves" => curves = args_iter.next(),
+            "--png-text" => png_text = args_iter.next(),
+            "--svg-lines" => svg_lines = true,
+            "--watermark" => watermark = args_iter.next(),
+            "--watermark-position" => watermark_position = args_iter.next(),
+            _ => paths.push(arg),
+        }
+    }
+    let format = format?;
+    Some(cli_export_options(margin, wrap, curves, png_text, svg_lines, watermark, watermark_position)
+        .and_then(|options| cli_export(format, &paths, page_number, max_size, &options)))
+}
+
+fn cli_export_options(
+    margin: Option<&String>,
+    wrap: Option<&String>,
+    curves: Option<&String>,
+    png_text: Option<&String>,
+    svg_lines: bool,
+    watermark: Option<&String>,
+    watermark_position: Option<&String>,
+) -> Result<export::ExportOptions, Box<dyn std::error::Error>> {
+    let mut options = export::ExportOptions::default();
+    if let Some(margin) = margin {
+        options.margin = margin.parse::<f32>().ok().filter(|margin| margin.is_finite() && *margin >= 0.0)
+            .ok_or("--margin must be zero or a positive number of pixels")?;
+    }
+    if let Some(wrap) = wrap {
+        options.wrap_width = Some(wrap.parse::<f32>().ok().filter(|wrap| wrap.is_finite() && *wrap > 0.0)
+            .ok_or("--wrap must be a positive number of pixels")?);
+    }
+    if let Some(curves) = curves {
+        options.curve_spacing = Some(curves.parse::<f32>().ok().filter(|curves| curves.is_finite() && *curves > 0.0)
+            .ok_or("--curves must be a positive number of pixels")?);
+    }
+    if let Some(png_text) = png_text {
+        options.png_text = match png_text.as_str() {
+            "outline" => export::PngTextStyle::Outline,
+            "blocks" => export::PngTextStyle::Blocks,
+            "pattern" => export::PngTextStyle::Pattern,
+            _ => return Err("--png-text must be outline, blocks or pattern".into()),
+        };
+    }
+    options.svg_curves = !svg_lines;
+    let position = match watermark_position.map(String::as_str) {
+        None => export::WatermarkPosition::default(),
+        Some("top-left") => export::WatermarkPosition::TopLeft,
+        Some("top-right") => export::WatermarkPosition::TopRight,
+        Some("bottom-left") => export::WatermarkPosition::BottomLeft,
+        Some("bottom-center") => export::WatermarkPosition::BottomCenter,
+        Some("bottom-right") => export::WatermarkPosition::BottomRight,
+        Some(_) => return Err("--watermark-position must be top-left, top-right, bottom-left, bottom-center or bottom-right".into()),
+    };
+    if watermark_position.is_some() && watermark.is_none() {
+        return Err("--watermark-position needs --watermark".into());
+    }
+    options.watermark = watermark.map(|text| export::Watermark { text: text.clone(), position });
+    Ok(options)
+}
+
+fn cli_export(
+    format: ExportFormat,
+    paths: &[&String],
+    page_number: Option<&String>,
+    max_size: Option<&String>,
+    options: &export::ExportOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let [input, output] = paths else {
+        return Err("expected an input file and an output file".into());
+    };
+    
+    // Defaults to the page the notebook was saved on, like the GUI export
+    let notebook = scribble_core::Notebook::load(input)?;
+    let page_index = match page_number {
+        Some(number) => number.parse::<usize>().ok()
+            .filter(|n| (1..=notebook.pages.len()).contains(n))
+            .ok_or_else(|| format!("--page must be between 1 and {}", notebook.pages.len()))?
+            - 1,
+        None => notebook.current_page_index,
+    };
+    let page = &notebook.pages[page_index];
+    
+    match format {
+        ExportFormat::Png => {
+            let max_dimension = match max_size {
+                Some(size) => size.parse::<u32>().ok().filter(|&size| size > 0)
+                    .ok_or("--max-size must be a positive number of pixels")?,
+                None => export::DEFAULT_MAX_EXPORT_DIMENSION,
+            };
+            let scale = export::png_scale(page, options, max_dimension);
+            if scale < 1.0 {
+                eprintln!("Note: image scaled down to {:.0}% to fit within {} px", scale * 100.0, max_dimension);
+            }
+            export::render_png(page, options, scale).save(output)?
+        }
+        ExportFormat::Svg => fs::write(output, export::render_svg(page, options))?,
+        ExportFormat::Html => {
+            let title = if notebook.meta.title.trim().is_empty() { "Scribble Notebook" } else { notebook.meta.title.as_str() };
+            fs::write(output, export::render_html(title, &notebook.pages, options))?
+        }
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 struct Stroke {
     points: Vec<egui::Pos2>,
     color: egui::Color32,
     width: f32,
+    colors: Vec<egui::Color32>, // Optional per-point colors, empty for single-color strokes
+    pressures: Vec<f32>, // Optional per-point stylus pressure, empty for constant width
+    smoothing: f32, // Strength applied once when the stroke was committed, 0 for raw input
+    opacity: f32, // 1.0 for opaque, lower for highlighter-style strokes
+    tag: Option<String>,
+    build_order: Option<u32>, // Presentation step that reveals it, None for always shown
 }
 
-#[derive(Clone)]
-struct TextElement {
-    position: egui::Pos2,
-    text: String,
-    font_size: f32,
+impl Stroke {
+    fn is_drawable(&self) -> bool {
+        self.points.len() >= scribble_core::MIN_DRAWABLE_POINTS
+    }
+    
+    // Color of the segment starting at the given point
+    fn segment_color(&self, point_index: usize) -> egui::Color32 {
+        self.colors.get(point_index).copied().unwrap_or(self.color)
+    }
+    
+    fn is_gradient(&self) -> bool {
+        !self.colors.is_empty()
+    }
+    
+    fn is_pressure_sensitive(&self) -> bool {
+        !self.pressures.is_empty()
+    }
+    
+    fn segment_width(&self, point_index: usize) -> f32 {
+        scribble_core::segment_width(self.width, &self.pressures, point_index)
+    }
 }
 
-#[derive(Clone)]
-struct Page {
-    strokes: Vec<Stroke>,
-    text_elements: Vec<TextElement>,
-    name: String,
+// Per-point pressures for a finished stroke: empty when no force was ever reported, otherwise
+// points sampled before the first reading take that first reading
+fn stroke_pressures(samples: &[Option<f32>]) -> Vec<f32> {
+    let Some(first) = samples.iter().copied().flatten().next() else {
+        return Vec::new();
+    };
+    samples.iter().map(|sample| sample.unwrap_or(first).clamp(0.0, 1.0)).collect()
 }
 
-// Serializable versions for saving/loading
-#[derive(Serialize, Deserialize)]
-struct SerializableStroke {
-    points: Vec<(f32, f32)>,
-    color: (u8, u8, u8),
-    width: f32,
+// Number of neighbour-averaging passes made at full smoothing strength
+const SMOOTHING_PASSES: usize = 3;
+
+// Pull each interior point toward the midpoint of its neighbours. Endpoints stay put and
+// the point count is unchanged, so per-point colors still line up.
+fn smooth_points(points: &[egui::Pos2], strength: f32) -> Vec<egui::Pos2> {
+    let mut smoothed = points.to_vec();
+    if strength <= 0.0 || points.len() < 3 {
+        return smoothed;
+    }
+    
+    for _ in 0..SMOOTHING_PASSES {
+        let previous = smoothed.clone();
+        for i in 1..previous.len() - 1 {
+            let midpoint = previous[i - 1].lerp(previous[i + 1], 0.5);
+            smoothed[i] = previous[i].lerp(midpoint, strength);
+        }
+    }
+    smoothed
 }
 
-#[derive(Serialize, Deserialize)]
-struct SerializableTextElement {
-    position: (f32, f32),
-    text: String,
-    font_size: f32,
+#[derive(PartialEq)]
+enum PenMode {
+    Solid,
+    Gradient,
+    Calligraphy,
 }
 
-#[derive(Serialize, Deserialize)]
-struct SerializablePage {
-    strokes: Vec<SerializableStroke>,
-    text_elements: Vec<SerializableTextElement>,
-    name: String,
+// Per-point width factors for a broad nib held at `nib_degrees` from horizontal: moving across the
+// nib's edge lays down its full width, sliding along the edge leaves a hairline. Each point takes
+// the direction of the segment leaving it, the last point that of the segment arriving.
+fn calligraphy_widths(points: &[egui::Pos2], nib_degrees: f32) -> Vec<f32> {
+    let nib = nib_degrees.to_radians();
+    (0..points.len())
+        .map(|i| {
+            let (from, to) = if i + 1 < points.len() { (points[i], points[i + 1]) } else { (points[i.saturating_sub(1)], points[i]) };
+            let direction = to - from;
+            if direction == egui::Vec2::ZERO {
+                1.0
+            } else {
+                (direction.angle() - nib).sin().abs()
+            }
+        })
+        .collect()
 }
 
-#[derive(Serialize, Deserialize)]
-struct ScribbleNotebook {
-    pages: Vec<SerializablePage>,
-    current_page_index: usize,
-    canvas_size: (f32, f32),
+// Points filling the jump from `last` to `next` (both excluded) so no two are more than `max_gap` apart.
+// They follow a Catmull-Rom curve that carries on from `before`, so a fast sweep stays round.
+fn fill_capture_gap(before: Option<egui::Pos2>, last: egui::Pos2, next: egui::Pos2, max_gap: f32) -> Vec<egui::Pos2> {
+    if max_gap <= 0.0 {
+        return Vec::new();
+    }
+    let steps = (last.distance(next) / max_gap).ceil() as usize;
+    let before = before.unwrap_or(last);
+    (1..steps)
+        .map(|step| {
+            let t = step as f32 / steps as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            let curve = |p0: f32, p1: f32, p2: f32, p3: f32| {
+                0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+            };
+            egui::Pos2::new(curve(before.x, last.x, next.x, next.x), curve(before.y, last.y, next.y, next.y))
+        })
+        .collect()
 }
 
-#[derive(Serialize, Deserialize)]
-struct ScribbleProject {
-    strokes: Vec<SerializableStroke>,
-    text_elements: Vec<SerializableTextElement>,
-    canvas_size: (f32, f32),
+// Screen distance within which a new stroke's ends snap to existing stroke ends
+const ENDPOINT_SNAP_DISTANCE: f32 = 12.0;
+
+// Canvas distance over which the gradient pen cycles through the full hue wheel
+const GRADIENT_HUE_CYCLE_LENGTH: f32 = 600.0;
+
+// Extra distance beyond a stroke's half-width that still counts as a hit
+const STROKE_HIT_TOLERANCE: f32 = 4.0;
+
+// The point on a segment nearest to `point`
+fn closest_point_on_segment(point: egui::Pos2, start: egui::Pos2, end: egui::Pos2) -> egui::Pos2 {
+    let segment = end - start;
+    let length_sq = segment.length_sq();
+    if length_sq == 0.0 {
+        return start;
+    }
+    let t = ((point - start).dot(segment) / length_sq).clamp(0.0, 1.0);
+    start + segment * t
 }
 
-#[derive(PartialEq)]
-enum Tool {
-    Draw,
-    Text,
-    Select,
+fn distance_to_segment(point: egui::Pos2, start: egui::Pos2, end: egui::Pos2) -> f32 {
+    point.distance(closest_point_on_segment(point, start, end))
 }
 
-struct ScribbleApp {
-    // Multi-page notebook support
-    pages: Vec<Page>,
-    current_page_index: usize,
-    is_notebook_mode: bool,
-    show_create_notebook_dialog: bool,
-    new_notebook_pages_input: String,
+// Byte ranges of a regex's matches in `text`. Zero-width matches, such as `a*` between the letters
+// of "banana", are left out unless `empty_matches` is set; `find_iter` already steps past them safely.
+fn regex_match_ranges(regex: &Regex, text: &str, empty_matches: bool) -> Vec<(usize, usize)> {
+    regex.find_iter(text)
+        .filter(|found| empty_matches || !found.is_empty())
+        .map(|found| (found.start(), found.end()))
+        .collect()
+}
+
+// Byte ranges in `text` of a literal query, ignoring case. Matching `text` itself rather than a
+// lowercased copy keeps the ranges on character boundaries even where lowercasing changes lengths.
+fn literal_match_ranges(query: &str, text: &str) -> Vec<(usize, usize)> {
+    match Regex::new(&format!("(?i){}", regex::escape(query))) {
+        Ok(regex) => regex_match_ranges(&regex, text, false),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Replace up to `limit` of a regex's matches (0 for all), skipping zero-width ones like
+// `regex_match_ranges`. With `expand`, `$1` and the like in the replacement name capture groups.
+fn replace_regex_matches(regex: &Regex, text: &str, limit: usize, replacement: &str, expand: bool, empty_matches: bool) -> String {
+    let mut replaced = String::new();
+    let mut last_end = 0;
+    let mut count = 0;
+    for captures in regex.captures_iter(text) {
+        let Some(found) = captures.get(0) else {
+            continue;
+        };
+        if found.is_empty() && !empty_matches {
+            continue;
+        }
+        if limit != 0 && count == limit {
+            break;
+        }
+        replaced.push_str(&text[last_end..found.start()]);
+        if expand {
+            captures.expand(replacement, &mut replaced);
+        } else {
+            replaced.push_str(replacement);
+        }
+        last_end = found.end();
+        count += 1;
+    }
+    replaced.push_str(&text[last_end..]);
+    replaced
+}
+
+// The span of the segment from `start` (0) to `end` (1) lying inside a circle, if any
+fn segment_span_in_circle(start: egui::Pos2, end: egui::Pos2, center: egui::Pos2, radius: f32) -> Option<(f32, f32)> {
+    let direction = end - start;
+    let offset = start - center;
+    let a = direction.length_sq();
+    if a <= f32::EPSILON {
+        return (offset.length() <= radius).then_some((0.0, 1.0));
+    }
+    let b = 2.0 * direction.dot(offset);
+    let c = offset.length_sq() - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let (from, to) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+    (from <= 1.0 && to >= 0.0).then_some((from.max(0.0), to.min(1.0)))
+}
+
+// The span of the segment from `start` (0) to `end` (1) lying inside a rectangle, if any
+// (Liang-Barsky clipping)
+fn segment_span_in_rect(start: egui::Pos2, end: egui::Pos2, rect: egui::Rect) -> Option<(f32, f32)> {
+    let direction = end - start;
+    let (mut from, mut to) = (0.0_f32, 1.0_f32);
+    for (step, room) in [
+        (-direction.x, start.x - rect.min.x),
+        (direction.x, rect.max.x - start.x),
+        (-direction.y, start.y - rect.min.y),
+        (direction.y, rect.max.y - start.y),
+    ] {
+        if step == 0.0 {
+            if room < 0.0 {
+                return None;
+            }
+        } else if step < 0.0 {
+            from = from.max(room / step);
+        } else {
+            to = to.min(room / step);
+        }
+    }
+    (from <= to).then_some((from, to))
+}
+
+// Screen distance within which trace mode pulls the pen onto an existing stroke
+const TRACE_SNAP_DISTANCE: f32 = 12.0;
+
+// How far apart two strokes' paths may be and still count as the same line traced twice
+const DUPLICATE_STROKE_TOLERANCE: f32 = 3.0;
+
+// Whether every point of each stroke lies within `tolerance` of the other's path
+fn strokes_nearly_identical(a: &Stroke, b: &Stroke, tolerance: f32) -> bool {
+    if a.points.is_empty() || b.points.is_empty() {
+        return false;
+    }
     
-    current_stroke: Vec<egui::Pos2>,
-    is_drawing: bool,
-    stroke_color: egui::Color32,
-    stroke_width: f32,
-    current_tool: Tool,
-    text_input: String,
-    text_font_size: f32,
-    active_text_position: Option<egui::Pos2>,
-    text_input_id: egui::Id,
-    search_query: String,
-    search_results: Vec<usize>,
-    show_search: bool,
-    regex_mode: bool,
-    search_error: Option<String>,
-    text_collisions: Vec<usize>, // Track which text elements have arrow collisions
-    // Text selection fields
-    is_selecting_text: bool,
-    selection_start: Option<egui::Pos2>,
-    selection_end: Option<egui::Pos2>,
-    selected_text_elements: Vec<usize>,
-    clipboard: Option<Clipboard>,
-    // Drag and drop state
-    is_file_hovered: bool,
+    let covers = |path: &[egui::Pos2], points: &[egui::Pos2]| {
+        points.iter().all(|&point| match path {
+            [single] => point.distance(*single) <= tolerance,
+            _ => path.windows(2).any(|segment| distance_to_segment(point, segment[0], segment[1]) <= tolerance),
+        })
+    };
+    
+    // Cheap rejection before the point-by-point test
+    let bounds = |points: &[egui::Pos2]| egui::Rect::from_points(points);
+    let (a_bounds, b_bounds) = (bounds(&a.points), bounds(&b.points));
+    if (a_bounds.min - b_bounds.min).abs().max_elem() > tolerance || (a_bounds.max - b_bounds.max).abs().max_elem() > tolerance {
+        return false;
+    }
+    covers(&a.points, &b.points) && covers(&b.points, &a.points)
 }
 
-impl Default for ScribbleApp {
+#[derive(Clone)]
+struct TextElement {
+    position: egui::Pos2,
+    text: String,
+    font_size: f32,
+    outline: Option<egui::Color32>, // Contrasting halo drawn behind the text
+    shadow: Option<(egui::Vec2, egui::Color32)>, // Offset in canvas units and color of a drop shadow
+    pinned: bool, // Position is relative to the canvas area's top-left on screen, unaffected by pan/zoom
+    tag: Option<String>,
+    build_order: Option<u32>,
+    orientation: TextOrientation,
+}
+
+// Zoom limits for the per-page camera
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 10.0;
+
+// Limits for the UI scale, a multiple of the display's own scale factor
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 3.0;
+
+// View transform between canvas coordinates and screen coordinates
+#[derive(Clone, Copy)]
+struct Camera {
+    offset: egui::Vec2, // Canvas point shown at the screen origin
+    zoom: f32,
+    quarter_turns: u8, // Clockwise view rotation in 90° steps, 0 to 3
+}
+
+impl Default for Camera {
     fn default() -> Self {
         Self {
-            pages: vec![Page {
-                strokes: Vec::new(),
-                text_elements: Vec::new(),
-                name: "Page 1".to_string(),
-            }],
-            current_page_index: 0,
-            is_notebook_mode: false,
-            show_create_notebook_dialog: false,
-            new_notebook_pages_input: "5".to_string(),
-            current_stroke: Vec::new(),
-            is_drawing: false,
-            stroke_color: egui::Color32::BLACK,
-            stroke_width: 2.0,
-            current_tool: Tool::Draw,
-            text_input: String::new(),
-            text_font_size: 20.0,
-            active_text_position: None,
-            text_input_id: egui::Id::new("floating_text_input"),
-            search_query: String::new(),
-            search_results: Vec::new(),
-            show_search: false,
-            regex_mode: false,
-            search_error: None,
-            text_collisions: Vec::new(),
-            is_selecting_text: false,
-            selection_start: None,
-            selection_end: None,
-            selected_text_elements: Vec::new(),
-            clipboard: Clipboard::new().ok(),
-            // Drag and drop state
-            is_file_hovered: false,
+            offset: egui::Vec2::ZERO,
+            zoom: 1.0,
+            quarter_turns: 0,
         }
     }
 }
 
-impl ScribbleApp {
-    // Helper methods for current page access
-    fn current_page(&self) -> &Page {
-        &self.pages[self.current_page_index]
+// Turn a vector clockwise on screen (y points down) by whole quarter turns
+fn turn_vec(v: egui::Vec2, quarter_turns: u8) -> egui::Vec2 {
+    match quarter_turns % 4 {
+        0 => v,
+        1 => egui::Vec2::new(-v.y, v.x),
+        2 => -v,
+        _ => egui::Vec2::new(v.y, -v.x),
     }
-    
-    fn current_page_mut(&mut self) -> &mut Page {
-        &mut self.pages[self.current_page_index]
+}
+
+impl Camera {
+    fn to_screen(self, pos: egui::Pos2) -> egui::Pos2 {
+        self.vec_to_screen(pos.to_vec2() - self.offset).to_pos2()
     }
     
-    fn current_strokes(&self) -> &Vec<Stroke> {
-        &self.current_page().strokes
+    fn to_canvas(self, pos: egui::Pos2) -> egui::Pos2 {
+        (turn_vec(pos.to_vec2(), 4 - self.quarter_turns % 4) / self.zoom + self.offset).to_pos2()
     }
     
-    fn current_strokes_mut(&mut self) -> &mut Vec<Stroke> {
-        &mut self.current_page_mut().strokes
+    // A canvas-space distance and direction as it appears on screen
+    fn vec_to_screen(self, v: egui::Vec2) -> egui::Vec2 {
+        self.turn(v * self.zoom)
     }
     
-    fn current_text_elements(&self) -> &Vec<TextElement> {
-        &self.current_page().text_elements
+    fn turn(self, v: egui::Vec2) -> egui::Vec2 {
+        turn_vec(v, self.quarter_turns)
     }
     
-    fn current_text_elements_mut(&mut self) -> &mut Vec<TextElement> {
-        &mut self.current_page_mut().text_elements
+    // Whether canvas x runs up and down the screen
+    fn swaps_axes(self) -> bool {
+        self.quarter_turns % 2 == 1
     }
     
-    // Calculate content bounds for export
-    fn calculate_content_bounds(&self) -> (f32, f32, f32, f32) {
-        let mut min_x = f32::INFINITY;
-        let mut min_y = f32::INFINITY;
-        let mut max_x = f32::NEG_INFINITY;
-        let mut max_y = f32::NEG_INFINITY;
-        
-        // Check stroke bounds
-        for stroke in self.current_strokes() {
-            for point in &stroke.points {
-                min_x = min_x.min(point.x);
-                min_y = min_y.min(point.y);
-                max_x = max_x.max(point.x);
-                max_y = max_y.max(point.y);
-            }
-        }
-        
-        // Check text element bounds
-        for text_element in self.current_text_elements() {
-            let lines: Vec<&str> = text_element.text.lines().collect();
-            let line_height = text_element.font_size * 1.2;
-            
-            for (line_idx, line) in lines.iter().enumerate() {
-                if !line.trim().is_empty() {
-                    let line_y = text_element.position.y + (line_idx as f32) * line_height;
-                    let estimated_width = line.len() as f32 * text_element.font_size * 0.6;
-                    
-                    min_x = min_x.min(text_element.position.x);
-                    min_y = min_y.min(line_y);
-                    max_x = max_x.max(text_element.position.x + estimated_width);
-                    max_y = max_y.max(line_y + text_element.font_size);
-                }
-            }
-        }
-        
-        // If no content, return default canvas size
-        if min_x == f32::INFINITY {
-            return (0.0, 0.0, 800.0, 600.0);
-        }
-        
-        // Add padding around content
-        let padding = 20.0;
-        min_x -= padding;
-        min_y -= padding;
-        max_x += padding;
-        max_y += padding;
-        
-        // Ensure minimum size
-        let width = (max_x - min_x).max(400.0);
-        let height = (max_y - min_y).max(300.0);
-        
-        (min_x, min_y, width, height)
-    }
-
-    // Notebook management methods
-    fn create_notebook(&mut self, page_count: usize) {
-        self.pages.clear();
-        for i in 1..=page_count {
-            self.pages.push(Page {
-                strokes: Vec::new(),
-                text_elements: Vec::new(),
-                name: format!("Page {}", i),
-            });
-        }
-        self.current_page_index = 0;
-        self.is_notebook_mode = true;
+    fn angle(self) -> f32 {
+        self.quarter_turns as f32 * std::f32::consts::FRAC_PI_2
     }
     
-    fn add_new_page(&mut self) {
-        let page_number = self.pages.len() + 1;
-        self.pages.push(Page {
-            strokes: Vec::new(),
-            text_elements: Vec::new(),
-            name: format!("Page {}", page_number),
-        });
+    // Quarter turns keep rectangles axis-aligned, though their corners trade places
+    fn rect_to_screen(self, rect: egui::Rect) -> egui::Rect {
+        egui::Rect::from_two_pos(self.to_screen(rect.min), self.to_screen(rect.max))
     }
     
-    fn next_page(&mut self) {
-        if self.current_page_index < self.pages.len() - 1 {
-            self.current_page_index += 1;
-        }
+    // Place the view so a canvas point shows at a screen position
+    fn show_at(&mut self, canvas_pos: egui::Pos2, screen_pos: egui::Pos2) {
+        self.offset = canvas_pos.to_vec2() - turn_vec(screen_pos.to_vec2(), 4 - self.quarter_turns % 4) / self.zoom;
     }
     
-    fn previous_page(&mut self) {
-        if self.current_page_index > 0 {
-            self.current_page_index -= 1;
-        }
+    // Move the canvas by a distance on screen
+    fn pan(&mut self, screen_delta: egui::Vec2) {
+        self.offset -= turn_vec(screen_delta, 4 - self.quarter_turns % 4) / self.zoom;
     }
     
-    fn perform_search(&mut self) {
-        self.search_results.clear();
-        self.search_error = None;
-        
-        if self.search_query.is_empty() {
-            return;
-        }
-        
-        let text_elements = self.current_text_elements().clone();
-        
-        if self.regex_mode {
-            match Regex::new(&self.search_query) {
-                Ok(regex) => {
-                    for (index, text_element) in text_elements.iter().enumerate() {
-                        if regex.is_match(&text_element.text) {
-                            self.search_results.push(index);
-                        }
-                    }
-                }
-                Err(e) => {
-                    self.search_error = Some(format!("Regex error: {}", e));
-                }
-            }
-        } else {
-            let query_lower = self.search_query.to_lowercase();
-            for (index, text_element) in text_elements.iter().enumerate() {
-                if text_element.text.to_lowercase().contains(&query_lower) {
-                    self.search_results.push(index);
-                }
-            }
-        }
+    // Zoom while keeping the canvas point under the cursor fixed
+    fn zoom_around(&mut self, screen_pos: egui::Pos2, factor: f32) {
+        let anchor = self.to_canvas(screen_pos);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.show_at(anchor, screen_pos);
     }
     
-    fn get_total_match_count(&self) -> usize {
-        let mut total_matches = 0;
-        
-        if self.search_query.is_empty() {
-            return 0;
+    // Rotate the view by quarter turns (negative for counterclockwise) around a screen point
+    fn rotate_around(&mut self, screen_pos: egui::Pos2, quarter_turns: i32) {
+        let anchor = self.to_canvas(screen_pos);
+        self.quarter_turns = (self.quarter_turns as i32 + quarter_turns).rem_euclid(4) as u8;
+        self.show_at(anchor, screen_pos);
+    }
+}
+
+// Paint a guide across the canvas: a vertical one at a canvas x, or a horizontal one at a canvas y,
+// which run the other way on screen while the view is turned a quarter
+fn paint_guide(painter: &egui::Painter, camera: Camera, canvas_rect: egui::Rect, vertical: bool, value: f32, stroke: egui::Stroke) {
+    let screen = camera.to_screen(if vertical { egui::Pos2::new(value, 0.0) } else { egui::Pos2::new(0.0, value) });
+    if vertical != camera.swaps_axes() {
+        painter.vline(screen.x, canvas_rect.y_range(), stroke);
+    } else {
+        painter.hline(canvas_rect.x_range(), screen.y, stroke);
+    }
+}
+
+// Paint text turned with the view, placed where `painter.text` would put it on an unturned view
+fn paint_view_text(
+    painter: &egui::Painter,
+    camera: Camera,
+    anchor: egui::Pos2,
+    align: egui::Align2,
+    text: impl ToString,
+    font_id: egui::FontId,
+    color: egui::Color32,
+) {
+    if camera.quarter_turns == 0 {
+        painter.text(anchor, align, text, font_id, color);
+        return;
+    }
+    let galley = painter.layout_no_wrap(text.to_string(), font_id, color);
+    let corner = align.anchor_size(egui::Pos2::ZERO, galley.size()).min.to_vec2();
+    painter.add(egui::epaint::TextShape::new(anchor + camera.turn(corner), galley, color).with_angle(camera.angle()));
+}
+
+#[derive(Clone)]
+struct Page {
+    strokes: Vec<Stroke>,
+    text_elements: Vec<TextElement>,
+    name: String,
+    camera: Camera, // Each page remembers where it was being viewed
+    section: Option<String>,
+    connectors: Vec<scribble_core::Connector>, // Lines between text elements, by index into text_elements
+    guides_x: Vec<f32>, // Canvas x of each vertical custom guide
+    guides_y: Vec<f32>, // Canvas y of each horizontal custom guide
+}
+
+impl Page {
+    fn new(name: String) -> Self {
+        Self {
+            strokes: Vec::new(),
+            text_elements: Vec::new(),
+            name,
+            camera: Camera::default(),
+            section: None,
+            connectors: Vec::new(),
+            guides_x: Vec::new(),
+            guides_y: Vec::new(),
+        }
+    }
+}
+
+// Conversions to and from the core document model used for saving and loading
+
+// Colors cross into the document model as straight sRGB bytes plus an opacity. egui keeps
+// Color32 premultiplied, so a translucent color's own r/g/b would come out darkened.
+
+fn color_to_rgb(color: egui::Color32) -> [u8; 3] {
+    let [r, g, b, _] = color.to_srgba_unmultiplied();
+    [r, g, b]
+}
+
+fn color_opacity(color: egui::Color32) -> f32 {
+    scribble_core::alpha_to_opacity(color.a())
+}
+
+fn rgb_to_color([r, g, b]: [u8; 3]) -> egui::Color32 {
+    egui::Color32::from_rgb(r, g, b)
+}
+
+fn rgb_opacity_to_color([r, g, b]: [u8; 3], opacity: f32) -> egui::Color32 {
+    egui::Color32::from_rgba_unmultiplied(r, g, b, scribble_core::opacity_to_alpha(opacity))
+}
+
+impl From<&Stroke> for scribble_core::Stroke {
+    fn from(stroke: &Stroke) -> Self {
+        Self {
+            points: stroke.points.iter().map(|p| [p.x, p.y]).collect(),
+            color: color_to_rgb(stroke.color),
+            width: stroke.width,
+            colors: stroke.colors.iter().copied().map(color_to_rgb).collect(),
+            pressures: stroke.pressures.clone(),
+            smoothing: stroke.smoothing,
+            opacity: stroke.opacity,
+            tag: stroke.tag.clone(),
+            build_order: stroke.build_order,
+        }
+    }
+}
+
+impl From<scribble_core::Stroke> for Stroke {
+    fn from(stroke: scribble_core::Stroke) -> Self {
+        Self {
+            points: stroke.points.into_iter().map(|[x, y]| egui::Pos2::new(x, y)).collect(),
+            color: rgb_to_color(stroke.color),
+            width: stroke.width,
+            colors: stroke.colors.into_iter().map(rgb_to_color).collect(),
+            pressures: stroke.pressures,
+            smoothing: stroke.smoothing,
+            opacity: stroke.opacity,
+            tag: stroke.tag,
+            build_order: stroke.build_order,
+        }
+    }
+}
+
+impl From<&TextElement> for scribble_core::TextElement {
+    fn from(text_element: &TextElement) -> Self {
+        scribble_core::TextElement::new([text_element.position.x, text_element.position.y], text_element.text.clone())
+            .with_font_size(text_element.font_size)
+            .with_outline(text_element.outline.map(color_to_rgb))
+            .with_outline_opacity(text_element.outline.map_or(1.0, color_opacity))
+            .with_shadow(text_element.shadow.map(|(offset, color)| scribble_core::TextShadow {
+                offset: [offset.x, offset.y],
+                color: color_to_rgb(color),
+                opacity: color_opacity(color),
+            }))
+            .with_pinned(text_element.pinned)
+            .with_tag(text_element.tag.clone())
+            .with_build_order(text_element.build_order)
+            .with_orientation(text_element.orientation)
+    }
+}
+
+impl From<scribble_core::TextElement> for TextElement {
+    fn from(text_element: scribble_core::TextElement) -> Self {
+        Self {
+            position: egui::Pos2::new(text_element.position[0], text_element.position[1]),
+            text: text_element.text,
+            font_size: text_element.font_size,
+            outline: text_element.outline.map(|outline| rgb_opacity_to_color(outline, text_element.outline_opacity)),
+            shadow: text_element.shadow.map(|shadow| {
+                (egui::Vec2::new(shadow.offset[0], shadow.offset[1]), rgb_opacity_to_color(shadow.color, shadow.opacity))
+            }),
+            pinned: text_element.pinned,
+            tag: text_element.tag,
+            build_order: text_element.build_order,
+            orientation: text_element.orientation,
+        }
+    }
+}
+
+impl From<&Page> for scribble_core::Page {
+    fn from(page: &Page) -> Self {
+        Self {
+            strokes: page.strokes.iter().map(scribble_core::Stroke::from).collect(),
+            text_elements: page.text_elements.iter().map(scribble_core::TextElement::from).collect(),
+            name: page.name.clone(),
+            camera: scribble_core::Camera {
+                offset: [page.camera.offset.x, page.camera.offset.y],
+                zoom: page.camera.zoom,
+                quarter_turns: page.camera.quarter_turns,
+            },
+            section: page.section.clone(),
+            connectors: page.connectors.clone(),
+            guides_x: page.guides_x.clone(),
+            guides_y: page.guides_y.clone(),
+        }
+    }
+}
+
+impl From<scribble_core::Page> for Page {
+    fn from(page: scribble_core::Page) -> Self {
+        Self {
+            strokes: page.strokes.into_iter().map(Stroke::from).collect(),
+            text_elements: page.text_elements.into_iter().map(TextElement::from).collect(),
+            name: page.name,
+            camera: Camera {
+                offset: egui::Vec2::new(page.camera.offset[0], page.camera.offset[1]),
+                zoom: page.camera.zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+                quarter_turns: page.camera.quarter_turns % 4,
+            },
+            section: page.section,
+            connectors: page.connectors,
+            guides_x: page.guides_x,
+            guides_y: page.guides_y,
+        }
+    }
+}
+
+// The data export: every element of every page with its measured bounds and resolved colors,
+// for scripts and other tools. Unlike .scribble files it is only written, never read back.
+#[derive(Serialize)]
+struct DataExport {
+    format: &'static str,
+    version: u32,
+    pages: Vec<PageData>,
+}
+
+const DATA_EXPORT_FORMAT: &str = "scribble-data";
+const DATA_EXPORT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct PageData {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    section: Option<String>,
+    elements: Vec<ElementData>, // Strokes, then text, then connectors, each in drawing order
+}
+
+// A canvas rectangle; pinned text is measured from the top-left of the view instead
+#[derive(Serialize)]
+struct BoundsData {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl From<egui::Rect> for BoundsData {
+    fn from(rect: egui::Rect) -> Self {
+        Self { x: rect.min.x, y: rect.min.y, width: rect.width(), height: rect.height() }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ElementData {
+    Stroke {
+        index: usize, // Position among the page's strokes
+        bounds: BoundsData, // Includes the stroke's width
+        color: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        colors: Vec<String>, // Per-point colors of a gradient stroke
+        opacity: f32,
+        width: f32,
+        point_count: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+    },
+    Text {
+        index: usize, // Position among the page's text elements, as connectors refer to it
+        bounds: BoundsData,
+        text: String,
+        font_size: f32,
+        color: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outline: Option<String>,
+        orientation: TextOrientation,
+        pinned: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+    },
+    Connector {
+        index: usize,
+        bounds: BoundsData,
+        from: usize, // Text element indices
+        to: usize,
+        arrow: bool,
+    },
+}
+
+// "#rrggbb", ignoring opacity
+fn color_hex(color: egui::Color32) -> String {
+    let [r, g, b] = color_to_rgb(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[derive(PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+enum Tool {
+    #[default]
+    Draw,
+    Line,
+    Arrow,
+    Curve,
+    Text,
+    Select,
+    Erase,
+}
+
+impl Tool {
+    const ALL: [Tool; 7] = [Tool::Draw, Tool::Line, Tool::Arrow, Tool::Curve, Tool::Text, Tool::Select, Tool::Erase];
+    
+    fn label(&self) -> &'static str {
+        match self {
+            Tool::Draw => "✏️ Draw",
+            Tool::Line => "📏 Line",
+            Tool::Arrow => "➡ Arrow",
+            Tool::Curve => "〰 Curve",
+            Tool::Text => "📝 Text",
+            Tool::Select => "🔍 Select",
+            Tool::Erase => "🧽 Erase",
+        }
+    }
+    
+    fn is_shape(&self) -> bool {
+        matches!(self, Tool::Line | Tool::Arrow)
+    }
+    
+    fn instructions(&self) -> &'static str {
+        match self {
+            Tool::Draw => "Click and drag to draw!",
+            Tool::Line => "Drag to draw a straight line!\nHold Shift to snap the angle.",
+            Tool::Arrow => "Drag to draw an arrow!\nHold Shift to snap the angle.",
+            Tool::Curve => "Click to place points, or drag to pull out a smooth curve!\nDouble-click or press Enter to finish, Esc to cancel.",
+            Tool::Text => "Click to place text!",
+            Tool::Select => "Drag to select text and strokes, or click one to select it (Shift+click adds to the selection), then drag the selection to move it!\nUse the Copy button to copy selected text.",
+            Tool::Erase => "Click or drag over strokes to erase them, or drag a rectangle with the area eraser!\nOne drag is undone in a single step.",
+        }
+    }
+}
+
+// Length of the slide/fade when stepping between pages, in seconds
+const PAGE_TRANSITION_DURATION: f32 = 0.25;
+// How far across the canvas the incoming page starts
+const PAGE_TRANSITION_DISTANCE: f32 = 0.3;
+
+#[derive(Clone, Copy)]
+struct PageTransition {
+    started: std::time::Instant,
+    direction: f32, // 1.0 when moving forward, so the new page enters from the right
+}
+
+// How long a notification stays on screen, in seconds
+const NOTIFICATION_DURATION: f32 = 4.0;
+
+struct Notification {
+    message: String,
+    is_error: bool,
+    created: std::time::Instant,
+}
+
+// Oldest log lines are dropped past this many
+const MAX_LOG_ENTRIES: usize = 500;
+
+// Eraser reach around the pointer, in screen pixels, unless set otherwise
+const DEFAULT_ERASER_RADIUS: f32 = 8.0;
+
+#[derive(PartialEq, Clone, Copy)]
+enum EraserMode {
+    Stroke,  // Delete whole strokes on touch
+    Segment, // Cut away only the touched part, splitting strokes
+    Area,    // Delete everything inside a dragged rectangle
+}
+
+impl EraserMode {
+    const ALL: [EraserMode; 3] = [EraserMode::Stroke, EraserMode::Segment, EraserMode::Area];
+    
+    fn label(&self) -> &'static str {
+        match self {
+            EraserMode::Stroke => "Stroke eraser",
+            EraserMode::Segment => "Segment eraser",
+            EraserMode::Area => "Area eraser",
+        }
+    }
+}
+
+// Keyboard shortcuts, shared by the input handling and the help overlay
+const SHORTCUT_ADD_TEXT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::Enter);
+const SHORTCUT_CANCEL_TEXT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::Escape);
+const SHORTCUT_TOGGLE_HELP: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F1);
+const SHORTCUT_UNDO: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+const SHORTCUT_REDO: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT), egui::Key::Z);
+const SHORTCUT_REDO_ALT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y);
+const SHORTCUT_COPY_IMAGE: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT), egui::Key::C);
+const SHORTCUT_PREVIOUS_PAGE: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::PageUp);
+const SHORTCUT_NEXT_PAGE: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::PageDown);
+const SHORTCUT_ADD_PAGE: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::N);
+const SHORTCUT_GO_TO_PAGE: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::G);
+const SHORTCUT_PRESENT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F5);
+
+const SHORTCUTS: &[(egui::KeyboardShortcut, &str)] = &[
+    (SHORTCUT_ADD_TEXT, "Add the text being typed"),
+    (SHORTCUT_CANCEL_TEXT, "Cancel text input"),
+    (SHORTCUT_TOGGLE_HELP, "Show or hide this help"),
+    (SHORTCUT_UNDO, "Undo"),
+    (SHORTCUT_REDO, "Redo"),
+    (SHORTCUT_REDO_ALT, "Redo"),
+    (SHORTCUT_COPY_IMAGE, "Copy the page (or selection) as an image"),
+    (SHORTCUT_PREVIOUS_PAGE, "Previous page (notebooks)"),
+    (SHORTCUT_NEXT_PAGE, "Next page (notebooks)"),
+    (SHORTCUT_ADD_PAGE, "Add a page (notebooks)"),
+    (SHORTCUT_GO_TO_PAGE, "Go to a page by number or name (notebooks)"),
+    (SHORTCUT_PRESENT, "Start or stop presenting; Space/→ and ← step through builds"),
+];
+
+const MOUSE_CONTROLS: &[(&str, &str)] = &[
+    ("Middle-drag / Scroll", "Pan the page"),
+    ("Ctrl+Scroll / Pinch", "Zoom around the cursor"),
+    ("Shift+Drag (Line, Arrow)", "Snap the angle to the chosen increment"),
+    ("Double-click text (Text)", "Edit it with the caret at the clicked character"),
+    ("Drop .scribble file", "Open the file"),
+    ("Drop .csv / .tsv file", "Lay it out as a grid of text"),
+];
+
+// Limits for scaling the width of existing strokes
+const MIN_STROKE_WIDTH: f32 = 0.5;
+const MAX_STROKE_WIDTH: f32 = 50.0;
+const STROKE_WIDTH_STEP: f32 = 1.25;
+
+// Angle increments offered for Shift-snapping lines and arrows, in degrees
+const ANGLE_SNAP_OPTIONS: [f32; 4] = [15.0, 30.0, 45.0, 90.0];
+
+// Arrowhead size relative to the stroke width, with a minimum in canvas units
+const ARROW_HEAD_WIDTH_RATIO: f32 = 4.0;
+const ARROW_HEAD_MIN_LENGTH: f32 = 12.0;
+
+// Points of a line or arrow from start to end. Arrows are one polyline that runs up the
+// shaft, out to one barb and back, then out to the other.
+fn shape_points(tool: &Tool, start: egui::Pos2, end: egui::Pos2, width: f32) -> Vec<egui::Pos2> {
+    if *tool != Tool::Arrow || start == end {
+        return vec![start, end];
+    }
+    
+    let direction = (end - start).normalized();
+    let head_length = (width * ARROW_HEAD_WIDTH_RATIO).max(ARROW_HEAD_MIN_LENGTH).min(start.distance(end));
+    let back = -direction * head_length;
+    let barb = |angle: f32| end + egui::Vec2::angled(back.angle() + angle) * head_length;
+    let (left, right) = (barb(0.5), barb(-0.5));
+    vec![start, end, left, end, right]
+}
+
+// Screen distance from a text element within which a line or arrow end attaches to it
+const TEXT_ATTACH_DISTANCE: f32 = 16.0;
+
+// Where a line from `from` aimed at the center of `rect` meets the rect grown by `gap`, or `from`
+// itself when it is already inside
+fn attach_to_rect(from: egui::Pos2, rect: egui::Rect, gap: f32) -> egui::Pos2 {
+    let rect = rect.expand(gap);
+    if rect.contains(from) {
+        return from;
+    }
+    let delta = rect.center() - from;
+    let tx = if delta.x == 0.0 { f32::INFINITY } else { rect.width() / 2.0 / delta.x.abs() };
+    let ty = if delta.y == 0.0 { f32::INFINITY } else { rect.height() / 2.0 / delta.y.abs() };
+    rect.center() - delta * tx.min(ty)
+}
+
+// An anchor of the curve being placed; the handle is the outgoing tangent and is mirrored on the way in
+#[derive(Clone, Copy)]
+struct CurveAnchor {
+    point: egui::Pos2,
+    handle: egui::Vec2,
+}
+
+// Canvas distance between the points a curve is flattened into, with per-segment limits
+const CURVE_POINT_SPACING: f32 = 4.0;
+const CURVE_MIN_STEPS: usize = 4;
+const CURVE_MAX_STEPS: usize = 200;
+
+// Flatten the cubic Béziers joining consecutive anchors into a polyline
+fn curve_points(anchors: &[CurveAnchor]) -> Vec<egui::Pos2> {
+    let mut points: Vec<egui::Pos2> = anchors.first().map(|anchor| anchor.point).into_iter().collect();
+    for pair in anchors.windows(2) {
+        let p0 = pair[0].point;
+        let p1 = pair[0].point + pair[0].handle;
+        let p2 = pair[1].point - pair[1].handle;
+        let p3 = pair[1].point;
+        let hull_length = p0.distance(p1) + p1.distance(p2) + p2.distance(p3);
+        let steps = ((hull_length / CURVE_POINT_SPACING).ceil() as usize).clamp(CURVE_MIN_STEPS, CURVE_MAX_STEPS);
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let u = 1.0 - t;
+            let point = p0.to_vec2() * (u * u * u)
+                + p1.to_vec2() * (3.0 * u * u * t)
+                + p2.to_vec2() * (3.0 * u * t * t)
+                + p3.to_vec2() * (t * t * t);
+            points.push(point.to_pos2());
+        }
+    }
+    points
+}
+
+// Drop shadow offset, down and to the right, relative to the font size
+const TEXT_SHADOW_OFFSET_RATIO: f32 = 0.08;
+
+// Outline stroke width for text converted to paths, relative to its font size
+const TEXT_PATH_WIDTH_RATIO: f32 = 0.05;
+
+// Points each glyph curve is flattened into
+const GLYPH_CURVE_STEPS: usize = 6;
+
+// Closed polylines in canvas space tracing every glyph of the text, laid out like the canvas
+// draws it. Glyphs come from the default proportional fonts, falling back down the list for
+// characters the first one lacks.
+fn text_outline_contours(text_element: &TextElement) -> Vec<Vec<egui::Pos2>> {
+    let definitions = egui::FontDefinitions::default();
+    let fonts: Vec<ab_glyph::FontRef> = definitions.families.get(&egui::FontFamily::Proportional)
+        .into_iter()
+        .flatten()
+        .filter_map(|name| definitions.font_data.get(name))
+        .filter_map(|data| ab_glyph::FontRef::try_from_slice_and_index(&data.font, data.index).ok())
+        .collect();
+    let font_size = text_element.font_size;
+    let glyph_for = |ch: char| {
+        fonts.iter().find(|font| font.glyph_id(ch).0 != 0).map(|font| (font, font.glyph_id(ch)))
+    };
+    
+    // Each placed glyph as its font, id, left edge and top edge relative to the element
+    let mut placed = Vec::new();
+    if text_element.orientation.is_vertical() {
+        for (ch, [x, y]) in scribble_core::vertical_char_offsets(&text_element.text, font_size, text_element.orientation) {
+            if let Some((font, id)) = glyph_for(ch) {
+                let scale = font_size / font.height_unscaled();
+                placed.push((font, id, x - font.h_advance_unscaled(id) * scale / 2.0, y));
+            }
+        }
+    } else {
+        for (line_idx, line) in text_element.text.lines().enumerate() {
+            let mut x = 0.0;
+            let mut previous: Option<(&ab_glyph::FontRef, ab_glyph::GlyphId)> = None;
+            for ch in line.chars() {
+                let Some((font, id)) = glyph_for(ch) else {
+                    continue;
+                };
+                let scale = font_size / font.height_unscaled();
+                if let Some((previous_font, previous_id)) = previous
+                    && std::ptr::eq(previous_font, font)
+                {
+                    x += font.kern_unscaled(previous_id, id) * scale;
+                }
+                placed.push((font, id, x, line_idx as f32 * font_size * 1.2));
+                x += font.h_advance_unscaled(id) * scale;
+                previous = Some((font, id));
+            }
+        }
+    }
+    
+    let mut contours: Vec<Vec<egui::Pos2>> = Vec::new();
+    for (font, id, left, top) in placed {
+        let Some(outline) = font.outline(id) else {
+            continue;
+        };
+        // Font units have y pointing up from the baseline, which sits an ascent below the top
+        let scale = font_size / font.height_unscaled();
+        let baseline = text_element.position + egui::vec2(left, top + font.ascent_unscaled() * scale);
+        let to_canvas = |point: ab_glyph::Point| baseline + egui::vec2(point.x, -point.y) * scale;
+        let bezier = |controls: &[ab_glyph::Point]| -> Vec<ab_glyph::Point> {
+            (1..=GLYPH_CURVE_STEPS).map(|step| {
+                // De Casteljau: repeatedly interpolate between neighbouring control points
+                let t = step as f32 / GLYPH_CURVE_STEPS as f32;
+                let mut level = controls.to_vec();
+                while level.len() > 1 {
+                    level = level.windows(2)
+                        .map(|pair| ab_glyph::point(pair[0].x + (pair[1].x - pair[0].x) * t, pair[0].y + (pair[1].y - pair[0].y) * t))
+                        .collect();
+                }
+                level[0]
+            }).collect()
+        };
+        let mut contour: Vec<egui::Pos2> = Vec::new();
+        for curve in &outline.curves {
+            let (start, points) = match *curve {
+                ab_glyph::OutlineCurve::Line(p0, p1) => (p0, vec![p1]),
+                ab_glyph::OutlineCurve::Quad(p0, p1, p2) => (p0, bezier(&[p0, p1, p2])),
+                ab_glyph::OutlineCurve::Cubic(p0, p1, p2, p3) => (p0, bezier(&[p0, p1, p2, p3])),
+            };
+            // A curve that doesn't continue from the last one starts the glyph's next contour
+            let start = to_canvas(start);
+            if contour.last() != Some(&start) {
+                if contour.len() > 1 {
+                    contours.push(std::mem::take(&mut contour));
+                }
+                contour = vec![start];
+            }
+            contour.extend(points.into_iter().map(to_canvas));
+        }
+        if contour.len() > 1 {
+            contours.push(contour);
+        }
+    }
+    contours
+}
+
+// Picker for the direction text runs in, returning whether it changed
+fn orientation_combo(ui: &mut egui::Ui, id: &str, orientation: &mut TextOrientation) -> bool {
+    let label = |orientation: TextOrientation| match orientation {
+        TextOrientation::Horizontal => "Horizontal",
+        TextOrientation::VerticalRl => "Vertical ←",
+        TextOrientation::VerticalLr => "Vertical →",
+    };
+    let previous = *orientation;
+    egui::ComboBox::from_id_source(id)
+        .selected_text(label(*orientation))
+        .show_ui(ui, |ui| {
+            for option in [TextOrientation::Horizontal, TextOrientation::VerticalRl, TextOrientation::VerticalLr] {
+                ui.selectable_value(orientation, option, label(option));
+            }
+        })
+        .response
+        .on_hover_text("Vertical text stacks characters downward, with further lines as columns to the left (←) or right (→)");
+    *orientation != previous
+}
+
+// Thick solid strokes as a filled triangle strip between their outline edges, matching exports
+fn thick_stroke_mesh(points: &[egui::Pos2], width: f32, color: egui::Color32) -> egui::Shape {
+    let centerline: Vec<[f32; 2]> = points.iter().map(|p| [p.x, p.y]).collect();
+    let mut mesh = egui::Mesh::default();
+    for (left, right) in export::stroke_outline_sides(&centerline, width) {
+        mesh.colored_vertex(egui::Pos2::new(left[0], left[1]), color);
+        mesh.colored_vertex(egui::Pos2::new(right[0], right[1]), color);
+    }
+    for i in 0..points.len().saturating_sub(1) as u32 {
+        let (l0, r0, l1, r1) = (2 * i, 2 * i + 1, 2 * i + 2, 2 * i + 3);
+        mesh.add_triangle(l0, r0, l1);
+        mesh.add_triangle(r0, r1, l1);
+    }
+    egui::Shape::mesh(mesh)
+}
+
+// Preferences kept between launches, separate from any notebook
+#[derive(Serialize, Deserialize)]
+struct AppSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_text_font_size: Option<f32>,
+    #[serde(default = "default_max_export_dimension")]
+    max_export_dimension: u32,
+    #[serde(default = "default_table_cell_spacing")]
+    table_cell_spacing: f32, // Gap between imported table columns and rows
+    #[serde(default = "default_eraser_radius")]
+    eraser_radius: f32, // Screen pixels
+    #[serde(default = "default_show_eraser_cursor")]
+    show_eraser_cursor: bool, // Outline the eraser's reach around the pointer
+    #[serde(default = "default_angle_snap_degrees")]
+    angle_snap_degrees: f32,
+    #[serde(default)]
+    auto_select_after_create: bool, // Hand each new shape, curve or text to the Select tool
+    #[serde(default = "default_attach_shapes_to_text")]
+    attach_shapes_to_text: bool, // Aim line and arrow ends dropped near text at its edge
+    #[serde(default = "default_export_margin")]
+    export_margin: f32,
+    #[serde(default)]
+    export_wrap_width: Option<f32>, // None widens exports to fit long text lines instead of wrapping
+    #[serde(default)]
+    export_curve_spacing: Option<f32>, // None exports strokes through their stored points only
+    #[serde(default)]
+    png_text_style: export::PngTextStyle,
+    #[serde(default = "default_svg_curves")]
+    svg_curves: bool, // Fit SVG stroke paths with Bézier curves instead of a line per captured point
+    #[serde(default)]
+    watermark: bool, // Stamp the watermark text into PNG, SVG and HTML exports
+    #[serde(default = "default_watermark_text")]
+    watermark_text: String,
+    #[serde(default)]
+    watermark_position: export::WatermarkPosition,
+    #[serde(default = "default_confirm_on_exit")]
+    confirm_on_exit: bool,
+    #[serde(default)]
+    default_tool: Tool, // Tool picked when the app starts
+    #[serde(default = "default_ui_scale")]
+    ui_scale: f32, // Size of the whole interface relative to the display's scale factor; the canvas zoom is separate
+    #[serde(default)]
+    selectable_text: bool, // Render text as egui labels so the OS can select and copy it
+    #[serde(default)]
+    translucent_stroke_preview: bool, // Fade the stroke being drawn so it stands apart from finished ones
+    #[serde(default)]
+    trim_extra_pages: bool, // Drop pages past the limit when loading instead of keeping them all
+    #[serde(default = "default_text_measure_cache_size")]
+    text_measure_cache_size: usize, // Most text measurements kept between frames
+    #[serde(default = "default_paste_at_cursor")]
+    paste_at_cursor: bool,
+    #[serde(default = "default_show_empty_hints")]
+    show_empty_hints: bool,
+    #[serde(default = "default_show_content_extent")]
+    show_content_extent: bool, // Outline where the page's content lives and mark edges it continues past
+    #[serde(default)]
+    keep_within_page: bool, // Clamp new and dragged content to the page size
+    #[serde(default = "default_smoothing_window")]
+    smoothing_window: usize, // Recent pointer positions averaged into each captured point, 1 for raw input
+    #[serde(default)]
+    export_visible_only: bool, // Leave elements the tag filter excludes out of exports
+    #[serde(default = "default_uniform_page_exports")]
+    uniform_page_exports: bool, // Give every image of an all-pages export the same size
+    #[serde(default)]
+    color_presets: Vec<[u8; 3]>, // Saved pen colors, in the order shown in the toolbar
+    #[serde(default)]
+    stamps: Vec<scribble_core::Stamp>, // Saved element groups, in the order shown in the Stamps menu
+    #[serde(default = "default_min_stroke_distance")]
+    min_stroke_distance: f32, // Screen pixels a freehand stroke must stray from its start to be kept
+    #[serde(default = "default_max_capture_gap")]
+    max_capture_gap: f32, // Screen pixels between captured points before the gap is filled in, 0 to never fill
+    #[serde(default)]
+    snap_text_to_grid: bool,
+    #[serde(default = "default_text_grid_spacing")]
+    text_grid_spacing: f32, // Canvas units between the points new text snaps to
+    #[serde(default = "default_nib_angle_degrees")]
+    nib_angle_degrees: f32, // Angle of the calligraphy pen's edge from horizontal
+}
+
+fn default_text_measure_cache_size() -> usize {
+    1024
+}
+
+fn default_paste_at_cursor() -> bool {
+    true
+}
+
+fn default_show_empty_hints() -> bool {
+    true
+}
+
+fn default_show_content_extent() -> bool {
+    true
+}
+
+fn default_smoothing_window() -> usize {
+    1
+}
+
+fn default_uniform_page_exports() -> bool {
+    true
+}
+
+fn default_min_stroke_distance() -> f32 {
+    3.0
+}
+
+fn default_max_capture_gap() -> f32 {
+    8.0
+}
+
+fn default_text_grid_spacing() -> f32 {
+    20.0
+}
+
+fn default_nib_angle_degrees() -> f32 {
+    30.0
+}
+
+fn default_confirm_on_exit() -> bool {
+    true
+}
+
+fn default_export_margin() -> f32 {
+    export::DEFAULT_EXPORT_MARGIN
+}
+
+fn default_attach_shapes_to_text() -> bool {
+    true
+}
+
+fn default_ui_scale() -> f32 {
+    1.0
+}
+
+fn default_watermark_text() -> String {
+    "{date} · {page}".to_string()
+}
+
+fn default_svg_curves() -> bool {
+    true
+}
+
+fn default_angle_snap_degrees() -> f32 {
+    15.0
+}
+
+fn default_table_cell_spacing() -> f32 {
+    DEFAULT_TABLE_CELL_SPACING
+}
+
+fn default_eraser_radius() -> f32 {
+    DEFAULT_ERASER_RADIUS
+}
+
+fn default_show_eraser_cursor() -> bool {
+    true
+}
+
+fn default_max_export_dimension() -> u32 {
+    export::DEFAULT_MAX_EXPORT_DIMENSION
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            last_text_font_size: None,
+            max_export_dimension: export::DEFAULT_MAX_EXPORT_DIMENSION,
+            table_cell_spacing: default_table_cell_spacing(),
+            eraser_radius: default_eraser_radius(),
+            show_eraser_cursor: default_show_eraser_cursor(),
+            angle_snap_degrees: default_angle_snap_degrees(),
+            auto_select_after_create: false,
+            attach_shapes_to_text: default_attach_shapes_to_text(),
+            export_margin: default_export_margin(),
+            export_wrap_width: None,
+            export_curve_spacing: None,
+            png_text_style: export::PngTextStyle::default(),
+            svg_curves: default_svg_curves(),
+            watermark: false,
+            watermark_text: default_watermark_text(),
+            watermark_position: export::WatermarkPosition::default(),
+            confirm_on_exit: default_confirm_on_exit(),
+            default_tool: Tool::default(),
+            ui_scale: default_ui_scale(),
+            selectable_text: false,
+            translucent_stroke_preview: false,
+            trim_extra_pages: false,
+            text_measure_cache_size: default_text_measure_cache_size(),
+            paste_at_cursor: default_paste_at_cursor(),
+            show_empty_hints: default_show_empty_hints(),
+            show_content_extent: default_show_content_extent(),
+            keep_within_page: false,
+            smoothing_window: default_smoothing_window(),
+            export_visible_only: false,
+            uniform_page_exports: default_uniform_page_exports(),
+            color_presets: Vec::new(),
+            stamps: Vec::new(),
+            min_stroke_distance: default_min_stroke_distance(),
+            max_capture_gap: default_max_capture_gap(),
+            snap_text_to_grid: false,
+            text_grid_spacing: default_text_grid_spacing(),
+            nib_angle_degrees: default_nib_angle_degrees(),
+        }
+    }
+}
+
+impl AppSettings {
+    fn path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("scribble").join("settings.json"))
+    }
+    
+    // Missing or unreadable settings fall back to defaults
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+    
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = Self::path().ok_or("No configuration directory found")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// How many matches the search preview lists and how many characters surround each
+const SEARCH_PREVIEW_LIMIT: usize = 5;
+const SEARCH_PREVIEW_CONTEXT: usize = 15;
+
+struct SearchPreviewEntry {
+    page_index: usize,
+    element_index: usize,
+    snippet: String,
+}
+
+// Vertical gap between text blocks arranged by the tidy command
+const TIDY_TEXT_SPACING: f32 = 12.0;
+
+// Oldest undo entries are dropped past this many
+const MAX_UNDO_HISTORY: usize = 100;
+
+// Extra fading of the in-progress stroke when the translucent preview is turned on
+const STROKE_PREVIEW_OPACITY: f32 = 0.6;
+
+// Fading of the stamp that follows the pointer while placing
+const STAMP_PREVIEW_OPACITY: f32 = 0.4;
+
+// Most pages a notebook can be created or grown to
+const MAX_PAGES: usize = 100;
+
+// Longest text file imported in one go, and where it lands relative to the view's top-left (screen units)
+const MAX_IMPORTED_TEXT_CHARS: usize = 20_000;
+const IMPORTED_TEXT_MARGIN: f32 = 20.0;
+
+// Most cells an imported table may have, each becoming its own text element
+const MAX_TABLE_CELLS: usize = 2_000;
+const DEFAULT_TABLE_CELL_SPACING: f32 = 16.0;
+
+// Rows of cells from comma- or tab-separated text; tabs win when the first line has one. Quoted
+// cells may hold the separator, line breaks and doubled quotes. Blank lines are skipped.
+fn parse_table(contents: &str) -> Vec<Vec<String>> {
+    let separator = if contents.lines().next().is_some_and(|line| line.contains('\t')) { '\t' } else { ',' };
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = contents.chars().filter(|&c| c != '\r').peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c != '"' {
+                cell.push(c);
+            } else if chars.peek() == Some(&'"') {
+                chars.next();
+                cell.push('"');
+            } else {
+                quoted = false;
+            }
+        } else if c == '"' && cell.trim().is_empty() {
+            cell.clear();
+            quoted = true;
+        } else if c == separator {
+            row.push(std::mem::take(&mut cell));
+        } else if c == '\n' {
+            row.push(std::mem::take(&mut cell));
+            rows.push(std::mem::take(&mut row));
+        } else {
+            cell.push(c);
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+    rows
+}
+
+// Opacity of elements the tag filter dims rather than hides
+const TAG_FILTER_DIM_OPACITY: f32 = 0.2;
+
+// Opacity of everything outside the selection while focus mode is on
+const FOCUS_DIM_OPACITY: f32 = 0.25;
+
+// Pulsing elements fade down to this opacity and back once per period, in seconds
+const PULSE_MIN_OPACITY: f32 = 0.25;
+const PULSE_PERIOD: f64 = 1.2;
+
+// Scrollbar thickness in screen units, and the canvas space kept scrollable around the content
+const SCROLLBAR_THICKNESS: f32 = 10.0;
+const SCROLL_REGION_MARGIN: f32 = 200.0;
+
+// Screen size of the corner handles on a selection, and the smallest font size they resize text to
+const TEXT_HANDLE_SIZE: f32 = 8.0;
+const MIN_TEXT_FONT_SIZE: f32 = 6.0;
+
+// Smallest factor a selection's handles scale it by along either axis, so it can't collapse or flip
+const MIN_SELECTION_SCALE: f32 = 0.05;
+
+// Screen distance within which a dragged selection's edges or center snap to a smart guide
+const SMART_GUIDE_SNAP_DISTANCE: f32 = 6.0;
+
+// Screen width of the rulers along the canvas's top and left edges that custom guides are dragged out of,
+// how close the pointer must be to grab a placed guide, and how close committed positions snap to one
+const GUIDE_RULER_WIDTH: f32 = 12.0;
+const GUIDE_GRAB_DISTANCE: f32 = 4.0;
+const GUIDE_SNAP_DISTANCE: f32 = 8.0;
+
+// Closest grid point to a canvas position, for grids with points every `spacing` units from the origin
+fn snap_to_grid(pos: egui::Pos2, spacing: f32) -> egui::Pos2 {
+    if spacing <= 0.0 {
+        return pos;
+    }
+    egui::Pos2::new((pos.x / spacing).round() * spacing, (pos.y / spacing).round() * spacing)
+}
+
+// Text grids finer than this on screen aren't drawn, since the dots would blur into a fill
+const TEXT_GRID_MIN_SCREEN_SPACING: f32 = 8.0;
+
+// Screen gap between the content and its extent outline
+const CONTENT_EXTENT_PADDING: f32 = 8.0;
+
+// Point spacing offered when curve smoothing is first turned on for exports
+const DEFAULT_EXPORT_CURVE_SPACING: f32 = 4.0;
+
+// Characters of a text element shown in the elements panel before it is cut off
+const ELEMENT_LABEL_CHARS: usize = 20;
+
+// Drag-and-drop payload for reordering color presets: the dragged preset's position
+struct PresetSlot(usize);
+
+// A row in the elements panel, and the drag-and-drop payload when reordering rows
+#[derive(Clone, Copy, PartialEq, Hash)]
+enum ElementRef {
+    Stroke(usize),
+    Text(usize),
+}
+
+// The corner handle of a screen rectangle within reach of a screen position, if any
+fn corner_handle_at(rect: egui::Rect, screen_pos: egui::Pos2) -> Option<egui::Pos2> {
+    [rect.left_top(), rect.right_top(), rect.left_bottom(), rect.right_bottom()].into_iter()
+        .find(|corner| corner.distance(screen_pos) <= TEXT_HANDLE_SIZE)
+}
+
+// A corner-handle drag scaling a whole selection in screen space from the opposite corner. Each
+// step is worked out from the elements as they were when the drag began.
+struct SelectionResize {
+    anchor: egui::Pos2,
+    start_diagonal: egui::Vec2,
+    strokes: Vec<(usize, Vec<egui::Pos2>)>, // Original points
+    texts: Vec<(usize, egui::Pos2, f32, Option<egui::Vec2>)>, // Original position, font size and shadow offset
+}
+
+// A corner-handle drag in progress, measured in screen space from the opposite corner
+struct TextResize {
+    index: usize,
+    anchor: egui::Pos2,
+    start_position: egui::Pos2,
+    start_diagonal: egui::Vec2,
+    start_font_size: f32,
+}
+
+// Sizes of laid-out text keyed by text and font size, so measuring the same text every frame
+// doesn't lay it out again. The least recently used entries go first once the limit is passed.
+#[derive(Default)]
+struct TextMeasureCache {
+    entries: HashMap<(String, u32), (egui::Vec2, u64)>,
+    clock: u64,
+    pixels_per_point: f32,
+}
+
+impl TextMeasureCache {
+    fn measure(&mut self, ctx: &egui::Context, text: &str, font_size: f32, capacity: usize) -> egui::Vec2 {
+        // Glyph metrics are rounded to physical pixels, so a scale change makes every entry stale
+        let pixels_per_point = ctx.pixels_per_point();
+        if pixels_per_point != self.pixels_per_point {
+            self.entries.clear();
+            self.pixels_per_point = pixels_per_point;
+        }
+        
+        self.clock += 1;
+        let key = (text.to_string(), font_size.to_bits());
+        if let Some((size, last_used)) = self.entries.get_mut(&key) {
+            *last_used = self.clock;
+            return *size;
+        }
+        
+        let size = ctx.fonts(|fonts| fonts.layout_no_wrap(
+            text.to_string(),
+            egui::FontId::proportional(font_size),
+            egui::Color32::BLACK,
+        )).size();
+        self.entries.insert(key, (size, self.clock));
+        
+        // Evict down to three quarters of the limit at once so a full cache isn't scanned on every miss
+        let capacity = capacity.max(1);
+        if self.entries.len() > capacity {
+            let mut ages: Vec<u64> = self.entries.values().map(|&(_, last_used)| last_used).collect();
+            let keep = (capacity * 3 / 4).max(1);
+            let cutoff_idx = ages.len() - keep;
+            let (_, &mut cutoff, _) = ages.select_nth_unstable(cutoff_idx);
+            self.entries.retain(|_, &mut (_, last_used)| last_used >= cutoff);
+        }
+        size
+    }
+}
+
+// Snapshot of the notebook taken before an undoable change
+struct UndoEntry {
+    label: String,
+    pages: Vec<Page>,
+    current_page_index: usize,
+    canvas_size: egui::Vec2,
+}
+
+struct ScribbleApp {
+    // Multi-page notebook support
+    pages: Vec<Page>,
+    current_page_index: usize,
+    is_notebook_mode: bool,
+    show_create_notebook_dialog: bool,
+    new_notebook_pages_input: String,
+    
+    current_stroke: Vec<egui::Pos2>,
+    curve_anchors: Vec<CurveAnchor>, // Points placed so far with the curve tool
+    current_pressures: Vec<Option<f32>>, // Stylus force sampled with each point of current_stroke, if any
+    recent_input: VecDeque<egui::Pos2>, // Raw pointer positions behind the capture smoothing window
+    is_drawing: bool,
+    stroke_color: egui::Color32,
+    stroke_width: f32,
+    pen_mode: PenMode,
+    smoothing_strength: f32, // Applied to new strokes when they are committed
+    stroke_opacity: f32,
+    endpoint_snapping: bool,
+    trace_mode: bool, // Pull the pen onto the nearest existing stroke, for inking over a sketch
+    use_pressure: bool, // Vary new strokes' width with stylus force where the hardware reports it
+    smart_guides: bool, // Snap dragged selections into line with other elements
+    guide_snap_residual: egui::Vec2, // How far snapping has held the selection back from the pointer
+    active_guides: [Option<f32>; 2], // Canvas x of the vertical guide and y of the horizontal guide being shown
+    guide_drag: Option<(bool, usize)>, // Custom guide being dragged: whether it's vertical, and its index on the page
+    shape_start: Option<egui::Pos2>, // Anchor of the line or arrow being dragged
+    current_tool: Tool,
+    text_input: String,
+    text_font_size: f32,
+    text_outline_enabled: bool,
+    text_outline_color: egui::Color32,
+    text_shadow_enabled: bool,
+    text_shadow_color: egui::Color32,
+    pin_new_text: bool,
+    text_orientation: TextOrientation, // Direction new text runs in
+    single_line_text: bool, // Enter commits new text instead of starting a new line
+    active_tag: String, // Tag given to new strokes and text, empty for none
+    tag_filter: Option<String>, // Only elements with this tag are shown normally
+    hide_filtered: bool, // Hide rather than dim elements the filter excludes
+    canvas_origin: egui::Pos2, // Screen position of the canvas area's top-left, where pinned text is anchored
+    canvas_center: egui::Pos2, // Screen position of the canvas area's middle, which the view rotates around
+    canvas_pointer: Option<egui::Pos2>, // Last screen position of the pointer over the canvas, where pastes land
+    notebook_font_size: Option<f32>, // Per-notebook default, saved with the notebook when set
+    notebook_meta: scribble_core::NotebookMeta,
+    canvas_size: egui::Vec2, // Page size, saved with the file; content can be kept inside it
+    show_notebook_info: bool,
+    show_page_list: bool,
+    show_elements_panel: bool,
+    show_results_panel: bool,
+    notebook_results: Vec<SearchPreviewEntry>, // Every match on every page, while the results panel is open
+    show_go_to_page: bool,
+    show_animation_export: bool,
+    animation_fps: u32,
+    animation_seconds: f32,
+    animation_as_frames: bool, // Numbered PNGs in a folder instead of one GIF
+    dirty: bool, // Changes made since the last save or load
+    presenting: bool,
+    build_step: u32, // Elements with a build order up to this step are shown while presenting
+    build_order_input: u32,
+    pulsing: Vec<ElementRef>, // Elements on the current page fading in and out for attention; any edit stops them
+    applied_ui_scale: Option<f32>, // UI scale last handed to egui, None until the first frame
+    placing_stamp: Option<usize>, // Stamp in the settings placed by each canvas click, instead of the tool acting
+    stamp_name_input: String,
+    show_exit_confirm: bool,
+    close_confirmed: bool, // The user chose to close despite unsaved changes
+    go_to_page_input: String,
+    section_input: Option<String>, // Section being typed for the current page, None when the field isn't in use
+    window_title: String, // Last title sent to the window, so it is only updated on change
+    settings: AppSettings,
+    text_measure: RefCell<TextMeasureCache>,
+    active_text_position: Option<egui::Pos2>,
+    editing_text: Option<usize>, // Existing text element open in the floating editor
+    text_input_id: egui::Id,
+    search_query: String,
+    search_results: Vec<usize>,
+    show_search: bool,
+    regex_mode: bool,
+    regex_empty_matches: bool, // Count zero-width regex matches instead of skipping them
+    search_error: Option<String>,
+    search_preview: Vec<SearchPreviewEntry>,
+    search_index: SearchIndex, // Notebook text indexed for literal searches
+    search_index_stale: bool, // Set by anything that may change the notebook's text, so the next search re-syncs the index
+    replace_text: String,
+    show_search_preview: bool,
+    color_search: egui::Color32, // Swatch the color search looks for
+    color_search_tolerance: u8, // Largest per-channel difference still counted as a match
+    text_collisions: Vec<usize>, // Track which text elements have arrow collisions
+    // Text selection fields
+    is_selecting_text: bool,
+    selection_start: Option<egui::Pos2>,
+    selection_end: Option<egui::Pos2>,
+    selected_text_elements: Vec<usize>,
+    selected_strokes: Vec<usize>,
+    erase_pass_recorded: bool, // The current eraser drag already has its undo entry
+    eraser_mode: EraserMode,
+    erase_area: Option<(egui::Pos2, egui::Pos2)>, // Rectangle being dragged by the area eraser
+    clipboard: Option<Clipboard>,
+    // Drag and drop state
+    is_file_hovered: bool,
+    show_help: bool,
+    show_origin: bool,
+    animate_page_transitions: bool,
+    show_hover_highlight: bool,
+    focus_mode: bool, // Dim everything but the selection, while there is one
+    enclosed_selection: bool, // Rubber-band selects only what it fully encloses; Alt flips it for one drag
+    page_transition: Option<PageTransition>,
+    // Undo history
+    undo_stack: Vec<UndoEntry>,
+    redo_stack: Vec<UndoEntry>,
+    undo_coalescing: bool, // Set while the pointer is held so continuous edits form one entry
+    text_resize: Option<TextResize>,
+    selection_resize: Option<SelectionResize>,
+    keep_proportions: bool, // Selection handles scale both axes alike; Shift flips it for one drag
+    notifications: Vec<Notification>,
+    log_entries: Vec<String>,
+    show_log: bool,
+    show_history: bool,
+    // Session stats
+    session_start: std::time::Instant,
+    session_ink: f32, // Total canvas length of strokes drawn since launch
+}
+
+impl Default for ScribbleApp {
+    fn default() -> Self {
+        Self {
+            pages: vec![Page::new("Page 1".to_string())],
+            current_page_index: 0,
+            is_notebook_mode: false,
+            show_create_notebook_dialog: false,
+            new_notebook_pages_input: "5".to_string(),
+            current_stroke: Vec::new(),
+            curve_anchors: Vec::new(),
+            current_pressures: Vec::new(),
+            recent_input: VecDeque::new(),
+            is_drawing: false,
+            stroke_color: egui::Color32::BLACK,
+            stroke_width: 2.0,
+            pen_mode: PenMode::Solid,
+            smoothing_strength: 0.0,
+            stroke_opacity: 1.0,
+            endpoint_snapping: false,
+            trace_mode: false,
+            use_pressure: false,
+            smart_guides: true,
+            guide_snap_residual: egui::Vec2::ZERO,
+            active_guides: [None, None],
+            guide_drag: None,
+            shape_start: None,
+            current_tool: Tool::Draw,
+            text_input: String::new(),
+            text_font_size: 20.0,
+            text_outline_enabled: false,
+            text_outline_color: egui::Color32::WHITE,
+            text_shadow_enabled: false,
+            text_shadow_color: egui::Color32::from_gray(150),
+            pin_new_text: false,
+            text_orientation: TextOrientation::Horizontal,
+            single_line_text: false,
+            active_tag: String::new(),
+            tag_filter: None,
+            hide_filtered: false,
+            canvas_origin: egui::Pos2::ZERO,
+            canvas_center: egui::Pos2::ZERO,
+            canvas_pointer: None,
+            notebook_font_size: None,
+            notebook_meta: scribble_core::NotebookMeta::new(),
+            canvas_size: egui::Vec2::from(scribble_core::DEFAULT_CANVAS_SIZE),
+            show_notebook_info: false,
+            show_page_list: false,
+            show_elements_panel: false,
+            show_results_panel: false,
+            notebook_results: Vec::new(),
+            show_go_to_page: false,
+            show_animation_export: false,
+            animation_fps: 10,
+            animation_seconds: 3.0,
+            animation_as_frames: false,
+            dirty: false,
+            presenting: false,
+            build_step: 0,
+            build_order_input: 1,
+            pulsing: Vec::new(),
+            applied_ui_scale: None,
+            placing_stamp: None,
+            stamp_name_input: String::new(),
+            show_exit_confirm: false,
+            close_confirmed: false,
+            go_to_page_input: String::new(),
+            section_input: None,
+            window_title: WINDOW_TITLE.to_string(),
+            settings: AppSettings::default(),
+            text_measure: RefCell::default(),
+            active_text_position: None,
+            editing_text: None,
+            text_input_id: egui::Id::new("floating_text_input"),
+            search_query: String::new(),
+            search_results: Vec::new(),
+            show_search: false,
+            regex_mode: false,
+            regex_empty_matches: false,
+            search_error: None,
+            search_preview: Vec::new(),
+            search_index: SearchIndex::new(),
+            search_index_stale: true,
+            replace_text: String::new(),
+            show_search_preview: true,
+            color_search: egui::Color32::RED,
+            color_search_tolerance: 40,
+            text_collisions: Vec::new(),
+            is_selecting_text: false,
+            selection_start: None,
+            selection_end: None,
+            selected_text_elements: Vec::new(),
+            selected_strokes: Vec::new(),
+            erase_pass_recorded: false,
+            eraser_mode: EraserMode::Stroke,
+            erase_area: None,
+            clipboard: Clipboard::new().ok(),
+            // Drag and drop state
+            is_file_hovered: false,
+            show_help: false,
+            show_origin: false,
+            animate_page_transitions: true,
+            show_hover_highlight: true,
+            focus_mode: false,
+            enclosed_selection: false,
+            page_transition: None,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            undo_coalescing: false,
+            text_resize: None,
+            selection_resize: None,
+            keep_proportions: false,
+            notifications: Vec::new(),
+            log_entries: Vec::new(),
+            show_log: false,
+            show_history: false,
+            session_start: std::time::Instant::now(),
+            session_ink: 0.0,
+        }
+    }
+}
+
+impl ScribbleApp {
+    // Laid-out size of text at a font size, measured once and then served from the cache
+    fn text_size(&self, ctx: &egui::Context, text: &str, font_size: f32) -> egui::Vec2 {
+        self.text_measure.borrow_mut().measure(ctx, text, font_size, self.settings.text_measure_cache_size)
+    }
+    
+    fn with_settings(settings: AppSettings) -> Self {
+        let mut app = Self::default();
+        if let Some(font_size) = settings.last_text_font_size {
+            app.text_font_size = font_size;
+        }
+        app.current_tool = settings.default_tool;
+        app.settings = settings;
+        app
+    }
+    
+    // Keep egui's scale in step with the UI scale setting. Pixels per point are the display's own
+    // scale factor times the setting, so HiDPI screens stay sharp at 100%; canvas coordinates are in
+    // points and don't change. Zooming the interface with Ctrl+plus/minus updates the setting.
+    fn sync_ui_scale(&mut self, ctx: &egui::Context) {
+        let scale = self.settings.ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+        if self.applied_ui_scale != Some(scale) {
+            // Wait for the pointer to be released, so the scale slider doesn't jump under it
+            if !ctx.input(|i| i.pointer.any_down()) {
+                let native = ctx.native_pixels_per_point().unwrap_or(1.0);
+                ctx.set_pixels_per_point(native * scale);
+                self.applied_ui_scale = Some(scale);
+            }
+        } else if (ctx.zoom_factor() - scale).abs() > 0.001 {
+            self.settings.ui_scale = ctx.zoom_factor().clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+            self.applied_ui_scale = Some(self.settings.ui_scale);
+            if let Err(e) = self.settings.save() {
+                self.log(&format!("Failed to save settings: {}", e), true);
+            }
+        }
+    }
+    
+    // Show a short message in the corner of the window; errors are also logged
+    fn notify(&mut self, message: impl Into<String>, is_error: bool) {
+        let message = message.into();
+        self.log(&message, is_error);
+        self.notifications.push(Notification {
+            message,
+            is_error,
+            created: std::time::Instant::now(),
+        });
+    }
+    
+    // Keep a message in the in-app log (and the terminal), stamped with the time since launch
+    fn log(&mut self, message: &str, is_error: bool) {
+        let elapsed = self.session_start.elapsed().as_secs();
+        let level = if is_error { "ERROR" } else { "INFO" };
+        let entry = format!("[{:02}:{:02}:{:02}] {:5} {}", elapsed / 3600, elapsed % 3600 / 60, elapsed % 60, level, message);
+        if is_error {
+            eprintln!("{}", entry);
+        } else {
+            println!("{}", entry);
+        }
+        
+        self.log_entries.push(entry);
+        if self.log_entries.len() > MAX_LOG_ENTRIES {
+            let excess = self.log_entries.len() - MAX_LOG_ENTRIES;
+            self.log_entries.drain(..excess);
+        }
+    }
+    
+    // Load a file and report the outcome either way
+    fn open_file(&mut self, path: &Path) {
+        match self.load_project_from_path(path) {
+            Ok(()) => self.notify(format!("Opened {}", path.display()), false),
+            Err(e) => self.notify(format!("Could not open {}: {}", path.display(), e), true),
+        }
+    }
+    
+    // Helper methods for current page access
+    fn current_page(&self) -> &Page {
+        &self.pages[self.current_page_index]
+    }
+    
+    fn current_page_mut(&mut self) -> &mut Page {
+        &mut self.pages[self.current_page_index]
+    }
+    
+    fn current_strokes(&self) -> &Vec<Stroke> {
+        &self.current_page().strokes
+    }
+    
+    fn current_strokes_mut(&mut self) -> &mut Vec<Stroke> {
+        &mut self.current_page_mut().strokes
+    }
+    
+    fn current_text_elements(&self) -> &Vec<TextElement> {
+        &self.current_page().text_elements
+    }
+    
+    fn current_text_elements_mut(&mut self) -> &mut Vec<TextElement> {
+        &mut self.current_page_mut().text_elements
+    }
+    
+    // Hue-cycling colors along a stroke, starting from the current stroke color
+    fn gradient_colors(&self, points: &[egui::Pos2]) -> Vec<egui::Color32> {
+        let base = egui::ecolor::HsvaGamma::from(self.stroke_color);
+        let mut distance = 0.0;
+        let mut colors = Vec::with_capacity(points.len());
+        
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                distance += point.distance(points[i - 1]);
+            }
+            let hue = (base.h + distance / GRADIENT_HUE_CYCLE_LENGTH).fract();
+            colors.push(egui::ecolor::HsvaGamma {
+                h: hue,
+                s: base.s.max(0.7), // Keep the cycle visible even when starting from grey or black
+                v: base.v.max(0.8),
+                a: 1.0,
+            }.into());
+        }
+        colors
+    }
+    
+    // Notebook management methods
+    fn create_notebook(&mut self, page_count: usize) {
+        self.push_undo("Create notebook");
+        self.clear_selection();
+        self.pages.clear();
+        for i in 1..=page_count {
+            self.pages.push(Page::new(format!("Page {}", i)));
+        }
+        self.current_page_index = 0;
+        self.is_notebook_mode = true;
+        self.notebook_font_size = None;
+        self.notebook_meta = scribble_core::NotebookMeta::new();
+        self.canvas_size = egui::Vec2::from(scribble_core::DEFAULT_CANVAS_SIZE);
+    }
+    
+    fn add_new_page(&mut self) {
+        if self.pages.len() >= MAX_PAGES {
+            self.notify(format!("A notebook can have at most {} pages", MAX_PAGES), true);
+            return;
+        }
+        self.push_undo("Add page");
+        let page_number = self.pages.len() + 1;
+        let mut page = Page::new(format!("Page {}", page_number));
+        // New pages join the section they are appended to
+        page.section = self.pages.last().and_then(|last| last.section.clone());
+        self.pages.push(page);
+    }
+    
+    // Index of the page a "go to page" entry refers to: a 1-based number, or a page name
+    // (an exact match first, then the first name containing it, ignoring case)
+    fn find_page(&self, query: &str) -> Option<usize> {
+        let query = query.trim();
+        if let Ok(number) = query.parse::<usize>() {
+            return (1..=self.pages.len()).contains(&number).then(|| number - 1);
+        }
+        let query = query.to_lowercase();
+        if query.is_empty() {
+            return None;
+        }
+        self.pages.iter().position(|page| page.name.to_lowercase() == query)
+            .or_else(|| self.pages.iter().position(|page| page.name.to_lowercase().contains(&query)))
+    }
+    
+    // Pages in the list, split into runs of consecutive pages sharing a section
+    fn page_sections(&self) -> Vec<(Option<&str>, std::ops::Range<usize>)> {
+        let mut runs: Vec<(Option<&str>, std::ops::Range<usize>)> = Vec::new();
+        for (idx, page) in self.pages.iter().enumerate() {
+            let section = page.section.as_deref();
+            match runs.last_mut() {
+                Some((last, range)) if *last == section => range.end = idx + 1,
+                _ => runs.push((section, idx..idx + 1)),
+            }
+        }
+        runs
+    }
+    
+    fn show_page_list(&mut self, ui: &mut egui::Ui) {
+        let mut target_page = None;
+        for (run_idx, (section, range)) in self.page_sections().into_iter().enumerate() {
+            let mut page_rows = |ui: &mut egui::Ui| {
+                for idx in range.clone() {
+                    let selected = idx == self.current_page_index;
+                    if ui.selectable_label(selected, format!("{}. {}", idx + 1, self.pages[idx].name)).clicked() {
+                        target_page = Some(idx);
+                    }
+                }
+            };
+            match section {
+                Some(section) => {
+                    egui::CollapsingHeader::new(section)
+                        .id_source(("page_section", run_idx))
+                        .default_open(true)
+                        .show(ui, page_rows);
+                }
+                None => page_rows(ui),
+            }
+        }
+        if let Some(idx) = target_page
+            && idx != self.current_page_index
+        {
+            let direction = if idx > self.current_page_index { 1.0 } else { -1.0 };
+            self.go_to_page(idx);
+            self.start_page_transition(direction);
+        }
+        
+        ui.separator();
+        ui.label("Section of this page:");
+        // Typing goes into a buffer; the section is set once, trimmed, on Enter or clicking away
+        let mut section = self.section_input.take().unwrap_or_else(|| self.current_page().section.clone().unwrap_or_default());
+        let response = ui.text_edit_singleline(&mut section).on_hover_text("Leave empty for no section");
+        if response.lost_focus() {
+            let section = section.trim();
+            let section = (!section.is_empty()).then(|| section.to_string());
+            if section != self.current_page().section {
+                self.push_undo("Edit section");
+                self.current_page_mut().section = section;
+            }
+        } else if response.has_focus() {
+            self.section_input = Some(section);
+        }
+    }
+    
+    fn element_label(&self, element: ElementRef) -> String {
+        match element {
+            ElementRef::Stroke(idx) => {
+                let stroke = &self.current_strokes()[idx];
+                let [r, g, b, _] = stroke.color.to_array();
+                format!("Stroke (#{:02x}{:02x}{:02x}, {}px)", r, g, b, stroke.width)
+            }
+            ElementRef::Text(idx) => {
+                let text = self.current_text_elements()[idx].text.replace('\n', " ");
+                if text.chars().count() > ELEMENT_LABEL_CHARS {
+                    format!("Text: '{}...'", text.chars().take(ELEMENT_LABEL_CHARS).collect::<String>())
+                } else {
+                    format!("Text: '{}'", text)
+                }
+            }
+        }
+    }
+    
+    // Move an element to another's place in the drawing order; text always stays above strokes
+    fn move_element(&mut self, from: ElementRef, to: ElementRef) {
+        match (from, to) {
+            (ElementRef::Stroke(from), ElementRef::Stroke(to)) if from != to => {
+                self.push_undo("Reorder elements");
+                let stroke = self.current_strokes_mut().remove(from);
+                self.current_strokes_mut().insert(to, stroke);
+                self.clear_selection();
+                self.selected_strokes.push(to);
+            }
+            (ElementRef::Text(from), ElementRef::Text(to)) if from != to => {
+                self.push_undo("Reorder elements");
+                let text_element = self.current_text_elements_mut().remove(from);
+                self.current_text_elements_mut().insert(to, text_element);
+                scribble_core::remap_connectors(&mut self.current_page_mut().connectors, |idx| Some(match idx {
+                    idx if idx == from => to,
+                    idx if from < idx && idx <= to => idx - 1,
+                    idx if to <= idx && idx < from => idx + 1,
+                    idx => idx,
+                }));
+                self.clear_selection();
+                self.selected_text_elements.push(to);
+                self.perform_search();
+            }
+            _ => {}
+        }
+    }
+    
+    fn delete_element(&mut self, element: ElementRef) {
+        self.push_undo("Delete element");
+        self.close_text_editor();
+        match element {
+            ElementRef::Stroke(idx) => {
+                self.current_strokes_mut().remove(idx);
+            }
+            ElementRef::Text(idx) => {
+                self.remove_text_elements(&[idx]);
+            }
+        }
+        self.clear_selection();
+        self.perform_search();
+    }
+    
+    // Strokes with any segment, and text whose fill or outline, within `tolerance` of the color on every channel
+    fn find_by_color(&self, color: egui::Color32, tolerance: u8) -> (Vec<usize>, Vec<usize>) {
+        let target = color_to_rgb(color);
+        let close = |other: egui::Color32| {
+            target.iter().zip(color_to_rgb(other)).all(|(&a, b)| a.abs_diff(b) <= tolerance)
+        };
+        let strokes = self.current_strokes().iter().enumerate()
+            .filter(|(_, stroke)| !self.hidden_by_tag_filter(&stroke.tag))
+            .filter(|(_, stroke)| close(stroke.color) || stroke.colors.iter().any(|&segment| close(segment)))
+            .map(|(idx, _)| idx)
+            .collect();
+        // Text is always drawn black, so only its outline can carry another color
+        let texts = self.current_text_elements().iter().enumerate()
+            .filter(|(_, text_element)| !self.hidden_by_tag_filter(&text_element.tag))
+            .filter(|(_, text_element)| close(egui::Color32::BLACK) || text_element.outline.is_some_and(close))
+            .map(|(idx, _)| idx)
+            .collect();
+        (strokes, texts)
+    }
+    
+    // Select everything matching the color search so it is highlighted and ready to act on
+    fn select_by_color(&mut self) {
+        let (strokes, texts) = self.find_by_color(self.color_search, self.color_search_tolerance);
+        let found = strokes.len() + texts.len();
+        self.close_text_editor();
+        self.clear_selection();
+        self.selected_strokes = strokes;
+        self.selected_text_elements = texts;
+        if found > 0 {
+            self.current_tool = Tool::Select;
+            self.cancel_curve();
+        }
+        self.notify(format!("Found {} element(s) in that color", found), false);
+    }
+    
+    // Saved pen colors: click one to use it, drag to reorder, right-click to remove
+    fn show_color_presets(&mut self, ui: &mut egui::Ui) {
+        let mut picked = None;
+        let mut removed = None;
+        let mut moved = None;
+        for (idx, &preset) in self.settings.color_presets.iter().enumerate() {
+            let color = rgb_to_color(preset);
+            let swatch = ui.dnd_drag_source(egui::Id::new(("color_preset", idx)), PresetSlot(idx), |ui| {
+                let button = ui.add(egui::Button::new("").fill(color).min_size(egui::Vec2::splat(16.0)))
+                    .on_hover_text("Click to draw in this color, drag to reorder, right-click to remove");
+                if button.clicked() {
+                    picked = Some(color);
+                }
+                button.context_menu(|ui| {
+                    if ui.button("🗑 Remove preset").clicked() {
+                        removed = Some(idx);
+                        ui.close_menu();
+                    }
+                });
+            }).response;
+            
+            // Mark the slot a dragged preset would take
+            if swatch.dnd_hover_payload::<PresetSlot>().is_some_and(|dragged| dragged.0 != idx) {
+                ui.painter().vline(swatch.rect.left() - 2.0, swatch.rect.y_range(), ui.visuals().selection.stroke);
+            }
+            if let Some(dragged) = swatch.dnd_release_payload::<PresetSlot>() {
+                moved = Some((dragged.0, idx));
+            }
+        }
+        let mut changed = false;
+        if ui.small_button("➕").on_hover_text("Save the current color as a preset").clicked() {
+            let color = color_to_rgb(self.stroke_color);
+            if !self.settings.color_presets.contains(&color) {
+                self.settings.color_presets.push(color);
+                changed = true;
+            }
+        }
+        
+        if let Some(color) = picked {
+            self.stroke_color = color;
+        }
+        if let Some((from, to)) = moved.filter(|(from, to)| from != to) {
+            let preset = self.settings.color_presets.remove(from);
+            self.settings.color_presets.insert(to, preset);
+            changed = true;
+        } else if let Some(idx) = removed {
+            self.settings.color_presets.remove(idx);
+            changed = true;
+        }
+        if changed
+            && let Err(e) = self.settings.save()
+        {
+            self.notify(format!("Failed to save settings: {}", e), true);
+        }
+    }
+    
+    // Everything on the page, topmost first. Rows can be dragged to change the drawing order,
+    // clicked to select (Shift or Ctrl adds to the selection) and deleted.
+    fn show_elements_panel(&mut self, ui: &mut egui::Ui) {
+        let texts = (0..self.current_text_elements().len()).rev().map(ElementRef::Text);
+        let strokes = (0..self.current_strokes().len()).rev().map(ElementRef::Stroke);
+        let rows: Vec<ElementRef> = texts.chain(strokes).collect();
+        if rows.is_empty() {
+            ui.weak("Nothing on this page yet");
+            return;
+        }
+        
+        let mut clicked = None;
+        let mut deleted = None;
+        let mut moved = None;
+        for element in rows {
+            let selected = match element {
+                ElementRef::Stroke(idx) => self.selected_strokes.contains(&idx),
+                ElementRef::Text(idx) => self.selected_text_elements.contains(&idx),
+            };
+            let label = self.element_label(element);
+            let row = ui.horizontal(|ui| {
+                ui.dnd_drag_source(egui::Id::new(("element_row", element)), element, |ui| {
+                    ui.label("☰");
+                }).response.on_hover_text("Drag to change the drawing order");
+                if ui.selectable_label(selected, label).clicked() {
+                    clicked = Some(element);
+                }
+                if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                    deleted = Some(element);
+                }
+            }).response;
+            
+            // Mark where a dragged row of the same kind would land
+            let same_kind = |dragged: &ElementRef| std::mem::discriminant(dragged) == std::mem::discriminant(&element);
+            if row.dnd_hover_payload::<ElementRef>().is_some_and(|dragged| same_kind(&dragged)) {
+                ui.painter().hline(row.rect.x_range(), row.rect.top(), ui.visuals().selection.stroke);
+            }
+            if let Some(dragged) = row.dnd_release_payload::<ElementRef>()
+                && same_kind(&dragged)
+            {
+                moved = Some((*dragged, element));
+            }
+        }
+        
+        if let Some((from, to)) = moved {
+            self.move_element(from, to);
+        } else if let Some(element) = deleted {
+            self.delete_element(element);
+        } else if let Some(element) = clicked {
+            let extend = ui.input(|i| i.modifiers.shift || i.modifiers.command);
+            if !extend {
+                self.clear_selection();
+            }
+            let (list, idx) = match element {
+                ElementRef::Stroke(idx) => (&mut self.selected_strokes, idx),
+                ElementRef::Text(idx) => (&mut self.selected_text_elements, idx),
+            };
+            if let Some(position) = list.iter().position(|&selected| selected == idx) {
+                list.remove(position);
+            } else {
+                list.push(idx);
+            }
+        }
+    }
+    
+    fn next_page(&mut self) {
+        if self.current_page_index < self.pages.len() - 1 {
+            self.go_to_page(self.current_page_index + 1);
+            self.start_page_transition(1.0);
+        }
+    }
+    
+    fn previous_page(&mut self) {
+        if self.current_page_index > 0 {
+            self.go_to_page(self.current_page_index - 1);
+            self.start_page_transition(-1.0);
+        }
+    }
+    
+    fn start_page_transition(&mut self, direction: f32) {
+        if self.animate_page_transitions {
+            self.page_transition = Some(PageTransition {
+                started: std::time::Instant::now(),
+                direction,
+            });
+        }
+    }
+    
+    fn go_to_page(&mut self, page_index: usize) {
+        self.current_page_index = page_index.min(self.pages.len() - 1);
+        self.section_input = None;
+        self.clear_selection();
+        self.pulsing.clear();
+        if self.editing_text.is_some() {
+            self.close_text_editor();
+        }
+        // Search results index into the current page's text elements
+        self.perform_search();
+    }
+    
+    fn perform_search(&mut self) {
+        self.search_results.clear();
+        self.search_preview.clear();
+        self.notebook_results.clear();
+        self.search_error = None;
+        
+        if self.search_query.is_empty() {
+            return;
+        }
+        
+        // Literal queries only look at the elements the index says contain them
+        let candidates = if self.regex_mode {
+            None
+        } else {
+            self.sync_search_index();
+            Some(self.search_index.find(&self.search_query))
+        };
+        self.update_search_preview(candidates.as_deref());
+        if self.show_results_panel {
+            self.update_notebook_results(candidates.as_deref());
+        }
+        
+        if let Some(candidates) = candidates {
+            self.search_results = candidates.into_iter()
+                .filter(|&(page_index, _)| page_index == self.current_page_index)
+                .map(|(_, element_index)| element_index)
+                .collect();
+            return;
+        }
+        
+        let text_elements = self.current_text_elements().clone();
+        
+        match Regex::new(&self.search_query) {
+            Ok(regex) => {
+                for (index, text_element) in text_elements.iter().enumerate() {
+                    if !regex_match_ranges(&regex, &text_element.text, self.regex_empty_matches).is_empty() {
+                        self.search_results.push(index);
+                    }
+                }
+            }
+            Err(e) => {
+                self.search_error = Some(format!("Regex error: {}", e));
+            }
+        }
+    }
+    
+    // Bring the search index up to date after edits; only text that changed is re-indexed
+    fn sync_search_index(&mut self) {
+        if !self.search_index_stale {
+            return;
+        }
+        self.search_index_stale = false;
+        for (page_index, page) in self.pages.iter().enumerate() {
+            self.search_index.sync_page(page_index, page.text_elements.iter().map(|text_element| text_element.text.as_str()));
+        }
+        self.search_index.truncate_pages(self.pages.len());
+    }
+    
+    // Text elements a search should look at, in page order: the given candidates, or every element
+    fn searched_elements(&self, candidates: Option<&[TextLocation]>) -> Vec<(usize, usize, &TextElement)> {
+        match candidates {
+            Some(candidates) => candidates.iter()
+                .filter_map(|&(page_index, element_index)| {
+                    let text_element = self.pages.get(page_index)?.text_elements.get(element_index)?;
+                    Some((page_index, element_index, text_element))
+                })
+                .collect(),
+            None => self.pages.iter().enumerate()
+                .flat_map(|(page_index, page)| {
+                    page.text_elements.iter().enumerate().map(move |(element_index, text_element)| (page_index, element_index, text_element))
+                })
+                .collect(),
+        }
+    }
+    
+    // Collect the first few matches across every page, with a little context around each
+    fn update_search_preview(&mut self, candidates: Option<&[TextLocation]>) {
+        let mut preview = Vec::new();
+        for (page_index, element_index, text_element) in self.searched_elements(candidates) {
+            if preview.len() >= SEARCH_PREVIEW_LIMIT {
+                break;
+            }
+            if let Some(&(start, end)) = self.get_match_positions(&text_element.text).first() {
+                preview.push(SearchPreviewEntry {
+                    page_index,
+                    element_index,
+                    snippet: Self::match_snippet(&text_element.text, start, end),
+                });
+            }
+        }
+        self.search_preview = preview;
+    }
+    
+    // Every match on every page, one entry per match, for the results panel
+    fn update_notebook_results(&mut self, candidates: Option<&[TextLocation]>) {
+        let mut results = Vec::new();
+        for (page_index, element_index, text_element) in self.searched_elements(candidates) {
+            for (start, end) in self.get_match_positions(&text_element.text) {
+                results.push(SearchPreviewEntry {
+                    page_index,
+                    element_index,
+                    snippet: Self::match_snippet(&text_element.text, start, end),
+                });
+            }
+        }
+        self.notebook_results = results;
+    }
+    
+    // Matches across the notebook grouped under their page names; click one to jump to it
+    fn show_results_panel(&mut self, ui: &mut egui::Ui) {
+        if self.search_query.is_empty() {
+            ui.weak("Type in the search field to list matches here");
+            return;
+        }
+        let page_count = {
+            let mut pages: Vec<usize> = self.notebook_results.iter().map(|entry| entry.page_index).collect();
+            pages.dedup();
+            pages.len()
+        };
+        ui.label(format!("{} match(es) on {} page(s)", self.notebook_results.len(), page_count));
+        ui.separator();
+        
+        let mut chosen = None;
+        let mut previous_page = None;
+        for entry in &self.notebook_results {
+            if previous_page != Some(entry.page_index) {
+                previous_page = Some(entry.page_index);
+                let name = self.pages.get(entry.page_index).map_or("", |page| page.name.as_str());
+                ui.strong(format!("{}. {}", entry.page_index + 1, name));
+            }
+            let current = entry.page_index == self.current_page_index && self.selected_text_elements.contains(&entry.element_index);
+            if ui.selectable_label(current, &entry.snippet).clicked() {
+                chosen = Some((entry.page_index, entry.element_index));
+            }
+        }
+        if let Some((page_index, element_index)) = chosen {
+            self.open_search_preview_entry(page_index, element_index, ui.ctx().screen_rect().size());
+        }
+    }
+    
+    // A single-line excerpt of the text around a match, with ellipses where it was cut. `start` and
+    // `end` are the match's byte range, as `get_match_positions` gives it.
+    fn match_snippet(text: &str, start: usize, end: usize) -> String {
+        let before: String = text[..start].chars().rev().take(SEARCH_PREVIEW_CONTEXT).collect::<Vec<_>>().into_iter().rev().collect();
+        let after: String = text[end..].chars().take(SEARCH_PREVIEW_CONTEXT).collect();
+        
+        let mut snippet = String::new();
+        if before.len() < start {
+            snippet.push('…');
+        }
+        snippet.push_str(&before);
+        snippet.push_str(&text[start..end]);
+        snippet.push_str(&after);
+        if end + after.len() < text.len() {
+            snippet.push('…');
+        }
+        snippet.replace(['\n', '\r'], " ")
+    }
+    
+    // Jump to a previewed match: switch page, center it in the view and select it
+    fn open_search_preview_entry(&mut self, page_index: usize, element_index: usize, view_size: egui::Vec2) {
+        self.go_to_page(page_index);
+        if let Some(text_element) = self.current_text_elements().get(element_index) {
+            let position = text_element.position;
+            let camera = &mut self.current_page_mut().camera;
+            camera.show_at(position, (view_size / 2.0).to_pos2());
+            self.selected_text_elements.push(element_index);
+        }
+    }
+    
+    // The search query as a regex: literal queries are escaped and matched case-insensitively,
+    // the same rules `perform_search` uses
+    fn search_regex(&self) -> Option<Regex> {
+        if self.search_query.is_empty() {
+            return None;
+        }
+        if self.regex_mode {
+            Regex::new(&self.search_query).ok()
+        } else {
+            Regex::new(&format!("(?i){}", regex::escape(&self.search_query))).ok()
+        }
+    }
+    
+    // Replace matches in the current page's matching text elements. With `all` false only
+    // the first match is replaced. Regex replacements may use capture groups like `$1`.
+    fn replace_matches(&mut self, all: bool) {
+        let Some(regex) = self.search_regex() else {
+            return;
+        };
+        let targets: Vec<usize> = self.search_results.iter().copied()
+            .filter(|&idx| {
+                self.current_text_elements().get(idx)
+                    .is_some_and(|t| !regex_match_ranges(&regex, &t.text, self.regex_empty_matches).is_empty())
+            })
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+        self.push_undo(if all { "Replace all" } else { "Replace" });
+        
+        let replacement = self.replace_text.clone();
+        let regex_mode = self.regex_mode;
+        let empty_matches = self.regex_empty_matches;
+        for idx in targets {
+            let text_element = &mut self.current_text_elements_mut()[idx];
+            let limit = if all { 0 } else { 1 };
+            // Literal mode inserts the replacement as-is, `$` included
+            text_element.text = replace_regex_matches(&regex, &text_element.text, limit, &replacement, regex_mode, empty_matches);
+            if !all {
+                break;
+            }
+        }
+        self.perform_search();
+    }
+    
+    fn get_total_match_count(&self) -> usize {
+        let mut total_matches = 0;
+        
+        if self.search_query.is_empty() {
+            return 0;
+        }
+        
+        for &index in &self.search_results {
+            if let Some(text_element) = self.current_text_elements().get(index) {
+                let matches = self.get_match_positions(&text_element.text);
+                total_matches += matches.len();
+            }
+        }
+        
+        total_matches
+    }
+    
+    fn get_match_positions(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut positions = Vec::new();
+        
+        if self.search_query.is_empty() {
+            return positions;
+        }
+        
+        if self.regex_mode {
+            if let Ok(regex) = Regex::new(&self.search_query) {
+                positions = regex_match_ranges(&regex, text, self.regex_empty_matches);
+            }
+        } else {
+            positions = literal_match_ranges(&self.search_query, text);
+        }
+        
+        positions
+    }
+    
+    fn draw_arrows_for_matches(&self, painter: &egui::Painter, camera: Camera, text_pos: egui::Pos2, text: &str, font_size: f32) {
+        let positions = self.get_match_positions(text);
+        if positions.is_empty() {
+            return;
+        }
+        
+        let ctx = painter.ctx();
+        
+        // Split text into lines to handle multiline positioning
+        let lines: Vec<&str> = text.lines().collect();
+        let line_height = self.text_size(ctx, "Ag", font_size).y; // Sample text to measure line height
+        
+        for (start_char, end_char) in positions {
+            // Find which line the match is on and position within that line
+            let mut char_count = 0;
+            let mut match_line = 0;
+            let mut match_start_in_line = start_char;
+            let mut match_end_in_line = end_char;
+            
+            // Find the line containing the match
+            for (line_idx, line) in lines.iter().enumerate() {
+                let line_len = line.len() + 1; // +1 for newline character
+                if char_count + line_len > start_char {
+                    match_line = line_idx;
+                    match_start_in_line = start_char - char_count;
+                    match_end_in_line = end_char - char_count;
+                    break;
+                }
+                char_count += line_len;
+            }
+            
+            // Ensure we don't go beyond the line boundary
+            if match_line < lines.len() {
+                let current_line = lines[match_line];
+                match_end_in_line = match_end_in_line.min(current_line.len());
+                
+                // Calculate positions within the specific line, relative to the text's corner
+                let line_y = match_line as f32 * line_height;
+                let before_match = &current_line[..match_start_in_line];
+                let match_text = &current_line[match_start_in_line..match_end_in_line];
+                
+                // Measure text to get horizontal positions; a zero-width match is a point between
+                // characters, as tall as the line
+                let before_size = self.text_size(ctx, before_match, font_size);
+                let match_size = if match_text.is_empty() {
+                    egui::Vec2::new(0.0, line_height)
+                } else {
+                    self.text_size(ctx, match_text, font_size)
+                };
+                
+                // The arrows stay upright, around wherever the view's rotation puts the match
+                let match_center = text_pos + camera.turn(egui::Vec2::new(before_size.x + match_size.x / 2.0, line_y + match_size.y / 2.0));
+                let match_size = camera.turn(match_size).abs();
+                
+                // Draw arrows pointing to the match on the correct line
+                self.draw_pointing_arrows(painter, match_center.x, match_center.y + match_size.y / 2.0, match_size.x);
+            }
+        }
+    }
+    
+    fn draw_pointing_arrows(&self, painter: &egui::Painter, center_x: f32, text_bottom: f32, match_width: f32) {
+        // Always use dark orange for arrows
+        let arrow_color = egui::Color32::from_rgb(200, 80, 0); // Dark orange arrows
+        
+        let arrow_length = 15.0;
+        let arrow_gap = 5.0;
+        
+        // Try different arrow positions to avoid collisions
+        let text_top = text_bottom - 20.0; // Approximate text height
+        let text_center_y = text_top + 10.0; // Center of text
+        let arrow_positions = [
+            ("bottom", center_x, text_bottom + arrow_gap),  // Below (pointing up)
+            ("top", center_x, text_top - arrow_gap - arrow_length), // Above (pointing down)
+            ("left", center_x - match_width / 2.0 - arrow_gap - arrow_length, text_center_y), // Left (pointing right)
+            ("right", center_x + match_width / 2.0 + arrow_gap, text_center_y), // Right (pointing left)
+        ];
+        
+        let mut arrow_drawn = false;
+        
+        for (arrow_type, arrow_x, arrow_y) in arrow_positions {
+            if !self.check_arrow_collision_at_position(arrow_x, arrow_y, arrow_length) {
+                match arrow_type {
+                    "bottom" => self.draw_bottom_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
+                    "top" => self.draw_top_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
+                    "left" => self.draw_left_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
+                    "right" => self.draw_right_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
+                    _ => {}
+                }
+                arrow_drawn = true;
+                break;
+            }
+        }
+        
+        // If no position is collision-free, draw at the original bottom position
+        if !arrow_drawn {
+            self.draw_bottom_arrow(painter, center_x, text_bottom + arrow_gap, arrow_length, arrow_color);
+        }
+        
+        // Draw side arrows if the match is wide enough and we used the bottom position
+        if match_width > 30.0 && !arrow_drawn {
+            let side_offset = match_width / 3.0;
+            
+            // Left side arrow
+            let left_center = center_x - side_offset;
+            self.draw_bottom_arrow(painter, left_center, text_bottom + arrow_gap, arrow_length * 0.7, arrow_color);
+            
+            // Right side arrow
+            let right_center = center_x + side_offset;
+            self.draw_bottom_arrow(painter, right_center, text_bottom + arrow_gap, arrow_length * 0.7, arrow_color);
+        }
+    }
+    
+    fn check_arrow_collision_at_position(&self, arrow_x: f32, arrow_y: f32, arrow_length: f32) -> bool {
+        // Create a slightly larger area around the arrow for collision detection
+        let collision_padding = 2.0;
+        let arrow_area = egui::Rect::from_center_size(
+            egui::Pos2::new(arrow_x, arrow_y),
+            egui::Vec2::new(arrow_length + collision_padding * 2.0, arrow_length + collision_padding * 2.0),
+        );
+        
+        // Arrows are placed in screen space, so text bounds go through the camera
+        let camera = self.current_page().camera;
+        
+        // Only check for collisions with other text elements (not the one being searched)
+        for (text_idx, text_element) in self.current_text_elements().iter().enumerate() {
+            // Skip text elements that are search results (we want to point to them)
+            if self.search_results.contains(&text_idx) {
+                continue;
+            }
+            
+            let lines: Vec<&str> = text_element.text.lines().collect();
+            let font_size = text_element.font_size;
+            let line_height = font_size * 1.2;
+            
+            for (line_idx, line) in lines.iter().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                
+                let line_y = text_element.position.y + (line_idx as f32) * line_height;
+                let estimated_text_width = line.len() as f32 * font_size * 0.6; // Rough estimation
+                
+                let text_rect = camera.rect_to_screen(egui::Rect::from_min_size(
+                    egui::Pos2::new(text_element.position.x, line_y),
+                    egui::Vec2::new(estimated_text_width, font_size), // Standard text height
+                ));
+                
+                if arrow_area.intersects(text_rect) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    
+    fn draw_bottom_arrow(&self, painter: &egui::Painter, center_x: f32, arrow_y: f32, arrow_length: f32, color: egui::Color32) {
+        let arrow_tip = egui::Pos2::new(center_x, arrow_y);
+        let arrow_base = egui::Pos2::new(center_x, arrow_y + arrow_length);
+        
+        // Main arrow line
+        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
+        
+        // Arrow head
+        let head_size = 4.0;
+        let left_wing = egui::Pos2::new(center_x - head_size, arrow_y + head_size);
+        let right_wing = egui::Pos2::new(center_x + head_size, arrow_y + head_size);
+        
+        painter.line_segment([arrow_tip, left_wing], egui::Stroke::new(2.0, color));
+        painter.line_segment([arrow_tip, right_wing], egui::Stroke::new(2.0, color));
+    }
+    
+    fn draw_top_arrow(&self, painter: &egui::Painter, center_x: f32, arrow_y: f32, arrow_length: f32, color: egui::Color32) {
+        let arrow_tip = egui::Pos2::new(center_x, arrow_y + arrow_length);
+        let arrow_base = egui::Pos2::new(center_x, arrow_y);
+        
+        // Main arrow line
+        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
+        
+        // Arrow head (pointing down)
+        let head_size = 4.0;
+        let left_wing = egui::Pos2::new(center_x - head_size, arrow_y + arrow_length - head_size);
+        let right_wing = egui::Pos2::new(center_x + head_size, arrow_y + arrow_length - head_size);
+        
+        painter.line_segment([arrow_tip, left_wing], egui::Stroke::new(2.0, color));
+        painter.line_segment([arrow_tip, right_wing], egui::Stroke::new(2.0, color));
+    }
+    
+    fn draw_left_arrow(&self, painter: &egui::Painter, arrow_x: f32, center_y: f32, arrow_length: f32, color: egui::Color32) {
+        let arrow_base = egui::Pos2::new(arrow_x, center_y);
+        let arrow_tip = egui::Pos2::new(arrow_x + arrow_length, center_y);
+        
+        // Main arrow line
+        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
+        
+        // Arrow head (pointing right)
+        let head_size = 4.0;
+        let top_wing = egui::Pos2::new(arrow_x + arrow_length - head_size, center_y - head_size);
+        let bottom_wing = egui::Pos2::new(arrow_x + arrow_length - head_size, center_y + head_size);
+        
+        painter.line_segment([arrow_tip, top_wing], egui::Stroke::new(2.0, color));
+        painter.line_segment([arrow_tip, bottom_wing], egui::Stroke::new(2.0, color));
+    }
+    
+    fn draw_right_arrow(&self, painter: &egui::Painter, arrow_x: f32, center_y: f32, arrow_length: f32, color: egui::Color32) {
+        let arrow_base = egui::Pos2::new(arrow_x + arrow_length, center_y);
+        let arrow_tip = egui::Pos2::new(arrow_x, center_y);
+        
+        // Main arrow line
+        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
+        
+        // Arrow head (pointing left)
+        let head_size = 4.0;
+        let top_wing = egui::Pos2::new(arrow_x + head_size, center_y - head_size);
+        let bottom_wing = egui::Pos2::new(arrow_x + head_size, center_y + head_size);
+        
+        painter.line_segment([arrow_tip, top_wing], egui::Stroke::new(2.0, color));
+        painter.line_segment([arrow_tip, bottom_wing], egui::Stroke::new(2.0, color));
+    }
+    
+    // Selection indices refer to the current page, so they are dropped whenever the page changes
+    fn clear_selection(&mut self) {
+        self.selected_text_elements.clear();
+        self.selected_strokes.clear();
+        self.is_selecting_text = false;
+        self.selection_start = None;
+        self.selection_end = None;
+    }
+    
+    fn has_selection(&self) -> bool {
+        !self.selected_text_elements.is_empty() || !self.selected_strokes.is_empty()
+    }
+    
+    // Select what the rubber band touches, or with `enclosed` only what lies entirely inside it
+    fn update_text_selection(&mut self, enclosed: bool) {
+        self.selected_text_elements.clear();
+        self.selected_strokes.clear();
+        
+        if let (Some(start), Some(end)) = (self.selection_start, self.selection_end) {
+            let selection_rect = egui::Rect::from_two_pos(start, end);
+            
+            // Strokes are selected when any of their points fall inside the rectangle, or all of them when enclosed
+            self.selected_strokes = self.current_strokes().iter().enumerate()
+                .filter(|(_, stroke)| !self.hidden_by_tag_filter(&stroke.tag))
+                .filter(|(_, stroke)| if enclosed {
+                    !stroke.points.is_empty() && stroke.points.iter().all(|p| selection_rect.contains(*p))
+                } else {
+                    stroke.points.iter().any(|p| selection_rect.contains(*p))
+                })
+                .map(|(idx, _)| idx)
+                .collect();
+            
+            self.selected_text_elements = self.current_text_elements().iter().enumerate()
+                .filter(|(_, text_element)| !self.hidden_by_tag_filter(&text_element.tag))
+                .filter(|(_, text_element)| if enclosed {
+                    self.text_within_rect(text_element, selection_rect)
+                } else {
+                    self.text_intersects_rect(text_element, selection_rect)
+                })
+                .map(|(idx, _)| idx)
+                .collect();
+        }
+    }
+    
+    // The topmost text element under a canvas position, which is the last one drawn
+    fn get_text_element_at_position(&self, pos: egui::Pos2) -> Option<usize> {
+        self.text_elements_at_position(pos).first().copied()
+    }
+    
+    // Every text element under a canvas position, topmost first
+    fn text_elements_at_position(&self, pos: egui::Pos2) -> Vec<usize> {
+        let screen_pos = self.current_page().camera.to_screen(pos);
+        self.current_text_elements().iter().enumerate().rev()
+            .filter(|(_, text_element)| !self.hidden_by_tag_filter(&text_element.tag))
+            .filter(|(_, text_element)| {
+                // Pinned text is tested in its own screen-anchored space
+                let pos = self.text_camera(text_element).to_canvas(screen_pos);
+                Self::text_line_rects(text_element).iter().any(|rect| rect.contains(pos))
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+    
+    // Alt+click steps down through text elements stacked under the pointer, wrapping back to the top
+    fn cycle_text_selection_at(&mut self, pos: egui::Pos2) {
+        let stack = self.text_elements_at_position(pos);
+        if stack.is_empty() {
+            return;
+        }
+        let next = match self.selected_text_elements.as_slice() {
+            [selected] => stack.iter().position(|idx| idx == selected).map_or(0, |depth| (depth + 1) % stack.len()),
+            _ => 0,
+        };
+        self.selected_text_elements = vec![stack[next]];
+        self.selected_strokes.clear();
+    }
+    
+    // Transform for drawing and hit-testing a text element: the page camera, or for pinned
+    // text a fixed mapping onto the canvas area's screen position
+    fn text_camera(&self, text_element: &TextElement) -> Camera {
+        if text_element.pinned {
+            self.pinned_camera()
+        } else {
+            self.current_page().camera
+        }
+    }
+    
+    // Whether any line of a text element overlaps a canvas rectangle, as seen on screen
+    fn text_intersects_rect(&self, text_element: &TextElement, rect: egui::Rect) -> bool {
+        let rect = self.rect_in_text_space(text_element, rect);
+        Self::text_line_rects(text_element).iter().any(|line| line.intersects(rect))
+    }
+    
+    fn text_within_rect(&self, text_element: &TextElement, rect: egui::Rect) -> bool {
+        let rect = self.rect_in_text_space(text_element, rect);
+        Self::text_line_rects(text_element).iter().all(|line| rect.contains_rect(*line))
+    }
+    
+    // A canvas rect in the space a text element is laid out in, which differs for pinned text
+    fn rect_in_text_space(&self, text_element: &TextElement, rect: egui::Rect) -> egui::Rect {
+        let screen_rect = self.current_page().camera.rect_to_screen(rect);
+        let text_camera = self.text_camera(text_element);
+        egui::Rect::from_two_pos(text_camera.to_canvas(screen_rect.min), text_camera.to_canvas(screen_rect.max))
+    }
+    
+    fn pinned_camera(&self) -> Camera {
+        Camera {
+            offset: -self.canvas_origin.to_vec2(),
+            zoom: 1.0,
+            quarter_turns: 0,
+        }
+    }
+    
+    // Move a canvas-space text element into pinned space, keeping its current on-screen size and place
+    fn pin_text(&self, mut text_element: TextElement) -> TextElement {
+        let camera = self.current_page().camera;
+        text_element.position = self.pinned_camera().to_canvas(camera.to_screen(text_element.position));
+        text_element.font_size *= camera.zoom;
+        if let Some((offset, _)) = &mut text_element.shadow {
+            *offset = camera.vec_to_screen(*offset);
+        }
+        text_element.pinned = true;
+        text_element
+    }
+    
+    fn unpin_text(&self, mut text_element: TextElement) -> TextElement {
+        let camera = self.current_page().camera;
+        text_element.position = camera.to_canvas(self.pinned_camera().to_screen(text_element.position));
+        text_element.font_size /= camera.zoom;
+        if let Some((offset, _)) = &mut text_element.shadow {
+            *offset = turn_vec(*offset, 4 - camera.quarter_turns % 4) / camera.zoom;
+        }
+        text_element.pinned = false;
+        text_element
+    }
+    
+    // Pin or unpin every selected text element
+    fn set_selection_pinned(&mut self, pinned: bool) {
+        self.push_undo(if pinned { "Pin text" } else { "Unpin text" });
+        for idx in self.selected_text_elements.clone() {
+            if let Some(text_element) = self.current_text_elements().get(idx).cloned()
+                && text_element.pinned != pinned
+            {
+                let text_element = if pinned { self.pin_text(text_element) } else { self.unpin_text(text_element) };
+                self.current_text_elements_mut()[idx] = text_element;
+            }
+        }
+    }
+    
+    fn set_selection_orientation(&mut self, orientation: TextOrientation) {
+        self.push_undo("Change text direction");
+        for idx in self.selected_text_elements.clone() {
+            if let Some(text_element) = self.current_text_elements_mut().get_mut(idx) {
+                text_element.orientation = orientation;
+            }
+        }
+    }
+    
+    // Delete the text elements at the given indices, keeping connectors between the rest attached
+    fn remove_text_elements(&mut self, indices: &[usize]) {
+        let mut idx = 0;
+        self.current_text_elements_mut().retain(|_| {
+            let keep = !indices.contains(&idx);
+            idx += 1;
+            keep
+        });
+        scribble_core::remap_connectors(&mut self.current_page_mut().connectors, |idx| scribble_core::index_after_removal(idx, indices));
+    }
+    
+    // The two selected text elements, in the order they were selected
+    fn selected_text_pair(&self) -> Option<(usize, usize)> {
+        match self.selected_text_elements[..] {
+            [from, to] if from != to => Some((from, to)),
+            _ => None,
+        }
+    }
+    
+    // Join the two selected text elements with a connector, replacing any already between them
+    fn connect_selected_text(&mut self, arrow: bool) {
+        let Some((from, to)) = self.selected_text_pair() else {
+            return;
+        };
+        self.push_undo("Connect text");
+        let connectors = &mut self.current_page_mut().connectors;
+        connectors.retain(|connector| !connector.joins(from, to));
+        let connector = scribble_core::Connector::new(from, to);
+        connectors.push(if arrow { connector.with_arrow() } else { connector });
+    }
+    
+    fn disconnect_selected_text(&mut self) {
+        let Some((from, to)) = self.selected_text_pair() else {
+            return;
+        };
+        self.push_undo("Disconnect text");
+        self.current_page_mut().connectors.retain(|connector| !connector.joins(from, to));
+    }
+    
+    // Replace each selected text element with strokes tracing its glyph outlines, then select them
+    fn convert_selection_to_paths(&mut self) {
+        let mut indices = self.selected_text_elements.clone();
+        indices.sort_unstable();
+        indices.dedup();
+        let text_elements: Vec<TextElement> = indices.iter()
+            .filter_map(|&idx| self.current_text_elements().get(idx).cloned())
+            .map(|text_element| if text_element.pinned { self.unpin_text(text_element) } else { text_element })
+            .collect();
+        if text_elements.is_empty() {
+            return;
+        }
+        self.push_undo("Convert text to paths");
+        self.close_text_editor();
+        self.remove_text_elements(&indices);
+        let first_new = self.current_strokes().len();
+        for text_element in &text_elements {
+            let width = (text_element.font_size * TEXT_PATH_WIDTH_RATIO).max(1.0);
+            for points in text_outline_contours(text_element) {
+                self.current_strokes_mut().push(Stroke {
+                    points,
+                    color: egui::Color32::BLACK,
+                    width,
+                    colors: Vec::new(),
+                    pressures: Vec::new(),
+                    smoothing: 0.0,
+                    opacity: 1.0,
+                    tag: text_element.tag.clone(),
+                    build_order: text_element.build_order,
+                });
+            }
+        }
+        self.clear_selection();
+        self.selected_strokes = (first_new..self.current_strokes().len()).collect();
+        self.perform_search();
+    }
+    
+    // Turn the floating editor's contents into a text element at the given canvas position
+    fn commit_text_input(&mut self, position: egui::Pos2) {
+        if let Some(idx) = self.editing_text {
+            self.commit_text_edit(idx);
+            return;
+        }
+        if self.text_input.trim().is_empty() {
+            return;
         }
+        self.push_undo("Add text");
         
-        for &index in &self.search_results {
-            if let Some(text_element) = self.current_text_elements().get(index) {
-                let matches = self.get_match_positions(&text_element.text);
-                total_matches += matches.len();
+        let text_element = self.new_text_element(position, self.text_input.clone());
+        let text_element = if self.pin_new_text { self.pin_text(text_element) } else { text_element };
+        self.current_text_elements_mut().push(text_element);
+        self.close_text_editor();
+        self.remember_font_size();
+    }
+    
+    // A canvas text element in the current text style
+    fn new_text_element(&self, position: egui::Pos2, text: String) -> TextElement {
+        TextElement {
+            position,
+            text,
+            font_size: self.text_font_size,
+            outline: self.text_outline_enabled.then_some(self.text_outline_color),
+            shadow: self.text_shadow_enabled.then(|| {
+                (egui::Vec2::splat(self.text_font_size * TEXT_SHADOW_OFFSET_RATIO), self.text_shadow_color)
+            }),
+            pinned: false,
+            tag: self.new_element_tag(),
+            build_order: None,
+            orientation: self.text_orientation,
+        }
+    }
+    
+    // Commit from the floating editor; a newly added element may then go to the Select tool
+    fn commit_text_editor(&mut self, position: egui::Pos2) {
+        let count = self.current_text_elements().len();
+        self.commit_text_input(position);
+        if self.current_text_elements().len() > count {
+            self.auto_select_created(true);
+        }
+    }
+    
+    // Write the editor's contents back into an existing element; emptying it deletes the element
+    fn commit_text_edit(&mut self, idx: usize) {
+        let text = std::mem::take(&mut self.text_input);
+        let font_size = self.text_font_size;
+        if self.current_text_elements().get(idx).is_some_and(|t| t.text != text || t.font_size != font_size) {
+            self.push_undo("Edit text");
+            if text.trim().is_empty() {
+                self.remove_text_elements(&[idx]);
+                self.clear_selection();
+            } else {
+                let text_element = &mut self.current_text_elements_mut()[idx];
+                text_element.text = text;
+                text_element.font_size = font_size;
             }
         }
+        self.close_text_editor();
+        self.remember_font_size();
+    }
+    
+    fn close_text_editor(&mut self) {
+        self.text_input.clear();
+        self.active_text_position = None;
+        self.editing_text = None;
+    }
+    
+    // Open an existing text element in the floating editor with the caret at the clicked character
+    fn edit_text_element(&mut self, ctx: &egui::Context, idx: usize, click_pos: egui::Pos2) {
+        let Some(text_element) = self.current_text_elements().get(idx).cloned() else {
+            return;
+        };
+        let camera = self.current_page().camera;
+        let text_camera = self.text_camera(&text_element);
         
-        total_matches
+        // Lay the text out unzoomed so the click offset can be measured in the element's own units
+        let local = text_camera.to_canvas(camera.to_screen(click_pos)) - text_element.position;
+        let galley = ctx.fonts(|fonts| fonts.layout_no_wrap(
+            text_element.text.clone(),
+            egui::FontId::proportional(text_element.font_size),
+            egui::Color32::BLACK,
+        ));
+        let cursor = galley.cursor_from_pos(local);
+        
+        let mut state = egui::text_edit::TextEditState::load(ctx, self.text_input_id).unwrap_or_default();
+        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(cursor.ccursor)));
+        state.store(ctx, self.text_input_id);
+        
+        self.editing_text = Some(idx);
+        self.active_text_position = Some(camera.to_canvas(text_camera.to_screen(text_element.position)));
+        self.text_input = text_element.text;
+        self.text_font_size = text_element.font_size;
+        ctx.memory_mut(|mem| mem.request_focus(self.text_input_id));
+    }
+    
+    // The last size actually used becomes the default for new text
+    fn remember_font_size(&mut self) {
+        if self.notebook_font_size.is_some() {
+            self.notebook_font_size = Some(self.text_font_size);
+        }
+        if self.settings.last_text_font_size != Some(self.text_font_size) {
+            self.settings.last_text_font_size = Some(self.text_font_size);
+            if let Err(e) = self.settings.save() {
+                self.log(&format!("Failed to save settings: {}", e), true);
+            }
+        }
+    }
+    
+    // The stroke whose path passes nearest a canvas position, within its half width plus the hit tolerance
+    fn get_stroke_at_position(&self, pos: egui::Pos2) -> Option<usize> {
+        let mut nearest: Option<(usize, f32)> = None;
+        // Later strokes are drawn on top, so they win ties
+        for (idx, stroke) in self.current_strokes().iter().enumerate().rev() {
+            if self.hidden_by_tag_filter(&stroke.tag) {
+                continue;
+            }
+            let distance = match stroke.points.as_slice() {
+                [] => continue,
+                [point] => point.distance(pos),
+                points => points.windows(2)
+                    .map(|segment| distance_to_segment(pos, segment[0], segment[1]))
+                    .fold(f32::INFINITY, f32::min),
+            };
+            // Measured from the stroke's edge, so a thick stroke isn't beaten by a thin one inside it
+            let gap = distance - stroke.width / 2.0;
+            if gap <= STROKE_HIT_TOLERANCE && nearest.is_none_or(|(_, best)| gap < best) {
+                nearest = Some((idx, gap));
+            }
+        }
+        nearest.map(|(idx, _)| idx)
+    }
+    
+    // A plain click selects the text or stroke under the pointer; with Shift it is added to,
+    // or taken out of, the current selection
+    fn click_select_at(&mut self, pos: egui::Pos2, additive: bool) {
+        let (text, stroke) = match self.get_text_element_at_position(pos) {
+            Some(idx) => (Some(idx), None),
+            None => (None, self.get_stroke_at_position(pos)),
+        };
+        if text.is_none() && stroke.is_none() {
+            return;
+        }
+        if !additive {
+            self.selected_text_elements.clear();
+            self.selected_strokes.clear();
+        }
+        for (selected, idx) in [(&mut self.selected_text_elements, text), (&mut self.selected_strokes, stroke)] {
+            if let Some(idx) = idx {
+                match selected.iter().position(|&existing| existing == idx) {
+                    Some(at) if additive => {
+                        selected.remove(at);
+                    }
+                    Some(_) => {}
+                    None => selected.push(idx),
+                }
+            }
+        }
+    }
+    
+    // First and last points of every stroke on the current page
+    fn stroke_endpoints(&self) -> Vec<egui::Pos2> {
+        self.current_strokes().iter()
+            .filter_map(|stroke| Some([*stroke.points.first()?, *stroke.points.last()?]))
+            .flatten()
+            .collect()
+    }
+    
+    // The page as a canvas rectangle, from the origin
+    fn page_rect(&self) -> egui::Rect {
+        egui::Rect::from_min_size(egui::Pos2::ZERO, self.canvas_size)
+    }
+    
+    // A canvas position pulled inside the page, when content is kept within it
+    fn keep_within_page(&self, pos: egui::Pos2) -> egui::Pos2 {
+        if self.settings.keep_within_page {
+            let page = self.page_rect();
+            pos.clamp(page.min, page.max)
+        } else {
+            pos
+        }
+    }
+    
+    // Add a finished stroke in the current color and width as one undoable step
+    fn commit_stroke(&mut self, label: &str, points: Vec<egui::Pos2>, colors: Vec<egui::Color32>, pressures: Vec<f32>, smoothing: f32) {
+        let points: Vec<egui::Pos2> = points.into_iter().map(|point| self.keep_within_page(point)).collect();
+        self.session_ink += points.windows(2).map(|segment| segment[0].distance(segment[1])).sum::<f32>();
+        self.push_undo(label);
+        let stroke = Stroke {
+            points,
+            color: colors.first().copied().unwrap_or(self.stroke_color),
+            width: self.stroke_width,
+            colors,
+            pressures,
+            smoothing,
+            opacity: self.stroke_opacity,
+            tag: self.new_element_tag(),
+            build_order: None,
+        };
+        self.current_strokes_mut().push(stroke);
+    }
+    
+    // Commit the curve placed so far as a stroke; it needs two distinct points
+    fn finish_curve(&mut self) {
+        let mut anchors = std::mem::take(&mut self.curve_anchors);
+        anchors.dedup_by(|b, a| a.point == b.point); // A double-click also places a point on each click
+        if anchors.len() > 1 {
+            self.commit_stroke("Draw curve", curve_points(&anchors), Vec::new(), Vec::new(), 0.0);
+            self.auto_select_created(false);
+        }
+        self.current_stroke.clear();
+    }
+    
+    // With auto-select on, switch to the Select tool with the element just added (the last stroke or
+    // text element) selected
+    fn auto_select_created(&mut self, text: bool) {
+        if !self.settings.auto_select_after_create {
+            return;
+        }
+        let count = if text { self.current_text_elements().len() } else { self.current_strokes().len() };
+        let Some(index) = count.checked_sub(1) else {
+            return;
+        };
+        self.clear_selection();
+        if text {
+            self.selected_text_elements.push(index);
+        } else {
+            self.selected_strokes.push(index);
+        }
+        self.current_tool = Tool::Select;
+    }
+    
+    fn cancel_curve(&mut self) {
+        self.curve_anchors.clear();
+        self.current_stroke.clear();
+    }
+    
+    // End point of a line dragged from start, with its angle rounded to the snap increment when asked
+    fn constrain_shape_end(&self, start: egui::Pos2, end: egui::Pos2, snap_angle: bool) -> egui::Pos2 {
+        let delta = end - start;
+        if !snap_angle || delta == egui::Vec2::ZERO {
+            return end;
+        }
+        let increment = self.settings.angle_snap_degrees.to_radians();
+        let angle = (delta.angle() / increment).round() * increment;
+        start + egui::Vec2::angled(angle) * delta.length()
+    }
+    
+    // Pull each coordinate of a canvas position onto the nearest custom guide on the page within
+    // reach; a coordinate with no guide nearby is left unchanged
+    fn snap_to_page_guides(&self, pos: egui::Pos2) -> egui::Pos2 {
+        let page = self.current_page();
+        let range = GUIDE_SNAP_DISTANCE / page.camera.zoom;
+        let nearest = |guides: &[f32], value: f32| guides.iter().copied()
+            .filter(|guide| (guide - value).abs() <= range)
+            .min_by(|a, b| (a - value).abs().total_cmp(&(b - value).abs()))
+            .unwrap_or(value);
+        egui::Pos2::new(nearest(&page.guides_x, pos.x), nearest(&page.guides_y, pos.y))
+    }
+    
+    // The custom guide under a screen position, as (vertical, index); the nearest wins where they cross
+    fn guide_at(&self, screen_pos: egui::Pos2) -> Option<(bool, usize)> {
+        let page = self.current_page();
+        let pos = page.camera.to_canvas(screen_pos);
+        let range = GUIDE_GRAB_DISTANCE / page.camera.zoom;
+        let vertical = page.guides_x.iter().enumerate().map(|(idx, x)| ((x - pos.x).abs(), (true, idx)));
+        let horizontal = page.guides_y.iter().enumerate().map(|(idx, y)| ((y - pos.y).abs(), (false, idx)));
+        vertical.chain(horizontal)
+            .filter(|(distance, _)| *distance <= range)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, guide)| guide)
+    }
+    
+    // Drag custom guides out of the rulers, reposition them, and drop them back on a ruler or off the canvas to remove them.
+    // Placed guides are only grabbed with the Select tool, so drawing along a guide never moves it.
+    // Returns whether a guide has the pointer this frame.
+    fn handle_guide_drag(&mut self, ctx: &egui::Context, response: &egui::Response, canvas_rect: egui::Rect) -> bool {
+        let top_ruler = egui::Rect::from_min_max(canvas_rect.min, egui::Pos2::new(canvas_rect.max.x, canvas_rect.min.y + GUIDE_RULER_WIDTH));
+        let left_ruler = egui::Rect::from_min_max(canvas_rect.min, egui::Pos2::new(canvas_rect.min.x + GUIDE_RULER_WIDTH, canvas_rect.max.y));
+        let hover_pos = response.hover_pos();
+        if response.drag_started_by(egui::PointerButton::Primary)
+            && let Some(origin) = ctx.input(|i| i.pointer.press_origin())
+        {
+            let camera = self.current_page().camera;
+            let pos = camera.to_canvas(origin);
+            // The top ruler gives a line across the screen, which is a vertical guide on a quarter-turned view
+            let from_ruler = if top_ruler.contains(origin) {
+                Some(camera.swaps_axes())
+            } else if left_ruler.contains(origin) {
+                Some(!camera.swaps_axes())
+            } else {
+                None
+            };
+            let placed = if self.current_tool == Tool::Select { self.guide_at(origin) } else { None };
+            if from_ruler.is_some() || placed.is_some() {
+                self.push_undo("Move guide");
+            }
+            let page = self.current_page_mut();
+            self.guide_drag = match from_ruler {
+                Some(true) => {
+                    page.guides_x.push(pos.x);
+                    Some((true, page.guides_x.len() - 1))
+                }
+                Some(false) => {
+                    page.guides_y.push(pos.y);
+                    Some((false, page.guides_y.len() - 1))
+                }
+                None => placed,
+            };
+        }
+        
+        let swapped = self.current_page().camera.swaps_axes();
+        let Some((vertical, index)) = self.guide_drag else {
+            // Show where guides can be grabbed
+            if let Some(pos) = hover_pos {
+                let over_guide = if self.current_tool == Tool::Select { self.guide_at(pos) } else { None };
+                if top_ruler.contains(pos) || over_guide.is_some_and(|(vertical, _)| vertical == swapped) {
+                    ctx.set_cursor_icon(egui::CursorIcon::ResizeVertical);
+                } else if left_ruler.contains(pos) || over_guide.is_some() {
+                    ctx.set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+                }
+            }
+            return false;
+        };
+        
+        let upright = vertical != swapped;
+        ctx.set_cursor_icon(if upright { egui::CursorIcon::ResizeHorizontal } else { egui::CursorIcon::ResizeVertical });
+        let pointer = ctx.input(|i| i.pointer.latest_pos());
+        if let Some(pointer) = pointer {
+            let pos = self.current_page().camera.to_canvas(pointer);
+            let page = self.current_page_mut();
+            let guides = if vertical { &mut page.guides_x } else { &mut page.guides_y };
+            if let Some(guide) = guides.get_mut(index) {
+                *guide = if vertical { pos.x } else { pos.y };
+            }
+        }
+        if !response.dragged() {
+            let ruler = if upright { left_ruler } else { top_ruler };
+            if pointer.is_none_or(|pointer| ruler.contains(pointer) || !canvas_rect.contains(pointer)) {
+                let page = self.current_page_mut();
+                let guides = if vertical { &mut page.guides_x } else { &mut page.guides_y };
+                if index < guides.len() {
+                    guides.remove(index);
+                }
+            }
+            self.guide_drag = None;
+        }
+        true
+    }
+    
+    // Project a canvas position onto the closest visible stroke within reach, for trace mode
+    fn trace_snap(&self, pos: egui::Pos2) -> egui::Pos2 {
+        let range = TRACE_SNAP_DISTANCE / self.current_page().camera.zoom;
+        self.current_strokes().iter()
+            .filter(|stroke| !self.hidden_by_tag_filter(&stroke.tag) && !self.hidden_by_build(stroke.build_order))
+            .flat_map(|stroke| match stroke.points.as_slice() {
+                [single] => vec![*single],
+                points => points.windows(2).map(|segment| closest_point_on_segment(pos, segment[0], segment[1])).collect(),
+            })
+            .filter(|projected| projected.distance(pos) <= range)
+            .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)))
+            .unwrap_or(pos)
+    }
+    
+    // The nearest existing endpoint within snapping range, or the position unchanged
+    fn snap_to_endpoints(&self, pos: egui::Pos2, endpoints: &[egui::Pos2]) -> egui::Pos2 {
+        let range = ENDPOINT_SNAP_DISTANCE / self.current_page().camera.zoom;
+        endpoints.iter().copied()
+            .filter(|endpoint| endpoint.distance(pos) <= range)
+            .min_by(|a, b| a.distance(pos).total_cmp(&b.distance(pos)))
+            .unwrap_or(pos)
+    }
+    
+    // Remove every stroke passing within the radius of a canvas position
+    fn erase_strokes_at(&mut self, pos: egui::Pos2, radius: f32) {
+        let hide_filtered = self.hide_filtered;
+        let tag_filter = self.tag_filter.clone();
+        let hit = |stroke: &Stroke| {
+            if hide_filtered && tag_filter.is_some() && stroke.tag != tag_filter {
+                return false;
+            }
+            let tolerance = stroke.width / 2.0 + radius;
+            match stroke.points.as_slice() {
+                [point] => point.distance(pos) <= tolerance,
+                points => points.windows(2).any(|segment| distance_to_segment(pos, segment[0], segment[1]) <= tolerance),
+            }
+        };
+        if !self.current_strokes().iter().any(hit) {
+            return;
+        }
+        
+        self.record_erase_pass();
+        self.current_strokes_mut().retain(|stroke| !hit(stroke));
+        // Stroke indices have shifted
+        self.selected_strokes.clear();
+    }
+    
+    // Everything erased in one pass is a single undo step
+    fn record_erase_pass(&mut self) {
+        if !self.erase_pass_recorded {
+            self.push_undo("Erase");
+            self.erase_pass_recorded = true;
+        }
+    }
+    
+    // Cut away the part of each segment that `erased` reports, as the span of the segment from
+    // start (0) to end (1) lying inside the eraser, and split strokes where they were cut. Cuts
+    // fall exactly where segments cross the eraser's edge, so a long segment passing straight
+    // through is cut too. Pieces too short to draw are dropped. Returns whether anything changed.
+    fn cut_strokes(&mut self, erased: impl Fn(&Stroke, egui::Pos2, egui::Pos2) -> Option<(f32, f32)>) -> bool {
+        let hide_filtered = self.hide_filtered;
+        let tag_filter = self.tag_filter.clone();
+        let erased = |stroke: &Stroke, start: egui::Pos2, end: egui::Pos2| {
+            if hide_filtered && tag_filter.is_some() && stroke.tag != tag_filter {
+                return None;
+            }
+            erased(stroke, start, end)
+        };
+        // A lone point is tested as a segment of no length
+        let segments = |stroke: &Stroke| -> Vec<(usize, usize)> {
+            match stroke.points.len() {
+                1 => vec![(0, 0)],
+                len => (1..len).map(|i| (i - 1, i)).collect(),
+            }
+        };
+        let hit = self.current_strokes().iter()
+            .any(|stroke| segments(stroke).into_iter().any(|(a, b)| erased(stroke, stroke.points[a], stroke.points[b]).is_some()));
+        if !hit {
+            return false;
+        }
+        self.record_erase_pass();
+        
+        let mut remaining = Vec::new();
+        for stroke in self.current_strokes_mut().drain(..) {
+            let mut piece = Stroke { points: Vec::new(), colors: Vec::new(), pressures: Vec::new(), ..stroke.clone() };
+            // Add the point `t` of the way along the segment between points `a` and `b`
+            let push = |piece: &mut Stroke, a: usize, b: usize, t: f32| {
+                piece.points.push(stroke.points[a].lerp(stroke.points[b], t));
+                if let Some(&color) = stroke.colors.get(if t < 0.5 { a } else { b }) {
+                    piece.colors.push(color);
+                }
+                if let (Some(&from), Some(&to)) = (stroke.pressures.get(a), stroke.pressures.get(b)) {
+                    piece.pressures.push(from + (to - from) * t);
+                }
+            };
+            let mut finish = |piece: &mut Stroke| {
+                if piece.is_drawable() {
+                    remaining.push(piece.clone());
+                }
+                piece.points.clear();
+                piece.colors.clear();
+                piece.pressures.clear();
+            };
+            for (a, b) in segments(&stroke) {
+                let span = erased(&stroke, stroke.points[a], stroke.points[b]);
+                if piece.points.is_empty() && span.is_none_or(|(from, _)| from > 0.0) {
+                    push(&mut piece, a, b, 0.0);
+                }
+                match span {
+                    None => push(&mut piece, a, b, 1.0),
+                    Some((from, to)) => {
+                        if from > 0.0 {
+                            push(&mut piece, a, b, from);
+                        }
+                        finish(&mut piece);
+                        if to < 1.0 {
+                            push(&mut piece, a, b, to);
+                            push(&mut piece, a, b, 1.0);
+                        }
+                    }
+                }
+            }
+            finish(&mut piece);
+        }
+        *self.current_strokes_mut() = remaining;
+        self.selected_strokes.clear();
+        true
+    }
+    
+    fn erase_segments_at(&mut self, pos: egui::Pos2, radius: f32) {
+        self.cut_strokes(|stroke, start, end| segment_span_in_circle(start, end, pos, stroke.width / 2.0 + radius));
     }
     
-    fn get_match_positions(&self, text: &str) -> Vec<(usize, usize)> {
-        let mut positions = Vec::new();
+    // Cut strokes out of the rectangle and delete text that overlaps it
+    fn erase_in_rect(&mut self, rect: egui::Rect) {
+        let strokes_changed = self.cut_strokes(|_, start, end| segment_span_in_rect(start, end, rect));
         
-        if self.search_query.is_empty() {
-            return positions;
+        let overlapping: Vec<usize> = self.current_text_elements().iter().enumerate()
+            .filter(|(_, text_element)| !self.hidden_by_tag_filter(&text_element.tag))
+            .filter(|(_, text_element)| self.text_intersects_rect(text_element, rect))
+            .map(|(idx, _)| idx)
+            .collect();
+        if !overlapping.is_empty() {
+            self.record_erase_pass();
+            self.remove_text_elements(&overlapping);
         }
         
-        if self.regex_mode {
-            if let Ok(regex) = Regex::new(&self.search_query) {
-                for match_result in regex.find_iter(text) {
-                    positions.push((match_result.start(), match_result.end()));
-                }
+        if strokes_changed || !overlapping.is_empty() {
+            self.clear_selection();
+            self.perform_search();
+        }
+    }
+    
+    // Canvas area covered by the current page's strokes and unpinned text
+    fn content_rect(&self) -> Option<egui::Rect> {
+        let stroke_points = self.current_strokes().iter()
+            .filter(|stroke| stroke.is_drawable())
+            .flat_map(|stroke| stroke.points.iter().copied());
+        let text_corners = self.current_text_elements().iter()
+            .filter(|text_element| !text_element.pinned)
+            .flat_map(Self::text_line_rects)
+            .flat_map(|rect| [rect.min, rect.max]);
+        let points: Vec<egui::Pos2> = stroke_points.chain(text_corners).collect();
+        (!points.is_empty()).then(|| egui::Rect::from_points(&points))
+    }
+    
+    // Scrollbars along the bottom and right of the canvas spanning the content plus a margin and the current
+    // view. Dragging a thumb pans the camera; returns the thumbs to paint once the page is drawn.
+    fn canvas_scrollbars(&mut self, ui: &mut egui::Ui, canvas_rect: egui::Rect) -> Vec<(egui::Rect, bool)> {
+        // Measured on screen, so the bars follow the view's rotation
+        let camera = self.current_page().camera;
+        let view = canvas_rect;
+        let Some(content) = self.content_rect() else {
+            return Vec::new();
+        };
+        let region = camera.rect_to_screen(content.expand(SCROLL_REGION_MARGIN)).union(view);
+        
+        let mut thumbs = Vec::new();
+        for axis in 0..2 {
+            let (view_range, region_range) = if axis == 0 {
+                (view.x_range(), region.x_range())
+            } else {
+                (view.y_range(), region.y_range())
+            };
+            // Nothing to scroll to along this axis
+            if region_range.span() <= view_range.span() + 1.0 {
+                continue;
             }
-        } else {
-            let query_lower = self.search_query.to_lowercase();
-            let text_lower = text.to_lowercase();
-            let mut start = 0;
             
-            while let Some(pos) = text_lower[start..].find(&query_lower) {
-                let actual_pos = start + pos;
-                positions.push((actual_pos, actual_pos + self.search_query.len()));
-                start = actual_pos + 1;
+            let track = if axis == 0 {
+                egui::Rect::from_min_max(
+                    egui::pos2(canvas_rect.left(), canvas_rect.bottom() - SCROLLBAR_THICKNESS),
+                    egui::pos2(canvas_rect.right() - SCROLLBAR_THICKNESS, canvas_rect.bottom()),
+                )
+            } else {
+                egui::Rect::from_min_max(
+                    egui::pos2(canvas_rect.right() - SCROLLBAR_THICKNESS, canvas_rect.top()),
+                    egui::pos2(canvas_rect.right(), canvas_rect.bottom() - SCROLLBAR_THICKNESS),
+                )
+            };
+            let track_range = if axis == 0 { track.x_range() } else { track.y_range() };
+            let to_track = |value: f32| track_range.min + (value - region_range.min) / region_range.span() * track_range.span();
+            let thumb_range = egui::Rangef::new(to_track(view_range.min), to_track(view_range.max));
+            let thumb = if axis == 0 {
+                egui::Rect::from_x_y_ranges(thumb_range, track.y_range())
+            } else {
+                egui::Rect::from_x_y_ranges(track.x_range(), thumb_range)
+            };
+            
+            let response = ui.interact(thumb, ui.id().with(("canvas_scrollbar", axis)), egui::Sense::drag());
+            if response.dragged() {
+                let mut delta = egui::Vec2::ZERO;
+                delta[axis] = -response.drag_delta()[axis] * region_range.span() / track_range.span();
+                self.current_page_mut().camera.pan(delta);
+            }
+            thumbs.push((thumb, response.hovered() || response.dragged()));
+        }
+        thumbs
+    }
+    
+    // Estimated canvas rectangles of a text element's non-empty lines, or columns for vertical text
+    fn text_line_rects(text_element: &TextElement) -> Vec<egui::Rect> {
+        let font_size = text_element.font_size;
+        if text_element.orientation.is_vertical() {
+            let column_width = font_size * scribble_core::VERTICAL_COLUMN_RATIO;
+            let columns = text_element.text.lines().count();
+            return text_element.text.lines().enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(line_idx, line)| {
+                    let column = if text_element.orientation == TextOrientation::VerticalRl { columns - 1 - line_idx } else { line_idx };
+                    egui::Rect::from_min_size(
+                        text_element.position + egui::Vec2::new(column as f32 * column_width, 0.0),
+                        egui::Vec2::new(column_width, line.chars().count() as f32 * font_size * scribble_core::VERTICAL_ADVANCE_RATIO),
+                    )
+                })
+                .collect();
+        }
+        let line_height = font_size * 1.2;
+        text_element.text.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(line_idx, line)| egui::Rect::from_min_size(
+                egui::Pos2::new(text_element.position.x, text_element.position.y + line_idx as f32 * line_height),
+                egui::Vec2::new(line.len() as f32 * text_element.font_size * 0.6, text_element.font_size),
+            ))
+            .collect()
+    }
+    
+    // Keep only the first of any strokes that retrace each other, returning how many were removed
+    fn remove_duplicate_strokes(&mut self) -> usize {
+        let strokes = self.current_strokes();
+        let mut duplicate = vec![false; strokes.len()];
+        for i in 0..strokes.len() {
+            if duplicate[i] {
+                continue;
+            }
+            for j in i + 1..strokes.len() {
+                if !duplicate[j] && strokes_nearly_identical(&strokes[i], &strokes[j], DUPLICATE_STROKE_TOLERANCE) {
+                    duplicate[j] = true;
+                }
             }
         }
         
-        positions
+        let removed = duplicate.iter().filter(|&&d| d).count();
+        if removed > 0 {
+            self.push_undo("Remove duplicate strokes");
+            let mut flags = duplicate.into_iter();
+            self.current_strokes_mut().retain(|_| !flags.next().unwrap_or(false));
+            self.clear_selection();
+        }
+        removed
     }
     
-    fn draw_arrows_for_matches(&self, painter: &egui::Painter, text_pos: egui::Pos2, text: &str, font_size: f32) {
-        let positions = self.get_match_positions(text);
-        if positions.is_empty() {
+    fn recolor_selection(&mut self, color: egui::Color32) {
+        self.push_undo("Recolor strokes");
+        let selected_indices = self.selected_strokes.clone();
+        for stroke_idx in selected_indices {
+            if let Some(stroke) = self.current_strokes_mut().get_mut(stroke_idx) {
+                stroke.color = color;
+                stroke.colors.clear(); // A recolored gradient stroke becomes solid
+            }
+        }
+    }
+    
+    // Thicken or thin every selected stroke by the same factor
+    fn scale_selection_width(&mut self, factor: f32) {
+        if self.selected_strokes.is_empty() {
             return;
         }
+        self.push_undo("Scale stroke width");
+        let selected_indices = self.selected_strokes.clone();
+        for stroke_idx in selected_indices {
+            if let Some(stroke) = self.current_strokes_mut().get_mut(stroke_idx) {
+                stroke.width = (stroke.width * factor).clamp(MIN_STROKE_WIDTH, MAX_STROKE_WIDTH);
+            }
+        }
+    }
+    
+    fn copy_selected_text_to_clipboard(&mut self) -> bool {
+        if self.selected_text_elements.is_empty() {
+            return false;
+        }
         
-        let font_id = egui::FontId::proportional(font_size);
-        
-        // Split text into lines to handle multiline positioning
-        let lines: Vec<&str> = text.lines().collect();
-        let line_height = painter.layout_no_wrap(
-            "Ag".to_string(), // Sample text to measure line height
-            font_id.clone(),
-            egui::Color32::WHITE,
-        ).size().y;
-        
-        for (start_char, end_char) in positions {
-            // Find which line the match is on and position within that line
-            let mut char_count = 0;
-            let mut match_line = 0;
-            let mut match_start_in_line = start_char;
-            let mut match_end_in_line = end_char;
-            
-            // Find the line containing the match
-            for (line_idx, line) in lines.iter().enumerate() {
-                let line_len = line.len() + 1; // +1 for newline character
-                if char_count + line_len > start_char {
-                    match_line = line_idx;
-                    match_start_in_line = start_char - char_count;
-                    match_end_in_line = end_char - char_count;
-                    break;
+        let mut combined_text = String::new();
+        for &text_idx in &self.selected_text_elements {
+            if let Some(text_element) = self.current_text_elements().get(text_idx) {
+                if !combined_text.is_empty() {
+                    combined_text.push('\n');
                 }
-                char_count += line_len;
-            }
-            
-            // Ensure we don't go beyond the line boundary
-            if match_line < lines.len() {
-                let current_line = lines[match_line];
-                match_end_in_line = match_end_in_line.min(current_line.len());
-                
-                // Calculate positions within the specific line
-                let line_y = text_pos.y + (match_line as f32 * line_height);
-                let before_match = &current_line[..match_start_in_line];
-                let match_text = &current_line[match_start_in_line..match_end_in_line];
-                
-                // Measure text to get horizontal positions
-                let before_galley = painter.layout_no_wrap(
-                    before_match.to_string(),
-                    font_id.clone(),
-                    egui::Color32::WHITE,
-                );
-                let match_galley = painter.layout_no_wrap(
-                    match_text.to_string(),
-                    font_id.clone(),
-                    egui::Color32::WHITE,
-                );
-                
-                let match_start_x = text_pos.x + before_galley.size().x;
-                let match_end_x = match_start_x + match_galley.size().x;
-                let match_center_x = (match_start_x + match_end_x) / 2.0;
-                let text_bottom = line_y + match_galley.size().y;
-                
-                // Draw arrows pointing to the match on the correct line
-                self.draw_pointing_arrows(painter, match_center_x, text_bottom, match_galley.size().x);
+                combined_text.push_str(&text_element.text);
             }
         }
+        
+        if let Some(ref mut clipboard) = self.clipboard
+            && let Ok(()) = clipboard.set_text(combined_text)
+        {
+            return true;
+        }
+        false
     }
     
-    fn draw_pointing_arrows(&self, painter: &egui::Painter, center_x: f32, text_bottom: f32, match_width: f32) {
-        // Always use dark orange for arrows
-        let arrow_color = egui::Color32::from_rgb(200, 80, 0); // Dark orange arrows
-        
-        let arrow_length = 15.0;
-        let arrow_gap = 5.0;
+    // Measured bounds of a text element in its own space (canvas, or pinned space for pinned text)
+    fn text_bounds(&self, ctx: &egui::Context, text_element: &TextElement) -> egui::Rect {
+        let size = if text_element.orientation.is_vertical() {
+            egui::Vec2::from(scribble_core::vertical_text_size(&text_element.text, text_element.font_size))
+        } else {
+            self.text_size(ctx, &text_element.text, text_element.font_size)
+        };
+        egui::Rect::from_min_size(text_element.position, size)
+    }
+    
+    // Screen rectangle of a lone selected text element, whose handles resize its font
+    fn resizable_text_rect(&self, ctx: &egui::Context) -> Option<(usize, egui::Rect)> {
+        let [idx] = self.selected_text_elements[..] else {
+            return None;
+        };
+        if !self.selected_strokes.is_empty() {
+            return None;
+        }
+        let text_element = self.current_text_elements().get(idx)?;
+        Some((idx, self.text_camera(text_element).rect_to_screen(self.text_bounds(ctx, text_element))))
+    }
+    
+    // Screen rectangle around any other selection, whose handles scale everything in it
+    fn scalable_selection_rect(&self, ctx: &egui::Context) -> Option<egui::Rect> {
+        if self.resizable_text_rect(ctx).is_some() {
+            return None;
+        }
+        let camera = self.current_page().camera;
+        let strokes = self.selected_strokes.iter()
+            .filter_map(|&idx| self.current_strokes().get(idx))
+            .filter(|stroke| stroke.is_drawable())
+            .map(|stroke| camera.rect_to_screen(egui::Rect::from_points(&stroke.points)));
+        let texts = self.selected_text_elements.iter()
+            .filter_map(|&idx| self.current_text_elements().get(idx))
+            .map(|text_element| self.text_camera(text_element).rect_to_screen(self.text_bounds(ctx, text_element)));
+        strokes.chain(texts).reduce(|a, b| a.union(b))
+    }
+    
+    // Begin scaling if the pointer is on a corner handle of the selection's bounds
+    fn start_selection_resize(&mut self, ctx: &egui::Context, screen_pos: egui::Pos2) -> bool {
+        let Some(rect) = self.scalable_selection_rect(ctx) else {
+            return false;
+        };
+        let Some(corner) = corner_handle_at(rect, screen_pos) else {
+            return false;
+        };
         
-        // Try different arrow positions to avoid collisions
-        let text_top = text_bottom - 20.0; // Approximate text height
-        let text_center_y = text_top + 10.0; // Center of text
-        let arrow_positions = [
-            ("bottom", center_x, text_bottom + arrow_gap),  // Below (pointing up)
-            ("top", center_x, text_top - arrow_gap - arrow_length), // Above (pointing down)
-            ("left", center_x - match_width / 2.0 - arrow_gap - arrow_length, text_center_y), // Left (pointing right)
-            ("right", center_x + match_width / 2.0 + arrow_gap, text_center_y), // Right (pointing left)
-        ];
+        let strokes = self.selected_strokes.iter()
+            .filter_map(|&idx| Some((idx, self.current_strokes().get(idx)?.points.clone())))
+            .collect();
+        let texts = self.selected_text_elements.iter()
+            .filter_map(|&idx| {
+                let text_element = self.current_text_elements().get(idx)?;
+                Some((idx, text_element.position, text_element.font_size, text_element.shadow.map(|(offset, _)| offset)))
+            })
+            .collect();
+        let anchor = rect.center() - (corner - rect.center());
+        self.push_undo("Scale selection");
+        self.selection_resize = Some(SelectionResize {
+            anchor,
+            start_diagonal: corner - anchor,
+            strokes,
+            texts,
+        });
+        true
+    }
+    
+    // Scale the selection so the dragged corner follows the pointer, keeping the opposite corner
+    // where it was. Each axis scales on its own, or with `uniform` both follow the pointer's
+    // progress along the original diagonal and text fonts scale too.
+    fn update_selection_resize(&mut self, screen_pos: egui::Pos2, uniform: bool) {
+        let Some(resize) = &self.selection_resize else {
+            return;
+        };
+        let diagonal = resize.start_diagonal;
+        let reach = screen_pos - resize.anchor;
+        let scale = if uniform {
+            let diagonal_length_sq = diagonal.length_sq();
+            if diagonal_length_sq <= f32::EPSILON {
+                return;
+            }
+            egui::Vec2::splat(reach.dot(diagonal) / diagonal_length_sq)
+        } else {
+            // A flat selection, like one horizontal line, keeps its size across the flat axis
+            let axis_scale = |reach: f32, diagonal: f32| if diagonal.abs() < 1.0 { 1.0 } else { reach / diagonal };
+            egui::Vec2::new(axis_scale(reach.x, diagonal.x), axis_scale(reach.y, diagonal.y))
+        };
+        let scale = scale.max(egui::Vec2::splat(MIN_SELECTION_SCALE));
+        let anchor = resize.anchor;
+        let scaled = |camera: Camera, pos: egui::Pos2| camera.to_canvas(anchor + (camera.to_screen(pos) - anchor) * scale);
         
-        let mut arrow_drawn = false;
+        let camera = self.current_page().camera;
+        let strokes: Vec<(usize, Vec<egui::Pos2>)> = resize.strokes.iter()
+            .map(|(idx, points)| (*idx, points.iter().map(|&point| scaled(camera, point)).collect()))
+            .collect();
+        let texts: Vec<(usize, egui::Pos2, f32, Option<egui::Vec2>)> = resize.texts.iter()
+            .filter_map(|&(idx, position, font_size, shadow_offset)| {
+                let text_camera = self.text_camera(self.current_text_elements().get(idx)?);
+                let position = scaled(text_camera, position);
+                if uniform {
+                    let font_size = (font_size * scale.x).max(MIN_TEXT_FONT_SIZE);
+                    Some((idx, position, font_size, shadow_offset.map(|offset| offset * scale.x)))
+                } else {
+                    Some((idx, position, font_size, shadow_offset))
+                }
+            })
+            .collect();
         
-        for (arrow_type, arrow_x, arrow_y) in arrow_positions {
-            if !self.check_arrow_collision_at_position(arrow_x, arrow_y, arrow_length) {
-                match arrow_type {
-                    "bottom" => self.draw_bottom_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
-                    "top" => self.draw_top_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
-                    "left" => self.draw_left_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
-                    "right" => self.draw_right_arrow(painter, arrow_x, arrow_y, arrow_length, arrow_color),
-                    _ => {}
+        for (idx, points) in strokes {
+            if let Some(stroke) = self.current_strokes_mut().get_mut(idx) {
+                stroke.points = points;
+            }
+        }
+        for (idx, position, font_size, shadow_offset) in texts {
+            if let Some(text_element) = self.current_text_elements_mut().get_mut(idx) {
+                text_element.position = position;
+                text_element.font_size = font_size;
+                if let (Some((offset, _)), Some(shadow_offset)) = (&mut text_element.shadow, shadow_offset) {
+                    *offset = shadow_offset;
                 }
-                arrow_drawn = true;
-                break;
             }
         }
+    }
+    
+    // Begin resizing if the pointer is on a corner handle of the selected text
+    fn start_text_resize(&mut self, ctx: &egui::Context, screen_pos: egui::Pos2) -> bool {
+        let Some((idx, rect)) = self.resizable_text_rect(ctx) else {
+            return false;
+        };
+        let Some(corner) = corner_handle_at(rect, screen_pos) else {
+            return false;
+        };
         
-        // If no position is collision-free, draw at the original bottom position
-        if !arrow_drawn {
-            self.draw_bottom_arrow(painter, center_x, text_bottom + arrow_gap, arrow_length, arrow_color);
+        let anchor = rect.center() - (corner - rect.center());
+        let text_element = &self.current_text_elements()[idx];
+        let start_position = self.text_camera(text_element).to_screen(text_element.position);
+        self.push_undo("Resize text");
+        self.text_resize = Some(TextResize {
+            index: idx,
+            anchor,
+            start_position,
+            start_diagonal: corner - anchor,
+            start_font_size: self.current_text_elements()[idx].font_size,
+        });
+        true
+    }
+    
+    // Scale the font with the dragged corner's progress along the original diagonal, keeping the
+    // opposite corner where it was
+    fn update_text_resize(&mut self, screen_pos: egui::Pos2) {
+        let Some(resize) = &self.text_resize else {
+            return;
+        };
+        let diagonal_length_sq = resize.start_diagonal.length_sq();
+        if diagonal_length_sq <= f32::EPSILON {
+            return;
         }
         
-        // Draw side arrows if the match is wide enough and we used the bottom position
-        if match_width > 30.0 && !arrow_drawn {
-            let side_offset = match_width / 3.0;
-            
-            // Left side arrow
-            let left_center = center_x - side_offset;
-            self.draw_bottom_arrow(painter, left_center, text_bottom + arrow_gap, arrow_length * 0.7, arrow_color);
-            
-            // Right side arrow
-            let right_center = center_x + side_offset;
-            self.draw_bottom_arrow(painter, right_center, text_bottom + arrow_gap, arrow_length * 0.7, arrow_color);
+        let factor = (screen_pos - resize.anchor).dot(resize.start_diagonal) / diagonal_length_sq;
+        let font_size = (resize.start_font_size * factor).max(MIN_TEXT_FONT_SIZE);
+        let factor = font_size / resize.start_font_size;
+        let screen_position = resize.anchor + (resize.start_position - resize.anchor) * factor;
+        let index = resize.index;
+        
+        let Some(text_element) = self.current_text_elements().get(index) else {
+            return;
+        };
+        let position = self.text_camera(text_element).to_canvas(screen_position);
+        let text_element = &mut self.current_text_elements_mut()[index];
+        if let Some((offset, _)) = &mut text_element.shadow {
+            *offset *= font_size / text_element.font_size;
         }
+        text_element.font_size = font_size;
+        text_element.position = position;
     }
     
-    fn check_arrow_collision_at_position(&self, arrow_x: f32, arrow_y: f32, arrow_length: f32) -> bool {
-        // Create a slightly larger area around the arrow for collision detection
-        let collision_padding = 2.0;
-        let arrow_area = egui::Rect::from_center_size(
-            egui::Pos2::new(arrow_x, arrow_y),
-            egui::Vec2::new(arrow_length + collision_padding * 2.0, arrow_length + collision_padding * 2.0),
-        );
-        
-        // Only check for collisions with other text elements (not the one being searched)
-        for (text_idx, text_element) in self.current_text_elements().iter().enumerate() {
-            // Skip text elements that are search results (we want to point to them)
-            if self.search_results.contains(&text_idx) {
-                continue;
+    // Canvas bounds of each visible element, either the selected ones or the rest. Pinned text
+    // is left out since it doesn't live in canvas space.
+    fn element_rects(&self, selected: bool) -> Vec<egui::Rect> {
+        let strokes = self.current_strokes().iter().enumerate()
+            .filter(|(idx, stroke)| self.selected_strokes.contains(idx) == selected
+                && !self.hidden_by_tag_filter(&stroke.tag) && !self.hidden_by_build(stroke.build_order))
+            .filter(|(_, stroke)| stroke.is_drawable())
+            .map(|(_, stroke)| egui::Rect::from_points(&stroke.points));
+        let texts = self.current_text_elements().iter().enumerate()
+            .filter(|(idx, text_element)| self.selected_text_elements.contains(idx) == selected && !text_element.pinned
+                && !self.hidden_by_tag_filter(&text_element.tag) && !self.hidden_by_build(text_element.build_order))
+            .filter_map(|(_, text_element)| Self::text_line_rects(text_element).into_iter().reduce(|a, b| a.union(b)));
+        strokes.chain(texts).collect()
+    }
+    
+    // Bounds of the visible, unpinned text element nearest a canvas position, if one is within attaching distance
+    fn text_box_near(&self, pos: egui::Pos2) -> Option<egui::Rect> {
+        let range = TEXT_ATTACH_DISTANCE / self.current_page().camera.zoom;
+        self.current_text_elements().iter()
+            .filter(|text_element| !text_element.pinned
+                && !self.hidden_by_tag_filter(&text_element.tag) && !self.hidden_by_build(text_element.build_order))
+            .filter_map(|text_element| Self::text_line_rects(text_element).into_iter().reduce(|a, b| a.union(b)))
+            .map(|rect| (rect.distance_to_pos(pos), rect))
+            .filter(|(distance, _)| *distance <= range)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, rect)| rect)
+    }
+    
+    // Line or arrow ends dropped on or near text move to its edge, aimed at its center; a line
+    // between two text elements runs center to center like a connector
+    fn attach_shape_to_text(&self, start: egui::Pos2, end: egui::Pos2) -> (egui::Pos2, egui::Pos2) {
+        let gap = scribble_core::CONNECTOR_GAP;
+        match (self.text_box_near(start), self.text_box_near(end)) {
+            (Some(from), Some(to)) if from != to => (attach_to_rect(to.center(), from, gap), attach_to_rect(from.center(), to, gap)),
+            (Some(from), None) => (attach_to_rect(end, from, gap), end),
+            (None, Some(to)) => (start, attach_to_rect(start, to, gap)),
+            _ => (start, end),
+        }
+    }
+    
+    // Canvas area covered by the visible strokes and unpinned text on the current page
+    fn content_extent(&self) -> Option<egui::Rect> {
+        self.element_rects(false).into_iter()
+            .chain(self.element_rects(true))
+            .reduce(|a, b| a.union(b))
+    }
+    
+    // Nudge that lines the moved selection's edges or center up with another element's edges or
+    // center, or with the middle of the other content, along with the guides that caused it
+    // Custom guides are snapped to alongside the other elements, even with smart guides off
+    fn smart_guide_snap(&self, bounds: egui::Rect, smart: bool) -> (egui::Vec2, [Option<f32>; 2]) {
+        let others = if smart { self.element_rects(false) } else { Vec::new() };
+        let content = others.iter().copied().reduce(|a, b| a.union(b));
+        let page = self.current_page();
+        let range = SMART_GUIDE_SNAP_DISTANCE / page.camera.zoom;
+        let mut nudge = egui::Vec2::ZERO;
+        let mut guides = [None, None];
+        for axis in 0..2 {
+            let anchors = |rect: egui::Rect| [rect.min[axis], rect.center()[axis], rect.max[axis]];
+            let custom = if axis == 0 { &page.guides_x } else { &page.guides_y };
+            let candidates = others.iter().flat_map(|&rect| anchors(rect))
+                .chain(content.map(|content| content.center()[axis]))
+                .chain(custom.iter().copied());
+            let closest = candidates
+                .flat_map(|candidate| anchors(bounds).map(|anchor| (candidate, candidate - anchor)))
+                .filter(|(_, distance)| distance.abs() <= range)
+                .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()));
+            if let Some((guide, distance)) = closest {
+                nudge[axis] = distance;
+                guides[axis] = Some(guide);
+            }
+        }
+        (nudge, guides)
+    }
+    
+    fn drag_selected_text(&mut self, current_pos: egui::Pos2) {
+        // Calculate the offset from the initial drag position
+        if let Some(start_pos) = self.selection_start {
+            // Snapping holds the selection back from the pointer; carry that over so it lets go smoothly
+            let pointer_offset = current_pos - start_pos + self.guide_snap_residual;
+            let mut offset = pointer_offset;
+            self.active_guides = [None, None];
+            let has_custom_guides = !self.current_page().guides_x.is_empty() || !self.current_page().guides_y.is_empty();
+            if (self.smart_guides || has_custom_guides)
+                && let Some(bounds) = self.element_rects(true).into_iter().reduce(|a, b| a.union(b))
+            {
+                let (nudge, guides) = self.smart_guide_snap(bounds.translate(pointer_offset), self.smart_guides);
+                offset += nudge;
+                self.active_guides = guides;
+            }
+            // Content kept within the page stops at its edges; a selection larger than the page stays at its top-left
+            if self.settings.keep_within_page
+                && let Some(bounds) = self.element_rects(true).into_iter().reduce(|a, b| a.union(b))
+            {
+                let page = self.page_rect();
+                let moved = bounds.translate(offset);
+                for axis in 0..2 {
+                    offset[axis] += (page.max[axis] - moved.max[axis]).min(0.0).max(page.min[axis] - moved.min[axis]);
+                }
             }
+            self.guide_snap_residual = pointer_offset - offset;
             
-            let lines: Vec<&str> = text_element.text.lines().collect();
-            let font_size = text_element.font_size;
-            let line_height = font_size * 1.2;
+            // Apply offset to all selected text elements
+            // Pinned text lives in screen units, so it moves by the on-screen distance
+            let camera = self.current_page().camera;
+            let selected_indices = self.selected_text_elements.clone();
+            for text_idx in selected_indices {
+                if let Some(text_element) = self.current_text_elements_mut().get_mut(text_idx) {
+                    text_element.position += if text_element.pinned { camera.vec_to_screen(offset) } else { offset };
+                }
+            }
             
-            for (line_idx, line) in lines.iter().enumerate() {
-                if line.trim().is_empty() {
-                    continue;
+            // Selected strokes move along with the text
+            let selected_strokes = self.selected_strokes.clone();
+            for stroke_idx in selected_strokes {
+                if let Some(stroke) = self.current_strokes_mut().get_mut(stroke_idx) {
+                    for point in &mut stroke.points {
+                        *point += offset;
+                    }
                 }
-                
-                let line_y = text_element.position.y + (line_idx as f32) * line_height;
-                let estimated_text_width = line.len() as f32 * font_size * 0.6; // Rough estimation
-                
-                let text_rect = egui::Rect::from_min_size(
-                    egui::Pos2::new(text_element.position.x, line_y),
-                    egui::Vec2::new(estimated_text_width, font_size), // Standard text height
-                );
-                
-                if arrow_area.intersects(text_rect) {
-                    return true;
+            }
+            
+            // Update the drag start position for next frame
+            self.selection_start = Some(current_pos);
+        }
+    }
+    
+    fn new_element_tag(&self) -> Option<String> {
+        let tag = self.active_tag.trim();
+        (!tag.is_empty()).then(|| tag.to_string())
+    }
+    
+    // Whether an element with this tag passes the active filter
+    fn matches_tag_filter(&self, tag: &Option<String>) -> bool {
+        self.tag_filter.is_none() || *tag == self.tag_filter
+    }
+    
+    // Painter for an element under the tag filter: as is, faded, or None when it is hidden
+    fn tag_filtered_painter(&self, painter: &egui::Painter, tag: &Option<String>) -> Option<egui::Painter> {
+        if self.matches_tag_filter(tag) {
+            Some(painter.clone())
+        } else if self.hide_filtered {
+            None
+        } else {
+            let mut painter = painter.clone();
+            painter.multiply_opacity(TAG_FILTER_DIM_OPACITY);
+            Some(painter)
+        }
+    }
+    
+    // Whether focus mode fades an element, given whether it is selected
+    fn dimmed_by_focus(&self, selected: bool) -> bool {
+        self.focus_mode && !selected && self.has_selection()
+    }
+    
+    // Hidden elements can't be picked, selected or erased
+    fn hidden_by_tag_filter(&self, tag: &Option<String>) -> bool {
+        self.hide_filtered && !self.matches_tag_filter(tag)
+    }
+    
+    // Every tag used anywhere in the notebook, for the filter menu
+    fn notebook_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.pages.iter()
+            .flat_map(|page| {
+                let stroke_tags = page.strokes.iter().filter_map(|stroke| stroke.tag.clone());
+                let text_tags = page.text_elements.iter().filter_map(|text_element| text_element.tag.clone());
+                stroke_tags.chain(text_tags)
+            })
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+    
+    // Give every selected element the active tag, or clear their tags when it is empty
+    fn tag_selection(&mut self) {
+        self.push_undo("Tag selection");
+        let tag = self.new_element_tag();
+        for idx in self.selected_strokes.clone() {
+            if let Some(stroke) = self.current_strokes_mut().get_mut(idx) {
+                stroke.tag = tag.clone();
+            }
+        }
+        for idx in self.selected_text_elements.clone() {
+            if let Some(text_element) = self.current_text_elements_mut().get_mut(idx) {
+                text_element.tag = tag.clone();
+            }
+        }
+    }
+    
+    // Elements waiting for a later build step are hidden while presenting
+    fn hidden_by_build(&self, build_order: Option<u32>) -> bool {
+        self.presenting && build_order.is_some_and(|order| order > self.build_step)
+    }
+    
+    // Build steps used on the current page, in any order and possibly repeated
+    fn build_orders(&self) -> impl Iterator<Item = u32> + '_ {
+        let stroke_orders = self.current_strokes().iter().filter_map(|stroke| stroke.build_order);
+        let text_orders = self.current_text_elements().iter().filter_map(|text_element| text_element.build_order);
+        stroke_orders.chain(text_orders)
+    }
+    
+    // Reveal the next build step, moving on to the next page once everything is shown
+    fn advance_build(&mut self) {
+        if let Some(next) = self.build_orders().filter(|&order| order > self.build_step).min() {
+            self.build_step = next;
+        } else if self.is_notebook_mode && self.current_page_index + 1 < self.pages.len() {
+            self.next_page();
+            self.build_step = 0;
+        }
+    }
+    
+    // Step back one build, or to the fully built previous page
+    fn retreat_build(&mut self) {
+        if self.build_step > 0 {
+            self.build_step = self.build_orders().filter(|&order| order < self.build_step).max().unwrap_or(0);
+        } else if self.is_notebook_mode && self.current_page_index > 0 {
+            self.previous_page();
+            self.build_step = self.build_orders().max().unwrap_or(0);
+        }
+    }
+    
+    // Start the selection pulsing, or stop it if every selected element already is
+    fn toggle_selection_pulse(&mut self) {
+        let selected: Vec<ElementRef> = self.selected_strokes.iter().map(|&idx| ElementRef::Stroke(idx))
+            .chain(self.selected_text_elements.iter().map(|&idx| ElementRef::Text(idx)))
+            .collect();
+        if selected.iter().all(|element| self.pulsing.contains(element)) {
+            self.pulsing.retain(|element| !selected.contains(element));
+        } else {
+            for element in selected {
+                if !self.pulsing.contains(&element) {
+                    self.pulsing.push(element);
                 }
             }
         }
-        false
     }
     
-    fn draw_bottom_arrow(&self, painter: &egui::Painter, center_x: f32, arrow_y: f32, arrow_length: f32, color: egui::Color32) {
-        let arrow_tip = egui::Pos2::new(center_x, arrow_y);
-        let arrow_base = egui::Pos2::new(center_x, arrow_y + arrow_length);
-        
-        // Main arrow line
-        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
+    // Keep the selected elements, and connectors between selected text, as a stamp in the settings
+    fn save_selection_as_stamp(&mut self) {
+        let strokes = self.selected_strokes.iter()
+            .filter_map(|&idx| self.current_strokes().get(idx))
+            .map(scribble_core::Stroke::from)
+            .collect();
+        let text_elements = self.selected_text_elements.iter()
+            .filter_map(|&idx| self.current_text_elements().get(idx))
+            .map(scribble_core::TextElement::from)
+            .collect();
+        let mut connectors = self.current_page().connectors.clone();
+        let selected_text = self.selected_text_elements.clone();
+        scribble_core::remap_connectors(&mut connectors, |idx| selected_text.iter().position(|&selected| selected == idx));
         
-        // Arrow head
-        let head_size = 4.0;
-        let left_wing = egui::Pos2::new(center_x - head_size, arrow_y + head_size);
-        let right_wing = egui::Pos2::new(center_x + head_size, arrow_y + head_size);
-        
-        painter.line_segment([arrow_tip, left_wing], egui::Stroke::new(2.0, color));
-        painter.line_segment([arrow_tip, right_wing], egui::Stroke::new(2.0, color));
+        let name = match self.stamp_name_input.trim() {
+            "" => format!("Stamp {}", self.settings.stamps.len() + 1),
+            name => name.to_string(),
+        };
+        let stamp = scribble_core::Stamp::new(name.clone(), strokes, text_elements, connectors);
+        if stamp.is_empty() {
+            return;
+        }
+        self.settings.stamps.push(stamp);
+        self.stamp_name_input.clear();
+        match self.settings.save() {
+            Ok(()) => self.notify(format!("Saved stamp \"{}\"; place it from the ⭐ Stamps menu", name), false),
+            Err(e) => self.notify(format!("Failed to save settings: {}", e), true),
+        }
     }
     
-    fn draw_top_arrow(&self, painter: &egui::Painter, center_x: f32, arrow_y: f32, arrow_length: f32, color: egui::Color32) {
-        let arrow_tip = egui::Pos2::new(center_x, arrow_y + arrow_length);
-        let arrow_base = egui::Pos2::new(center_x, arrow_y);
-        
-        // Main arrow line
-        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
-        
-        // Arrow head (pointing down)
-        let head_size = 4.0;
-        let left_wing = egui::Pos2::new(center_x - head_size, arrow_y + arrow_length - head_size);
-        let right_wing = egui::Pos2::new(center_x + head_size, arrow_y + arrow_length - head_size);
+    // Add a copy of a stamp with its top-left corner at a canvas position, as one undoable step
+    fn place_stamp(&mut self, idx: usize, position: egui::Pos2) {
+        let Some(stamp) = self.settings.stamps.get(idx) else {
+            self.placing_stamp = None;
+            return;
+        };
+        let placed = stamp.placed_at([position.x, position.y]);
         
-        painter.line_segment([arrow_tip, left_wing], egui::Stroke::new(2.0, color));
-        painter.line_segment([arrow_tip, right_wing], egui::Stroke::new(2.0, color));
+        self.push_undo("Place stamp");
+        let text_offset = self.current_text_elements().len();
+        let page = self.current_page_mut();
+        page.strokes.extend(placed.strokes.into_iter().map(Stroke::from));
+        page.text_elements.extend(placed.text_elements.into_iter().map(TextElement::from));
+        page.connectors.extend(placed.connectors.into_iter().map(|connector| scribble_core::Connector {
+            from: connector.from + text_offset,
+            to: connector.to + text_offset,
+            ..connector
+        }));
     }
     
-    fn draw_left_arrow(&self, painter: &egui::Painter, arrow_x: f32, center_y: f32, arrow_length: f32, color: egui::Color32) {
-        let arrow_base = egui::Pos2::new(arrow_x, center_y);
-        let arrow_tip = egui::Pos2::new(arrow_x + arrow_length, center_y);
-        
-        // Main arrow line
-        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
-        
-        // Arrow head (pointing right)
-        let head_size = 4.0;
-        let top_wing = egui::Pos2::new(arrow_x + arrow_length - head_size, center_y - head_size);
-        let bottom_wing = egui::Pos2::new(arrow_x + arrow_length - head_size, center_y + head_size);
-        
-        painter.line_segment([arrow_tip, top_wing], egui::Stroke::new(2.0, color));
-        painter.line_segment([arrow_tip, bottom_wing], egui::Stroke::new(2.0, color));
+    // Opacity a pulsing element is drawn with at this moment, 1.0 for one that isn't pulsing
+    fn pulse_opacity(&self, ctx: &egui::Context, element: ElementRef) -> f32 {
+        if !self.pulsing.contains(&element) {
+            return 1.0;
+        }
+        let phase = (ctx.input(|i| i.time) / PULSE_PERIOD).fract() as f32;
+        let wave = 0.5 + 0.5 * (phase * std::f32::consts::TAU).cos();
+        PULSE_MIN_OPACITY + (1.0 - PULSE_MIN_OPACITY) * wave
     }
     
-    fn draw_right_arrow(&self, painter: &egui::Painter, arrow_x: f32, center_y: f32, arrow_length: f32, color: egui::Color32) {
-        let arrow_base = egui::Pos2::new(arrow_x + arrow_length, center_y);
-        let arrow_tip = egui::Pos2::new(arrow_x, center_y);
-        
-        // Main arrow line
-        painter.line_segment([arrow_base, arrow_tip], egui::Stroke::new(2.0, color));
+    fn set_presenting(&mut self, presenting: bool) {
+        self.presenting = presenting;
+        self.build_step = 0;
+        self.clear_selection();
+        self.close_text_editor();
+    }
+    
+    // Give every selected element a build step, or clear it
+    fn set_selection_build_order(&mut self, build_order: Option<u32>) {
+        self.push_undo("Set build order");
+        for idx in self.selected_strokes.clone() {
+            if let Some(stroke) = self.current_strokes_mut().get_mut(idx) {
+                stroke.build_order = build_order;
+            }
+        }
+        for idx in self.selected_text_elements.clone() {
+            if let Some(text_element) = self.current_text_elements_mut().get_mut(idx) {
+                text_element.build_order = build_order;
+            }
+        }
+    }
+    
+    // Arrange all text elements in a left-aligned column, keeping their top-to-bottom order
+    fn tidy_text_layout(&mut self) {
+        // Pinned text keeps its place on screen
+        let text_elements = self.current_text_elements();
+        if text_elements.iter().all(|t| t.pinned) {
+            return;
+        }
+        let left = text_elements.iter().filter(|t| !t.pinned).map(|t| t.position.x).fold(f32::INFINITY, f32::min);
+        let top = text_elements.iter().filter(|t| !t.pinned).map(|t| t.position.y).fold(f32::INFINITY, f32::min);
         
-        // Arrow head (pointing left)
-        let head_size = 4.0;
-        let top_wing = egui::Pos2::new(arrow_x + head_size, center_y - head_size);
-        let bottom_wing = egui::Pos2::new(arrow_x + head_size, center_y + head_size);
+        // Vector order is left alone so indices held by search and selection stay valid
+        let mut order: Vec<usize> = (0..text_elements.len()).filter(|&idx| !text_elements[idx].pinned).collect();
+        order.sort_by(|&a, &b| text_elements[a].position.y.total_cmp(&text_elements[b].position.y));
         
-        painter.line_segment([arrow_tip, top_wing], egui::Stroke::new(2.0, color));
-        painter.line_segment([arrow_tip, bottom_wing], egui::Stroke::new(2.0, color));
+        self.push_undo("Tidy text");
+        let mut y = top;
+        for idx in order {
+            let text_element = &mut self.current_text_elements_mut()[idx];
+            text_element.position = egui::Pos2::new(left, y);
+            let line_count = text_element.text.lines().count().max(1);
+            y += line_count as f32 * text_element.font_size * 1.2 + TIDY_TEXT_SPACING;
+        }
     }
     
-    fn update_text_selection(&mut self) {
-        self.selected_text_elements.clear();
+    // === UNDO / REDO ===
+    
+    // Record the state before an undoable change. Repeated changes with the same label
+    // while the pointer stays down (e.g. dragging a color picker) collapse into one entry.
+    fn push_undo(&mut self, label: &str) {
+        // Every undoable change is an unsaved change, and may have changed the text being searched
+        // or moved the elements that were pulsing
+        self.dirty = true;
+        self.search_index_stale = true;
+        self.pulsing.clear();
+        if self.undo_coalescing && self.undo_stack.last().is_some_and(|entry| entry.label == label) {
+            return;
+        }
         
-        if let (Some(start), Some(end)) = (self.selection_start, self.selection_end) {
-            let selection_rect = egui::Rect::from_two_pos(start, end);
-            
-            let text_elements = self.current_text_elements().clone();
-            for (idx, text_element) in text_elements.iter().enumerate() {
-                let lines: Vec<&str> = text_element.text.lines().collect();
-                let font_size = text_element.font_size;
-                let line_height = font_size * 1.2;
-                
-                for (line_idx, line) in lines.iter().enumerate() {
-                    if line.trim().is_empty() {
-                        continue;
-                    }
-                    
-                    let line_y = text_element.position.y + (line_idx as f32) * line_height;
-                    let estimated_text_width = line.len() as f32 * font_size * 0.6;
-                    
-                    let text_rect = egui::Rect::from_min_size(
-                        egui::Pos2::new(text_element.position.x, line_y),
-                        egui::Vec2::new(estimated_text_width, font_size),
-                    );
-                    
-                    if selection_rect.intersects(text_rect) && !self.selected_text_elements.contains(&idx) {
-                        self.selected_text_elements.push(idx);
-                        break; // Only need to add the text element once
-                    }
-                }
-            }
+        self.undo_stack.push(UndoEntry {
+            label: label.to_string(),
+            pages: self.pages.clone(),
+            current_page_index: self.current_page_index,
+            canvas_size: self.canvas_size,
+        });
+        if self.undo_stack.len() > MAX_UNDO_HISTORY {
+            self.undo_stack.remove(0);
         }
+        self.redo_stack.clear();
+        self.undo_coalescing = true;
     }
     
-    fn get_text_element_at_position(&self, pos: egui::Pos2) -> Option<usize> {
-        for (idx, text_element) in self.current_text_elements().iter().enumerate() {
-            let lines: Vec<&str> = text_element.text.lines().collect();
-            let font_size = text_element.font_size;
-            let line_height = font_size * 1.2;
-            
-            for (line_idx, line) in lines.iter().enumerate() {
-                if line.trim().is_empty() {
-                    continue;
-                }
-                
-                let line_y = text_element.position.y + (line_idx as f32) * line_height;
-                let estimated_text_width = line.len() as f32 * font_size * 0.6;
-                
-                let text_rect = egui::Rect::from_min_size(
-                    egui::Pos2::new(text_element.position.x, line_y),
-                    egui::Vec2::new(estimated_text_width, font_size),
-                );
-                
-                if text_rect.contains(pos) {
-                    return Some(idx);
-                }
-            }
+    fn undo(&mut self) {
+        if let Some(entry) = self.undo_stack.pop() {
+            let current = self.restore_snapshot(entry);
+            self.redo_stack.push(current);
         }
-        None
     }
     
-    fn copy_selected_text_to_clipboard(&mut self) -> bool {
-        if self.selected_text_elements.is_empty() {
-            return false;
+    fn redo(&mut self) {
+        if let Some(entry) = self.redo_stack.pop() {
+            let current = self.restore_snapshot(entry);
+            self.undo_stack.push(current);
         }
-        
-        let mut combined_text = String::new();
-        for &text_idx in &self.selected_text_elements {
-            if let Some(text_element) = self.current_text_elements().get(text_idx) {
-                if !combined_text.is_empty() {
-                    combined_text.push('\n');
-                }
-                combined_text.push_str(&text_element.text);
-            }
+    }
+    
+    // Undo or redo until exactly `applied` of the recorded actions are in effect
+    fn jump_to_history(&mut self, applied: usize) {
+        while self.undo_stack.len() > applied {
+            self.undo();
         }
-        
-        if let Some(ref mut clipboard) = self.clipboard {
-            if let Ok(()) = clipboard.set_text(combined_text) {
-                return true;
-            }
+        while self.undo_stack.len() < applied && !self.redo_stack.is_empty() {
+            self.redo();
         }
-        false
     }
     
-    fn drag_selected_text(&mut self, current_pos: egui::Pos2) {
-        // Calculate the offset from the initial drag position
-        if let Some(start_pos) = self.selection_start {
-            let offset = current_pos - start_pos;
-            
-            // Apply offset to all selected text elements
-            let selected_indices = self.selected_text_elements.clone();
-            for text_idx in selected_indices {
-                if let Some(text_element) = self.current_text_elements_mut().get_mut(text_idx) {
-                    text_element.position = text_element.position + offset;
-                }
-            }
-            
-            // Update the drag start position for next frame
-            self.selection_start = Some(current_pos);
+    // Swap in a snapshot and return the state it replaced under the same label
+    fn restore_snapshot(&mut self, entry: UndoEntry) -> UndoEntry {
+        self.dirty = true;
+        self.search_index_stale = true;
+        self.pulsing.clear();
+        let mut pages = entry.pages;
+        
+        // Undo changes content, not where each page is being viewed
+        for (page, current) in pages.iter_mut().zip(&self.pages) {
+            page.camera = current.camera;
         }
+        
+        let replaced = UndoEntry {
+            label: entry.label,
+            pages: std::mem::replace(&mut self.pages, pages),
+            current_page_index: self.current_page_index,
+            canvas_size: std::mem::replace(&mut self.canvas_size, entry.canvas_size),
+        };
+        
+        // An element being edited may no longer exist
+        if self.editing_text.is_some() {
+            self.close_text_editor();
+        }
+        
+        // Jump to the page the change was made on
+        self.current_page_index = entry.current_page_index.min(self.pages.len() - 1);
+        self.current_stroke.clear();
+        self.is_drawing = false;
+        self.clear_selection();
+        self.perform_search();
+        replaced
     }
     
     // === FILE OPERATIONS ===
     
-    fn save_project(&self) -> Result<(), Box<dyn std::error::Error>> {
+    fn save_project(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(path) = rfd::FileDialog::new()
             .add_filter("Scribble Project", &["scribble"])
             .set_file_name("my_drawing.scribble")
@@ -682,45 +4294,24 @@ impl ScribbleApp {
         {
             if self.is_notebook_mode {
                 // Save as notebook
-                let notebook = ScribbleNotebook {
-                    pages: self.pages.iter().map(|p| SerializablePage {
-                        name: p.name.clone(),
-                        strokes: p.strokes.iter().map(|s| SerializableStroke {
-                            points: s.points.iter().map(|pos| (pos.x, pos.y)).collect(),
-                            color: (s.color.r(), s.color.g(), s.color.b()),
-                            width: s.width,
-                        }).collect(),
-                        text_elements: p.text_elements.iter().map(|t| SerializableTextElement {
-                            position: (t.position.x, t.position.y),
-                            text: t.text.clone(),
-                            font_size: t.font_size,
-                        }).collect(),
-                    }).collect(),
-                    current_page_index: self.current_page_index,
-                    canvas_size: (800.0, 600.0),
-                };
-                
-                let json = serde_json::to_string_pretty(&notebook)?;
-                fs::write(path, json)?;
+                let mut notebook = scribble_core::Notebook::new();
+                notebook.pages = self.pages.iter().map(scribble_core::Page::from).collect();
+                notebook.current_page_index = self.current_page_index;
+                notebook.text_font_size = self.notebook_font_size;
+                notebook.meta = self.notebook_meta.clone();
+                notebook.canvas_size = [self.canvas_size.x, self.canvas_size.y];
+                notebook.save(path)?;
+                self.notebook_meta = notebook.meta;
             } else {
                 // Save as single page project (backwards compatibility)
-                let project = ScribbleProject {
-                    strokes: self.current_strokes().iter().map(|s| SerializableStroke {
-                        points: s.points.iter().map(|p| (p.x, p.y)).collect(),
-                        color: (s.color.r(), s.color.g(), s.color.b()),
-                        width: s.width,
-                    }).collect(),
-                    text_elements: self.current_text_elements().iter().map(|t| SerializableTextElement {
-                        position: (t.position.x, t.position.y),
-                        text: t.text.clone(),
-                        font_size: t.font_size,
-                    }).collect(),
-                    canvas_size: (800.0, 600.0), // Default canvas size
-                };
-                
-                let json = serde_json::to_string_pretty(&project)?;
-                fs::write(path, json)?;
+                let mut project = scribble_core::Project::new();
+                project.strokes = self.current_strokes().iter().map(scribble_core::Stroke::from).collect();
+                project.text_elements = self.current_text_elements().iter().map(scribble_core::TextElement::from).collect();
+                project.connectors = self.current_page().connectors.clone();
+                project.canvas_size = [self.canvas_size.x, self.canvas_size.y];
+                project.save(path)?;
             }
+            self.dirty = false;
         }
         Ok(())
     }
@@ -728,361 +4319,508 @@ impl ScribbleApp {
     fn load_project(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(path) = rfd::FileDialog::new()
             .add_filter("Scribble Project", &["scribble"])
+            .add_filter("All Files", &["*"])
             .pick_file()
         {
-            let json = fs::read_to_string(path)?;
-            
-            // Try to load as notebook first
-            if let Ok(notebook) = serde_json::from_str::<ScribbleNotebook>(&json) {
-                // Clear current state
-                self.pages.clear();
-                self.current_stroke.clear();
-                self.is_drawing = false;
-                self.selected_text_elements.clear();
-                self.is_selecting_text = false;
-                self.selection_start = None;
-                self.selection_end = None;
-                self.search_results.clear();
-                self.search_query.clear();
-                
-                // Load notebook
-                self.pages = notebook.pages.into_iter().map(|p| Page {
-                    name: p.name,
-                    strokes: p.strokes.into_iter().map(|s| Stroke {
-                        points: s.points.into_iter().map(|(x, y)| egui::Pos2::new(x, y)).collect(),
-                        color: egui::Color32::from_rgb(s.color.0, s.color.1, s.color.2),
-                        width: s.width,
-                    }).collect(),
-                    text_elements: p.text_elements.into_iter().map(|t| TextElement {
-                        position: egui::Pos2::new(t.position.0, t.position.1),
-                        text: t.text,
-                        font_size: t.font_size,
-                    }).collect(),
-                }).collect();
-                
+            self.load_project_from_path(&path)?;
+        }
+        Ok(())
+    }
+    
+    // Text shown on an empty canvas: the tool's instructions, plus a pointer to notebooks for a lone
+    // page or to page navigation inside a notebook
+    fn empty_canvas_hint(&self, ctx: &egui::Context) -> String {
+        let instructions = self.current_tool.instructions();
+        if !self.settings.show_empty_hints {
+            return instructions.to_string();
+        }
+        if !self.is_notebook_mode {
+            return format!("{}\n\nWorking on something longer? 📖 Create Notebook gives you several pages.", instructions);
+        }
+        
+        let page_number = self.current_page_index + 1;
+        let navigation = format!(
+            "{} / {} switch pages, {} adds one.",
+            ctx.format_shortcut(&SHORTCUT_PREVIOUS_PAGE),
+            ctx.format_shortcut(&SHORTCUT_NEXT_PAGE),
+            ctx.format_shortcut(&SHORTCUT_ADD_PAGE),
+        );
+        if self.pages.iter().all(|page| page.strokes.is_empty() && page.text_elements.is_empty()) {
+            format!("Page {} of your new {}-page notebook\n\n{}\n\n{}", page_number, self.pages.len(), instructions, navigation)
+        } else {
+            format!("Page {} of {} is empty\n\n{}\n\n{}", page_number, self.pages.len(), instructions, navigation)
+        }
+    }
+    
+    fn export_options(&self) -> export::ExportOptions {
+        export::ExportOptions {
+            margin: self.settings.export_margin,
+            wrap_width: self.settings.export_wrap_width,
+            curve_spacing: self.settings.export_curve_spacing,
+            png_text: self.settings.png_text_style,
+            svg_curves: self.settings.svg_curves,
+            watermark: self.settings.watermark.then(|| export::Watermark {
+                text: self.settings.watermark_text.clone(),
+                position: self.settings.watermark_position,
+            }),
+        }
+    }
+    
+    // Place the contents of a text file on the page as a new text element near the top-left of the view
+    fn import_text_file(&mut self, ctx: &egui::Context) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Text", &["txt", "md"])
+            .pick_file()
+        else {
+            return Ok(());
+        };
+        
+        let contents = fs::read_to_string(&path)?;
+        if contents.trim().is_empty() {
+            self.notify(format!("{} is empty", path.display()), false);
+            return Ok(());
+        }
+        self.insert_text_block(ctx, contents, false);
+        Ok(())
+    }
+    
+    // Lay out a CSV or TSV file as a grid of text elements near the top-left of the view
+    fn import_table_file(&mut self, ctx: &egui::Context) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Table", &["csv", "tsv", "txt"])
+            .add_filter("All Files", &["*"])
+            .pick_file()
+        else {
+            return Ok(());
+        };
+        
+        let contents = fs::read_to_string(&path)?;
+        self.insert_table(ctx, &contents);
+        Ok(())
+    }
+    
+    // Each cell becomes a text element in the current text style. Columns are as wide as their widest
+    // cell and rows as tall as their tallest, with the configured spacing between them; one undo step
+    // removes the whole table.
+    fn insert_table(&mut self, ctx: &egui::Context, contents: &str) {
+        let rows = parse_table(contents);
+        let cell_count = rows.iter().flatten().filter(|cell| !cell.trim().is_empty()).count();
+        if cell_count == 0 {
+            self.notify("No table cells found", false);
+            return;
+        }
+        if cell_count > MAX_TABLE_CELLS {
+            self.notify(format!("Tables can have at most {} cells; this one has {}", MAX_TABLE_CELLS, cell_count), true);
+            return;
+        }
+        
+        let font_size = self.text_font_size;
+        let sizes: Vec<Vec<egui::Vec2>> = rows.iter()
+            .map(|row| row.iter().map(|cell| self.text_size(ctx, cell.trim(), font_size)).collect())
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut column_widths = vec![0.0f32; columns];
+        for row_sizes in &sizes {
+            for (column, size) in row_sizes.iter().enumerate() {
+                column_widths[column] = column_widths[column].max(size.x);
+            }
+        }
+        
+        let spacing = self.settings.table_cell_spacing;
+        let origin = self.current_page().camera.to_canvas(self.canvas_origin + egui::Vec2::splat(IMPORTED_TEXT_MARGIN));
+        self.close_text_editor();
+        self.push_undo("Import table");
+        let mut y = origin.y;
+        for (row, row_sizes) in rows.iter().zip(&sizes) {
+            let mut x = origin.x;
+            for (column, cell) in row.iter().enumerate() {
+                if !cell.trim().is_empty() {
+                    let text_element = self.new_text_element(egui::Pos2::new(x, y), cell.trim().to_string());
+                    self.current_text_elements_mut().push(text_element);
+                }
+                x += column_widths[column] + spacing;
+            }
+            y += row_sizes.iter().map(|size| size.y).fold(font_size, f32::max) + spacing;
+        }
+        self.remember_font_size();
+        self.notify(format!("Imported a table of {} rows and {} columns", rows.len(), columns), false);
+    }
+    
+    // Add pasted, dropped or imported text as a new element. With `at_pointer` (and the setting on) it is
+    // centered under the pointer; otherwise it goes near the top-left of the view.
+    fn insert_text_block(&mut self, ctx: &egui::Context, mut contents: String, at_pointer: bool) {
+        if let Some((cut, _)) = contents.char_indices().nth(MAX_IMPORTED_TEXT_CHARS) {
+            contents.truncate(cut);
+            self.notify(format!("Text was cut to the first {} characters", MAX_IMPORTED_TEXT_CHARS), false);
+        }
+        let contents = contents.replace("\r\n", "\n");
+        
+        let camera = self.current_page().camera;
+        let pointer = self.canvas_pointer.filter(|_| at_pointer && self.settings.paste_at_cursor);
+        let position = match pointer {
+            Some(pointer) => {
+                camera.to_canvas(pointer) - self.text_size(ctx, &contents, self.text_font_size) / 2.0
+            }
+            None => camera.to_canvas(self.canvas_origin + egui::Vec2::splat(IMPORTED_TEXT_MARGIN)),
+        };
+        self.close_text_editor();
+        self.text_input = contents;
+        self.commit_text_input(position);
+    }
+    
+    // Add another file's pages after the current ones, keeping everything already open
+    fn append_notebook(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Scribble Project", &["scribble"])
+            .add_filter("All Files", &["*"])
+            .pick_file()
+        else {
+            return Ok(());
+        };
+        
+        let mut incoming: Vec<Page> = match scribble_core::Document::load(&path)? {
+            scribble_core::Document::Notebook(notebook) => notebook.pages.into_iter().map(Page::from).collect(),
+            scribble_core::Document::Project(project) => vec![Page {
+                name: path.file_stem().map_or_else(|| "Imported Page".to_string(), |stem| stem.to_string_lossy().into_owned()),
+                strokes: project.strokes.into_iter().map(Stroke::from).collect(),
+                text_elements: project.text_elements.into_iter().map(TextElement::from).collect(),
+                camera: Camera::default(),
+                section: None,
+                connectors: project.connectors,
+                guides_x: Vec::new(),
+                guides_y: Vec::new(),
+            }],
+        };
+        if incoming.is_empty() {
+            self.notify(format!("{} has no pages", path.display()), false);
+            return Ok(());
+        }
+        let room = MAX_PAGES.saturating_sub(self.pages.len());
+        if room == 0 {
+            self.notify(format!("A notebook can have at most {} pages", MAX_PAGES), true);
+            return Ok(());
+        }
+        if incoming.len() > room {
+            self.notify(format!("Only the first {} of {} pages fit within the {}-page limit", room, incoming.len(), MAX_PAGES), true);
+            incoming.truncate(room);
+        }
+        
+        self.push_undo("Append notebook");
+        let first_appended = self.pages.len();
+        let appended = incoming.len();
+        for mut page in incoming {
+            // Names that clash with an existing page get a number, like "Ideas (2)"
+            if self.pages.iter().any(|existing| existing.name == page.name) {
+                let base = page.name.clone();
+                let mut copy = 2;
+                while self.pages.iter().any(|existing| existing.name == page.name) {
+                    page.name = format!("{} ({})", base, copy);
+                    copy += 1;
+                }
+            }
+            self.pages.push(page);
+        }
+        self.is_notebook_mode = true;
+        self.go_to_page(first_appended);
+        self.start_page_transition(1.0);
+        self.notify(format!("Appended {} page(s) from {}", appended, path.display()), false);
+        Ok(())
+    }
+    
+    fn load_project_from_path(&mut self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let document = scribble_core::Document::load(file_path)?;
+        
+        // Clear current state
+        self.current_stroke.clear();
+        self.is_drawing = false;
+        self.clear_selection();
+        self.pulsing.clear();
+        self.search_results.clear();
+        self.search_query.clear();
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        
+        match document {
+            scribble_core::Document::Notebook(notebook) => {
+                self.canvas_size = egui::Vec2::from(notebook.canvas_size);
+                self.pages = notebook.pages.into_iter().map(Page::from).collect();
                 self.current_page_index = notebook.current_page_index.min(self.pages.len().saturating_sub(1));
                 self.is_notebook_mode = true;
-            } else if let Ok(project) = serde_json::from_str::<ScribbleProject>(&json) {
-                // Load as single page project (backwards compatibility)
-                self.pages.clear();
-                self.current_stroke.clear();
-                self.is_drawing = false;
-                self.selected_text_elements.clear();
-                self.is_selecting_text = false;
-                self.selection_start = None;
-                self.selection_end = None;
-                self.search_results.clear();
-                self.search_query.clear();
                 
-                // Create single page from project
+                self.notebook_meta = notebook.meta;
+                self.notebook_font_size = notebook.text_font_size;
+                if let Some(font_size) = notebook.text_font_size {
+                    self.text_font_size = font_size;
+                }
+            }
+            scribble_core::Document::Project(project) => {
+                // Load as single page project (backwards compatibility)
+                self.canvas_size = egui::Vec2::from(project.canvas_size);
                 self.pages = vec![Page {
                     name: "Imported Page".to_string(),
-                    strokes: project.strokes.into_iter().map(|s| Stroke {
-                        points: s.points.into_iter().map(|(x, y)| egui::Pos2::new(x, y)).collect(),
-                        color: egui::Color32::from_rgb(s.color.0, s.color.1, s.color.2),
-                        width: s.width,
-                    }).collect(),
-                    text_elements: project.text_elements.into_iter().map(|t| TextElement {
-                        position: egui::Pos2::new(t.position.0, t.position.1),
-                        text: t.text,
-                        font_size: t.font_size,
-                    }).collect(),
+                    strokes: project.strokes.into_iter().map(Stroke::from).collect(),
+                    text_elements: project.text_elements.into_iter().map(TextElement::from).collect(),
+                    camera: Camera::default(),
+                    section: None,
+                    connectors: project.connectors,
+                    guides_x: Vec::new(),
+                    guides_y: Vec::new(),
                 }];
-                
                 self.current_page_index = 0;
                 self.is_notebook_mode = false;
-            } else {
-                return Err("Invalid file format".into());
+                self.notebook_font_size = None;
+                self.notebook_meta = scribble_core::NotebookMeta::new();
             }
         }
+        self.dirty = false;
+        self.search_index_stale = true;
+        self.enforce_page_limit();
+        
         Ok(())
     }
     
-    fn load_project_from_path(&mut self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        let json = fs::read_to_string(file_path)?;
-        
-        // Try to load as notebook first
-        if let Ok(notebook) = serde_json::from_str::<ScribbleNotebook>(&json) {
-            // Clear current state
-            self.pages.clear();
-            self.current_stroke.clear();
-            self.is_drawing = false;
-            self.selected_text_elements.clear();
-            self.is_selecting_text = false;
-            self.selection_start = None;
-            self.selection_end = None;
-            self.search_results.clear();
-            self.search_query.clear();
-            
-            // Load notebook
-            self.pages = notebook.pages.into_iter().map(|p| Page {
-                name: p.name,
-                strokes: p.strokes.into_iter().map(|s| Stroke {
-                    points: s.points.into_iter().map(|(x, y)| egui::Pos2::new(x, y)).collect(),
-                    color: egui::Color32::from_rgb(s.color.0, s.color.1, s.color.2),
-                    width: s.width,
-                }).collect(),
-                text_elements: p.text_elements.into_iter().map(|t| TextElement {
-                    position: egui::Pos2::new(t.position.0, t.position.1),
-                    text: t.text,
-                    font_size: t.font_size,
-                }).collect(),
-            }).collect();
-            
-            self.current_page_index = notebook.current_page_index.min(self.pages.len().saturating_sub(1));
-            self.is_notebook_mode = true;
-        } else if let Ok(project) = serde_json::from_str::<ScribbleProject>(&json) {
-            // Load as single page project (backwards compatibility)
-            self.pages.clear();
-            self.current_stroke.clear();
-            self.is_drawing = false;
-            self.selected_text_elements.clear();
-            self.is_selecting_text = false;
-            self.selection_start = None;
-            self.selection_end = None;
-            self.search_results.clear();
-            self.search_query.clear();
-            
-            // Create single page from project
-            self.pages = vec![Page {
-                name: "Imported Page".to_string(),
-                strokes: project.strokes.into_iter().map(|s| Stroke {
-                    points: s.points.into_iter().map(|(x, y)| egui::Pos2::new(x, y)).collect(),
-                    color: egui::Color32::from_rgb(s.color.0, s.color.1, s.color.2),
-                    width: s.width,
-                }).collect(),
-                text_elements: project.text_elements.into_iter().map(|t| TextElement {
-                    position: egui::Pos2::new(t.position.0, t.position.1),
-                    text: t.text,
-                    font_size: t.font_size,
-                }).collect(),
-            }];
-            
-            self.current_page_index = 0;
-            self.is_notebook_mode = false;
+    // Files written elsewhere can exceed the page limit; keep them whole with a warning, or trim them
+    // when the setting asks for it
+    fn enforce_page_limit(&mut self) {
+        let page_count = self.pages.len();
+        if page_count <= MAX_PAGES {
+            return;
+        }
+        if self.settings.trim_extra_pages {
+            self.pages.truncate(MAX_PAGES);
+            self.current_page_index = self.current_page_index.min(MAX_PAGES - 1);
+            self.dirty = true;
+            self.notify(format!("Only the first {} of {} pages were loaded; saving will drop the rest", MAX_PAGES, page_count), true);
         } else {
-            return Err("Invalid file format".into());
+            self.notify(format!("This notebook has {} pages, over the {}-page limit, so no more can be added", page_count, MAX_PAGES), true);
         }
-        
-        Ok(())
     }
 
+    // The current page as exports should see it: without the elements the tag filter excludes,
+    // when the settings ask for that
+    fn export_page(&self) -> scribble_core::Page {
+        self.page_for_export(self.current_page())
+    }
+    
+    fn page_for_export(&self, page: &Page) -> scribble_core::Page {
+        let mut page = scribble_core::Page::from(page);
+        if !self.settings.export_visible_only || self.tag_filter.is_none() {
+            return page;
+        }
+        page.strokes.retain(|stroke| self.matches_tag_filter(&stroke.tag));
+        let excluded: Vec<usize> = page.text_elements.iter().enumerate()
+            .filter(|(_, text_element)| !self.matches_tag_filter(&text_element.tag))
+            .map(|(idx, _)| idx)
+            .collect();
+        page.text_elements.retain(|text_element| self.matches_tag_filter(&text_element.tag));
+        scribble_core::remap_connectors(&mut page.connectors, |idx| scribble_core::index_after_removal(idx, &excluded));
+        page
+    }
+    
     fn export_svg(&self) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(path) = rfd::FileDialog::new()
             .add_filter("SVG Image", &["svg"])
             .set_file_name("my_drawing.svg")
             .save_file()
         {
-            let mut svg = String::new();
-            
-            // Calculate content bounds
-            let (min_x, min_y, width, height) = self.calculate_content_bounds();
-            
-            // SVG header with calculated dimensions and viewBox
-            svg.push_str(&format!(
-                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="{:.0} {:.0} {:.0} {:.0}">"#,
-                width, height, min_x, min_y, width, height
-            ));
-            svg.push('\n');
-            
-            // Background
-            svg.push_str(&format!(
-                r#"<rect x="{:.0}" y="{:.0}" width="{:.0}" height="{:.0}" fill="rgb(245,245,245)"/>"#,
-                min_x, min_y, width, height
-            ));
-            svg.push('\n');
-            
-            // Export strokes as paths
-            for stroke in self.current_strokes() {
-                if stroke.points.len() > 1 {
-                    svg.push_str(&format!(
-                        r#"<path d="M{},{}"#,
-                        stroke.points[0].x, stroke.points[0].y
-                    ));
-                    
-                    for point in &stroke.points[1..] {
-                        svg.push_str(&format!(" L{},{}", point.x, point.y));
-                    }
-                    
-                    svg.push_str(&format!(
-                        r#"" stroke="rgb({},{},{})" stroke-width="{}" fill="none" stroke-linecap="round" stroke-linejoin="round"/>"#,
-                        stroke.color.r(), stroke.color.g(), stroke.color.b(),
-                        stroke.width
-                    ));
-                    svg.push('\n');
-                }
-            }
-            
-            // Export text elements
-            for text_element in self.current_text_elements() {
-                // Handle multiline text
-                let lines: Vec<&str> = text_element.text.lines().collect();
-                for (line_idx, line) in lines.iter().enumerate() {
-                    if !line.trim().is_empty() {
-                        let line_y = text_element.position.y + text_element.font_size + (line_idx as f32 * text_element.font_size * 1.2);
-                        svg.push_str(&format!(
-                            r#"<text x="{}" y="{}" font-size="{}" font-family="monospace" fill="black">{}</text>"#,
-                            text_element.position.x,
-                            line_y,
-                            text_element.font_size,
-                            Self::html_escape(line)
-                        ));
-                        svg.push('\n');
-                    }
-                }
+            let page = self.export_page();
+            fs::write(path, export::render_svg(&page, &self.export_options()))?;
+        }
+        Ok(())
+    }
+    
+    fn export_png(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG Image", &["png"])
+            .set_file_name("my_drawing.png")
+            .save_file()
+        {
+            let page = self.export_page();
+            let max_dimension = self.settings.max_export_dimension;
+            let options = self.export_options();
+            let scale = export::png_scale(&page, &options, max_dimension);
+            export::render_png(&page, &options, scale).save(path)?;
+            if scale < 1.0 {
+                self.notify(format!("Image was scaled down to {:.0}% to fit within {} px", scale * 100.0, max_dimension), false);
             }
-            
-            svg.push_str("</svg>");
-            fs::write(path, svg)?;
         }
         Ok(())
     }
     
-    fn export_png(&self) -> Result<(), Box<dyn std::error::Error>> {
+    // Save every page into one HTML file that pages through them in any browser
+    fn export_html(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let title = if self.notebook_meta.title.trim().is_empty() {
+            "Scribble Notebook".to_string()
+        } else {
+            self.notebook_meta.title.clone()
+        };
         if let Some(path) = rfd::FileDialog::new()
-            .add_filter("PNG Image", &["png"])
-            .set_file_name("my_drawing.png")
+            .add_filter("HTML Page", &["html"])
+            .set_file_name("my_drawing.html")
             .save_file()
         {
-            // Calculate content bounds
-            let (min_x, min_y, width_f, height_f) = self.calculate_content_bounds();
-            let width = width_f as u32;
-            let height = height_f as u32;
-            
-            // Create image buffer with light grey background
-            let mut img: RgbImage = ImageBuffer::new(width, height);
-            let bg_color = Rgb([245u8, 245u8, 245u8]); // Light grey background
-            
-            // Fill background
-            for pixel in img.pixels_mut() {
-                *pixel = bg_color;
-            }
-            
-            // Draw strokes
-            for stroke in self.current_strokes() {
-                if stroke.points.len() > 1 {
-                    let stroke_rgb = Rgb([stroke.color.r(), stroke.color.g(), stroke.color.b()]);
-                    
-                    for i in 0..stroke.points.len() - 1 {
-                        let start = stroke.points[i];
-                        let end = stroke.points[i + 1];
-                        
-                        // Adjust coordinates relative to content bounds
-                        self.draw_line_on_image(
-                            &mut img,
-                            (start.x - min_x) as i32,
-                            (start.y - min_y) as i32,
-                            (end.x - min_x) as i32,
-                            (end.y - min_y) as i32,
-                            stroke_rgb,
-                            stroke.width as u32,
-                        );
-                    }
-                }
-            }
-            
-            // Draw text elements as colored rectangles (placeholder for actual text)
-            for text_element in self.current_text_elements() {
-                let lines: Vec<&str> = text_element.text.lines().collect();
-                let line_height = text_element.font_size * 1.2;
-                
-                for (line_idx, line) in lines.iter().enumerate() {
-                    if !line.trim().is_empty() {
-                        let line_y = text_element.position.y + (line_idx as f32) * line_height;
-                        let estimated_width = line.len() as f32 * text_element.font_size * 0.6;
-                        
-                        // Draw a rectangle to represent text area
-                        let text_color = Rgb([0u8, 0u8, 0u8]); // Black for text
-                        let text_x = (text_element.position.x - min_x) as i32;
-                        let text_y = (line_y - min_y) as i32;
-                        let text_width = estimated_width as i32;
-                        let text_height = text_element.font_size as i32;
-                        
-                        // Draw text background rectangle
-                        for x in text_x..text_x + text_width {
-                            for y in text_y..text_y + text_height {
-                                if x >= 0 && x < width as i32 && y >= 0 && y < height as i32 {
-                                    // Draw a simple pattern to represent text
-                                    if (x + y) % 4 == 0 {
-                                        img.put_pixel(x as u32, y as u32, text_color);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            
-            // Save the image
-            img.save(path)?;
+            let pages: Vec<scribble_core::Page> = self.pages.iter().map(|page| self.page_for_export(page)).collect();
+            fs::write(path, export::render_html(&title, &pages, &self.export_options()))?;
         }
         Ok(())
     }
     
-    // Helper function to draw lines on image buffer
-    fn draw_line_on_image(
-        &self,
-        img: &mut RgbImage,
-        x0: i32,
-        y0: i32,
-        x1: i32,
-        y1: i32,
-        color: Rgb<u8>,
-        width: u32,
-    ) {
-        let (width_i, height_i) = img.dimensions();
-        let (img_width, img_height) = (width_i as i32, height_i as i32);
-        
-        // Bresenham's line algorithm
-        let dx = (x1 - x0).abs();
-        let dy = (y1 - y0).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx - dy;
-        
-        let mut x = x0;
-        let mut y = y0;
-        
-        loop {
-            // Draw a circle for line thickness
-            for offset_x in -(width as i32 / 2)..=(width as i32 / 2) {
-                for offset_y in -(width as i32 / 2)..=(width as i32 / 2) {
-                    let px = x + offset_x;
-                    let py = y + offset_y;
-                    
-                    // Check if pixel is within stroke radius and image bounds
-                    if offset_x * offset_x + offset_y * offset_y <= (width as i32 / 2).pow(2) &&
-                       px >= 0 && px < img_width && py >= 0 && py < img_height {
-                        img.put_pixel(px as u32, py as u32, color);
-                    }
-                }
-            }
-            
-            if x == x1 && y == y1 {
-                break;
-            }
-            
-            let e2 = 2 * err;
-            if e2 > -dy {
-                err -= dy;
-                x += sx;
+    // Every element of a page with its bounds measured the way the canvas lays it out
+    fn page_data(&self, ctx: &egui::Context, page: &Page) -> PageData {
+        let mut elements = Vec::new();
+        for (index, stroke) in page.strokes.iter().enumerate().filter(|(_, stroke)| stroke.is_drawable()) {
+            elements.push(ElementData::Stroke {
+                index,
+                bounds: egui::Rect::from_points(&stroke.points).expand(stroke.width / 2.0).into(),
+                color: color_hex(stroke.color),
+                colors: stroke.colors.iter().copied().map(color_hex).collect(),
+                opacity: stroke.opacity,
+                width: stroke.width,
+                point_count: stroke.points.len(),
+                tag: stroke.tag.clone(),
+            });
+        }
+        let text_rects: Vec<egui::Rect> = page.text_elements.iter()
+            .map(|text_element| self.text_bounds(ctx, text_element))
+            .collect();
+        for (index, text_element) in page.text_elements.iter().enumerate() {
+            elements.push(ElementData::Text {
+                index,
+                bounds: text_rects[index].into(),
+                text: text_element.text.clone(),
+                font_size: text_element.font_size,
+                color: color_hex(egui::Color32::BLACK),
+                outline: text_element.outline.map(color_hex),
+                orientation: text_element.orientation,
+                pinned: text_element.pinned,
+                tag: text_element.tag.clone(),
+            });
+        }
+        let as_box = |rect: egui::Rect| [rect.min.x, rect.min.y, rect.max.x, rect.max.y];
+        for (index, connector) in page.connectors.iter().enumerate() {
+            let (Some(&from), Some(&to)) = (text_rects.get(connector.from), text_rects.get(connector.to)) else {
+                continue;
+            };
+            let points: Vec<egui::Pos2> = scribble_core::connector_points(as_box(from), as_box(to), connector.arrow).into_iter()
+                .map(|[x, y]| egui::Pos2::new(x, y))
+                .collect();
+            elements.push(ElementData::Connector {
+                index,
+                bounds: egui::Rect::from_points(&points).into(),
+                from: connector.from,
+                to: connector.to,
+                arrow: connector.arrow,
+            });
+        }
+        PageData { name: page.name.clone(), section: page.section.clone(), elements }
+    }
+    
+    // Save every element of every page, with measured bounds and colors, as JSON for other tools
+    fn export_data(&mut self, ctx: &egui::Context) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("my_drawing.json")
+            .save_file()
+        else {
+            return Ok(());
+        };
+        let data = DataExport {
+            format: DATA_EXPORT_FORMAT,
+            version: DATA_EXPORT_VERSION,
+            pages: self.pages.iter().map(|page| self.page_data(ctx, page)).collect(),
+        };
+        fs::write(&path, serde_json::to_string_pretty(&data)?)?;
+        let count: usize = data.pages.iter().map(|page| page.elements.len()).sum();
+        self.notify(format!("Exported {} elements to {}", count, path.display()), false);
+        Ok(())
+    }
+    
+    // Save every page as a numbered PNG in a chosen folder
+    fn export_all_pages_png(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(folder) = rfd::FileDialog::new().pick_folder() else {
+            return Ok(());
+        };
+        let pages: Vec<scribble_core::Page> = self.pages.iter().map(|page| self.page_for_export(page)).collect();
+        let options = self.export_options();
+        // Each page is written before the next is rendered
+        let images = export::render_pages_png(&pages, &options, self.settings.max_export_dimension, self.settings.uniform_page_exports);
+        for (idx, image) in images.enumerate() {
+            image.save(folder.join(format!("page_{:03}.png", idx + 1)))?;
+        }
+        self.notify(format!("Saved {} pages to {}", pages.len(), folder.display()), false);
+        Ok(())
+    }
+    
+    fn animation_frame_count(&self) -> usize {
+        ((self.animation_seconds * self.animation_fps as f32).round() as usize).max(2)
+    }
+    
+    // Save the page being drawn, element by element, as a looping GIF or a folder of numbered PNGs
+    fn export_animation(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let page = self.export_page();
+        let options = self.export_options();
+        let scale = export::png_scale(&page, &options, self.settings.max_export_dimension);
+        let fps = self.animation_fps.max(1);
+        let frame_count = self.animation_frame_count();
+        
+        if self.animation_as_frames {
+            let Some(folder) = rfd::FileDialog::new().pick_folder() else {
+                return Ok(());
+            };
+            let frames = export::render_animation_frames(&page, &options, scale, frame_count);
+            let saved = frames.len();
+            for (idx, frame) in frames.enumerate() {
+                frame.save(folder.join(format!("frame_{:04}.png", idx + 1)))?;
             }
-            if e2 < dx {
-                err += dx;
-                y += sy;
+            self.notify(format!("Saved {} frames to {}", saved, folder.display()), false);
+        } else {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("GIF Animation", &["gif"])
+                .set_file_name("my_drawing.gif")
+                .save_file()
+            else {
+                return Ok(());
+            };
+            let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(fs::File::create(&path)?, 10);
+            encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+            let delay = image::Delay::from_numer_denom_ms(1000, fps);
+            for frame in export::render_animation_frames(&page, &options, scale, frame_count) {
+                let frame = image::DynamicImage::ImageRgb8(frame).into_rgba8();
+                encoder.encode_frame(image::Frame::from_parts(frame, 0, 0, delay))?;
             }
+            self.notify(format!("Saved animation to {}", path.display()), false);
         }
+        Ok(())
     }
     
-    fn html_escape(text: &str) -> String {
-        text.replace('&', "&amp;")
-            .replace('<', "&lt;")
-            .replace('>', "&gt;")
-            .replace('"', "&quot;")
-            .replace('\'', "&#39;")
+    // Render the selection, or the whole page when nothing is selected, onto the clipboard as an image
+    fn copy_image_to_clipboard(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut page = scribble_core::Page::from(self.current_page());
+        if self.has_selection() {
+            page.strokes = self.selected_strokes.iter()
+                .filter_map(|&idx| page.strokes.get(idx).cloned())
+                .collect();
+            page.text_elements = self.selected_text_elements.iter()
+                .filter_map(|&idx| page.text_elements.get(idx).cloned())
+                .collect();
+            // Only connectors between selected text come along, pointing at its new places
+            scribble_core::remap_connectors(&mut page.connectors, |idx| self.selected_text_elements.iter().position(|&selected| selected == idx));
+        }
+        
+        let options = self.export_options();
+        let scale = export::png_scale(&page, &options, self.settings.max_export_dimension);
+        let image = image::DynamicImage::ImageRgb8(export::render_png(&page, &options, scale)).into_rgba8();
+        let clipboard = self.clipboard.as_mut().ok_or("Clipboard is not available")?;
+        clipboard.set_image(arboard::ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: image.into_raw().into(),
+        })?;
+        Ok(())
     }
     
     fn detect_arrow_collisions(&mut self, painter: &egui::Painter) {
+        let ctx = painter.ctx();
         self.text_collisions.clear();
         
         if self.search_results.is_empty() {
@@ -1102,15 +4840,11 @@ impl ScribbleApp {
             let positions = self.get_match_positions(&search_element.text);
             
             for (start_char, end_char) in positions {
-                let font_id = egui::FontId::proportional(search_element.font_size);
+                let font_size = search_element.font_size;
                 
                 // Calculate arrow area (simplified version of the arrow drawing logic)
                 let lines: Vec<&str> = search_element.text.lines().collect();
-                let line_height = painter.layout_no_wrap(
-                    "Ag".to_string(),
-                    font_id.clone(),
-                    egui::Color32::WHITE,
-                ).size().y;
+                let line_height = self.text_size(ctx, "Ag", font_size).y;
                 
                 let mut char_count = 0;
                 let mut match_line = 0;
@@ -1134,20 +4868,12 @@ impl ScribbleApp {
                     let before_match = &current_line[..match_start_in_line];
                     let match_text = &current_line[match_start_in_line..match_end_in_line];
                     
-                    let before_galley = painter.layout_no_wrap(
-                        before_match.to_string(),
-                        font_id.clone(),
-                        egui::Color32::WHITE,
-                    );
-                    let match_galley = painter.layout_no_wrap(
-                        match_text.to_string(),
-                        font_id.clone(),
-                        egui::Color32::WHITE,
-                    );
+                    let before_size = self.text_size(ctx, before_match, font_size);
+                    let match_size = self.text_size(ctx, match_text, font_size);
                     
-                    let match_start_x = search_element.position.x + before_galley.size().x;
-                    let match_end_x = match_start_x + match_galley.size().x;
-                    let text_bottom = line_y + match_galley.size().y;
+                    let match_start_x = search_element.position.x + before_size.x;
+                    let match_end_x = match_start_x + match_size.x;
+                    let text_bottom = line_y + match_size.y;
                     
                     // Define arrow area (arrows appear below text)
                     let arrow_area = egui::Rect::from_min_max(
@@ -1163,21 +4889,11 @@ impl ScribbleApp {
                         
                         // Estimate text area for collision detection
                         let other_lines: Vec<&str> = other_element.text.lines().collect();
-                        let other_line_height = painter.layout_no_wrap(
-                            "Ag".to_string(),
-                            egui::FontId::proportional(other_element.font_size),
-                            egui::Color32::WHITE,
-                        ).size().y;
+                        let other_line_height = self.text_size(ctx, "Ag", other_element.font_size).y;
                         
                         // Calculate approximate text bounds
                         let max_line_width = other_lines.iter()
-                            .map(|line| {
-                                painter.layout_no_wrap(
-                                    line.to_string(),
-                                    egui::FontId::proportional(other_element.font_size),
-                                    egui::Color32::WHITE,
-                                ).size().x
-                            })
+                            .map(|line| self.text_size(ctx, line, other_element.font_size).x)
                             .fold(0.0, f32::max);
                         
                         let text_area = egui::Rect::from_min_size(
@@ -1204,42 +4920,171 @@ impl ScribbleApp {
 
 impl eframe::App for ScribbleApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Handle drag and drop for .scribble files
+        // Hold a window close until unsaved changes are saved or discarded
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.dirty
+            && self.settings.confirm_on_exit
+            && !self.close_confirmed
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.show_exit_confirm = true;
+        }
+        
+        // A released pointer ends any continuous edit
+        if !ctx.input(|i| i.pointer.any_down()) {
+            self.undo_coalescing = false;
+        }
+        
+        self.sync_ui_scale(ctx);
+        
+        // Undo/redo, unless a text field is using the keyboard for its own undo
+        if !ctx.wants_keyboard_input() {
+            if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_UNDO)) {
+                self.undo();
+            }
+            if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_REDO) || i.consume_shortcut(&SHORTCUT_REDO_ALT)) {
+                self.redo();
+            }
+            
+            // Pasted text becomes a new text element
+            let pasted = ctx.input(|i| i.events.iter().find_map(|event| match event {
+                egui::Event::Paste(text) if !text.trim().is_empty() => Some(text.clone()),
+                _ => None,
+            }));
+            if let Some(text) = pasted {
+                self.insert_text_block(ctx, text, true);
+            }
+            
+            // Native backends turn Cmd+C into a copy event rather than a key press, so look for both
+            let copy_image = ctx.input_mut(|i| {
+                i.consume_shortcut(&SHORTCUT_COPY_IMAGE)
+                    || (i.modifiers.shift && i.events.iter().any(|event| matches!(event, egui::Event::Copy)))
+            });
+            if copy_image {
+                match self.copy_image_to_clipboard() {
+                    Ok(()) => self.notify("Copied image to clipboard", false),
+                    Err(e) => self.notify(format!("Copy image error: {}", e), true),
+                }
+            }
+            
+            // Notebook navigation
+            if self.is_notebook_mode {
+                if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_PREVIOUS_PAGE)) {
+                    self.previous_page();
+                }
+                if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_NEXT_PAGE)) {
+                    self.next_page();
+                }
+                if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_ADD_PAGE)) {
+                    self.add_new_page();
+                }
+                if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_GO_TO_PAGE)) {
+                    self.show_go_to_page = true;
+                    self.go_to_page_input.clear();
+                }
+            }
+        }
+        
+        // Handle drag and drop for notebooks and text files. Notebooks are recognized by their
+        // content, so any other file is tried as one and reported if it isn't.
         self.is_file_hovered = false;
+        let mut dropped_text = Vec::new();
+        let mut dropped_tables = Vec::new();
         ctx.input(|i| {
             // Check for files being hovered
-            if !i.raw.hovered_files.is_empty() {
-                for file in &i.raw.hovered_files {
-                    if let Some(path) = &file.path {
-                        if let Some(extension) = path.extension() {
-                            if extension == "scribble" {
-                                self.is_file_hovered = true;
-                                break;
-                            }
-                        }
-                    }
-                }
+            if i.raw.hovered_files.iter().any(|file| file.path.is_some()) {
+                self.is_file_hovered = true;
             }
             
             // Check for files being dropped
             if !i.raw.dropped_files.is_empty() {
                 for file in &i.raw.dropped_files {
                     if let Some(path) = &file.path {
-                        if let Some(extension) = path.extension() {
-                            if extension == "scribble" {
-                                if let Err(e) = self.load_project_from_path(path) {
-                                    eprintln!("Failed to load dropped file: {}", e);
-                                } else {
-                                    // Successfully loaded file
-                                    println!("Successfully loaded: {}", path.display());
-                                }
-                            }
+                        let extension = path.extension().and_then(|extension| extension.to_str());
+                        if matches!(extension, Some("txt" | "md")) {
+                            dropped_text.push(path.clone());
+                        } else if matches!(extension, Some("csv" | "tsv")) {
+                            dropped_tables.push(path.clone());
+                        } else {
+                            self.open_file(path);
                         }
                     }
                 }
             }
         });
         
+        // Dropped text files land where they were dropped, like a paste
+        for path in dropped_text {
+            match fs::read_to_string(&path) {
+                Ok(contents) if !contents.trim().is_empty() => self.insert_text_block(ctx, contents, true),
+                Ok(_) => self.notify(format!("{} is empty", path.display()), false),
+                Err(e) => self.notify(format!("Could not read {}: {}", path.display(), e), true),
+            }
+        }
+        for path in dropped_tables {
+            match fs::read_to_string(&path) {
+                Ok(contents) => self.insert_table(ctx, &contents),
+                Err(e) => self.notify(format!("Could not read {}: {}", path.display(), e), true),
+            }
+        }
+        
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_TOGGLE_HELP)) {
+            self.show_help = !self.show_help;
+        }
+        
+        // Presentation controls
+        if !ctx.wants_keyboard_input() {
+            if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_PRESENT)) {
+                self.set_presenting(!self.presenting);
+            }
+            if self.presenting {
+                let (forward, back, exit) = ctx.input(|i| (
+                    i.key_pressed(egui::Key::Space) || i.key_pressed(egui::Key::ArrowRight),
+                    i.key_pressed(egui::Key::ArrowLeft) || i.key_pressed(egui::Key::Backspace),
+                    i.key_pressed(egui::Key::Escape),
+                ));
+                if forward {
+                    self.advance_build();
+                }
+                if back {
+                    self.retreat_build();
+                }
+                if exit {
+                    self.set_presenting(false);
+                }
+            }
+        }
+        
+        if self.is_notebook_mode && self.show_page_list {
+            egui::SidePanel::left("page_list")
+                .resizable(true)
+                .default_width(160.0)
+                .show(ctx, |ui| {
+                    ui.heading("Pages");
+                    egui::ScrollArea::vertical().show(ui, |ui| self.show_page_list(ui));
+                });
+        }
+        
+        if self.show_search && self.show_results_panel {
+            egui::SidePanel::right("search_results_panel")
+                .resizable(true)
+                .default_width(220.0)
+                .show(ctx, |ui| {
+                    ui.heading("Search Results");
+                    egui::ScrollArea::vertical().show(ui, |ui| self.show_results_panel(ui));
+                });
+        }
+        
+        if self.show_elements_panel {
+            egui::SidePanel::right("elements_panel")
+                .resizable(true)
+                .default_width(200.0)
+                .show(ctx, |ui| {
+                    ui.heading("Elements");
+                    egui::ScrollArea::vertical().show(ui, |ui| self.show_elements_panel(ui));
+                });
+        }
+        
         egui::CentralPanel::default().show(ctx, |ui| {
             // Top controls
             ui.horizontal(|ui| {
@@ -1259,27 +5104,101 @@ impl eframe::App for ScribbleApp {
                         self.next_page();
                     }
                     
-                    if ui.button("➕ Add Page").clicked() {
+                    if ui.add_enabled(self.pages.len() < MAX_PAGES, egui::Button::new("➕ Add Page"))
+                        .on_disabled_hover_text(format!("A notebook can have at most {} pages", MAX_PAGES))
+                        .clicked()
+                    {
                         self.add_new_page();
                     }
                     
+                    if ui.selectable_label(self.show_page_list, "📑 Pages").on_hover_text("Page list grouped by section").clicked() {
+                        self.show_page_list = !self.show_page_list;
+                    }
+                    
+                    ui.checkbox(&mut self.animate_page_transitions, "Animate")
+                        .on_hover_text("Slide between pages instead of switching instantly");
+                    
+                    if ui.button("ℹ Info").on_hover_text("Notebook title, author and dates").clicked() {
+                        self.show_notebook_info = true;
+                    }
+                    
                     ui.separator();
                 }
                 
+                if ui.add_enabled(!self.undo_stack.is_empty(), egui::Button::new("↶ Undo")).clicked() {
+                    self.undo();
+                }
+                if ui.add_enabled(!self.redo_stack.is_empty(), egui::Button::new("↷ Redo")).clicked() {
+                    self.redo();
+                }
+                if ui.selectable_label(self.show_history, "🕘").on_hover_text("Undo history").clicked() {
+                    self.show_history = !self.show_history;
+                }
+                
+                if ui.selectable_label(self.show_elements_panel, "🗂 Elements").on_hover_text("List, reorder and delete what is on this page").clicked() {
+                    self.show_elements_panel = !self.show_elements_panel;
+                }
+                
+                ui.menu_button("🧹 Cleanup", |ui| {
+                    if ui.button("Tidy text layout").on_hover_text("Arrange text in a left-aligned column").clicked() {
+                        self.tidy_text_layout();
+                        ui.close_menu();
+                    }
+                    if ui.button("Remove duplicate strokes").on_hover_text("Delete strokes that retrace another stroke").clicked() {
+                        match self.remove_duplicate_strokes() {
+                            0 => self.notify("No duplicate strokes found", false),
+                            1 => self.notify("Removed 1 duplicate stroke", false),
+                            removed => self.notify(format!("Removed {} duplicate strokes", removed), false),
+                        }
+                        ui.close_menu();
+                    }
+                });
+                
+                ui.menu_button("⭐ Stamps", |ui| {
+                    if self.settings.stamps.is_empty() {
+                        ui.label("Select elements and use ⭐ Save as stamp in the Select toolbar");
+                    }
+                    let mut removed = None;
+                    for (idx, stamp) in self.settings.stamps.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let placing = self.placing_stamp == Some(idx);
+                            if ui.selectable_label(placing, &stamp.name)
+                                .on_hover_text("Click the canvas to place copies; Esc or right-click stops")
+                                .clicked()
+                            {
+                                self.placing_stamp = (!placing).then_some(idx);
+                                ui.close_menu();
+                            }
+                            if ui.small_button("🗑").on_hover_text("Delete this stamp").clicked() {
+                                removed = Some(idx);
+                            }
+                        });
+                    }
+                    if let Some(idx) = removed {
+                        self.settings.stamps.remove(idx);
+                        self.placing_stamp = match self.placing_stamp {
+                            Some(placing) if placing == idx => None,
+                            Some(placing) if placing > idx => Some(placing - 1),
+                            placing => placing,
+                        };
+                        if let Err(e) = self.settings.save() {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    }
+                });
+                
                 if ui.button("Clear").clicked() {
+                    self.push_undo("Clear page");
                     self.current_strokes_mut().clear();
                     self.current_text_elements_mut().clear();
+                    self.current_page_mut().connectors.clear();
                     self.current_stroke.clear();
                     self.is_drawing = false;
-                    self.text_input.clear();
-                    self.active_text_position = None;
+                    self.close_text_editor();
                     self.search_results.clear();
                     self.search_query.clear();
                     // Clear selection state
-                    self.selected_text_elements.clear();
-                    self.is_selecting_text = false;
-                    self.selection_start = None;
-                    self.selection_end = None;
+                    self.clear_selection();
                 }
                 
                 ui.separator();
@@ -1288,32 +5207,375 @@ impl eframe::App for ScribbleApp {
                 ui.menu_button("📁 File", |ui| {
                     if ui.button("💾 Save Project").clicked() {
                         if let Err(e) = self.save_project() {
-                            eprintln!("Save error: {}", e);
+                            self.notify(format!("Save error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("📂 Load Project").clicked() {
+                        if let Err(e) = self.load_project() {
+                            self.notify(format!("Load error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("📚 Append notebook...").on_hover_text("Add another file's pages after these ones").clicked() {
+                        if let Err(e) = self.append_notebook() {
+                            self.notify(format!("Append error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("📝 Insert text from file...").clicked() {
+                        if let Err(e) = self.import_text_file(ctx) {
+                            self.notify(format!("Text import error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("▦ Import table...").on_hover_text("Lay out a CSV or TSV file as a grid of text").clicked() {
+                        if let Err(e) = self.import_table_file(ctx) {
+                            self.notify(format!("Table import error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("📋 Paste as table").on_hover_text("Lay out comma- or tab-separated text from the clipboard, e.g. cells copied from a spreadsheet, as a grid of text").clicked() {
+                        match self.clipboard.as_mut().map(|clipboard| clipboard.get_text()) {
+                            Some(Ok(contents)) => self.insert_table(ctx, &contents),
+                            Some(Err(e)) => self.notify(format!("Could not read the clipboard: {}", e), true),
+                            None => self.notify("Clipboard is not available", true),
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Table cell spacing:");
+                        let spacing_response = ui.add(egui::DragValue::new(&mut self.settings.table_cell_spacing)
+                            .range(0.0..=200.0)
+                            .suffix(" px"));
+                        if (spacing_response.lost_focus() || spacing_response.drag_stopped())
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    });
+                    
+                    ui.separator();
+                    
+                    if ui.button("📤 Export SVG").clicked() {
+                        if let Err(e) = self.export_svg() {
+                            self.notify(format!("SVG export error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("📸 Export PNG").clicked() {
+                        if let Err(e) = self.export_png() {
+                            self.notify(format!("PNG export error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("🌐 Export HTML...").on_hover_text("All pages in one web page, with buttons to flip between them").clicked() {
+                        if let Err(e) = self.export_html() {
+                            self.notify(format!("HTML export error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("🧾 Export data (JSON)...").on_hover_text("Every element on every page with its measured bounds, colors and text, for scripts and other tools").clicked() {
+                        if let Err(e) = self.export_data(ctx) {
+                            self.notify(format!("Data export error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.button("🗂 Export All Pages as PNG...").on_hover_text("One numbered image per page, saved into a folder").clicked() {
+                        if let Err(e) = self.export_all_pages_png() {
+                            self.notify(format!("PNG export error: {}", e), true);
+                        }
+                        ui.close_menu();
+                    }
+                    
+                    if ui.checkbox(&mut self.settings.uniform_page_exports, "Same size for every page")
+                        .on_hover_text("Size all page images to fit the largest content, padding the rest, so they line up as slides")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    if ui.button("🎞 Export Animation...").on_hover_text("The page being drawn, as a GIF or numbered PNG frames").clicked() {
+                        self.show_animation_export = true;
+                        ui.close_menu();
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Max PNG size:");
+                        let max_dimension = ui.add(egui::DragValue::new(&mut self.settings.max_export_dimension)
+                            .range(256..=32768)
+                            .suffix(" px"));
+                        if (max_dimension.lost_focus() || max_dimension.drag_stopped())
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
                         }
+                    });
+                    
+                    if ui.checkbox(&mut self.settings.confirm_on_exit, "Ask before closing with unsaved changes").changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("UI scale:")
+                            .on_hover_text("Size of menus, toolbars and text on top of your display's own scaling; Ctrl+plus/minus also change it");
+                        let scale_response = ui.add(egui::Slider::new(&mut self.settings.ui_scale, MIN_UI_SCALE..=MAX_UI_SCALE)
+                            .custom_formatter(|scale, _| format!("{:.0}%", scale * 100.0))
+                            .custom_parser(|text| text.trim().trim_end_matches('%').parse::<f64>().ok().map(|percent| percent / 100.0)));
+                        // Saved once a drag ends rather than on every step of it
+                        let mut changed = (scale_response.changed() && !scale_response.dragged()) || scale_response.drag_stopped();
+                        if ui.add_enabled(self.settings.ui_scale != 1.0, egui::Button::new("↺"))
+                            .on_hover_text("Back to 100%")
+                            .clicked()
+                        {
+                            self.settings.ui_scale = 1.0;
+                            changed = true;
+                        }
+                        if changed
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Start with:")
+                            .on_hover_text("The tool selected when Scribble opens");
+                        let mut changed = false;
+                        egui::ComboBox::from_id_source("default_tool")
+                            .selected_text(self.settings.default_tool.label())
+                            .show_ui(ui, |ui| {
+                                for tool in Tool::ALL {
+                                    changed |= ui.selectable_value(&mut self.settings.default_tool, tool, tool.label()).changed();
+                                }
+                            });
+                        if changed
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    });
+                    
+                    if ui.checkbox(&mut self.settings.translucent_stroke_preview, "Translucent stroke preview")
+                        .on_hover_text("Fade the stroke you are drawing until it is finished")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    if ui.checkbox(&mut self.settings.trim_extra_pages, format!("Trim notebooks over {} pages on load", MAX_PAGES))
+                        .on_hover_text("Otherwise oversized notebooks open whole but can't grow")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    if ui.checkbox(&mut self.settings.paste_at_cursor, "Paste at the pointer")
+                        .on_hover_text("Center pasted and dropped text under the pointer instead of at the top-left of the view")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    if ui.checkbox(&mut self.settings.show_empty_hints, "Hints on empty pages")
+                        .on_hover_text("Suggest notebooks on a blank page, or page navigation inside a notebook")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    let has_guides = !self.current_page().guides_x.is_empty() || !self.current_page().guides_y.is_empty();
+                    if ui.add_enabled(has_guides, egui::Button::new("📏 Clear guides"))
+                        .on_hover_text("Remove this page's custom guides; drag new ones out of the rulers along the canvas's top and left edges")
+                        .clicked()
+                    {
+                        self.push_undo("Clear guides");
+                        let page = self.current_page_mut();
+                        page.guides_x.clear();
+                        page.guides_y.clear();
                         ui.close_menu();
                     }
                     
-                    if ui.button("📂 Load Project").clicked() {
-                        if let Err(e) = self.load_project() {
-                            eprintln!("Load error: {}", e);
+                    if ui.checkbox(&mut self.settings.keep_within_page, "Keep content within page")
+                        .on_hover_text("Stop new and dragged text and strokes at the page's edges")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Page size:");
+                        let mut size = self.canvas_size;
+                        ui.add(egui::DragValue::new(&mut size.x).range(100.0..=10000.0).suffix(" px"));
+                        ui.label("×");
+                        ui.add(egui::DragValue::new(&mut size.y).range(100.0..=10000.0).suffix(" px"));
+                        // A whole drag is one step, since consecutive resizes coalesce
+                        if size != self.canvas_size {
+                            self.push_undo("Resize page");
+                            self.canvas_size = size;
+                        }
+                    });
+                    
+                    if ui.checkbox(&mut self.settings.show_content_extent, "Show content extent")
+                        .on_hover_text("Faintly outline the area your content covers, and mark view edges it continues past")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    if ui.checkbox(&mut self.settings.selectable_text, "Selectable text (native copy, slower)")
+                        .on_hover_text("Draws text as real labels so it can be selected and copied; with the Select tool, dragging over text selects characters instead of moving it")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Ignore strokes within:");
+                        let distance = ui.add(egui::DragValue::new(&mut self.settings.min_stroke_distance)
+                            .range(0.0..=50.0)
+                            .suffix(" px"))
+                            .on_hover_text("Freehand strokes that never move this far from where they started are treated as accidental and dropped");
+                        if (distance.lost_focus() || distance.drag_stopped())
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Fill drawing gaps over:");
+                        let gap = ui.add(egui::DragValue::new(&mut self.settings.max_capture_gap)
+                            .range(0.0..=100.0)
+                            .suffix(" px"))
+                            .on_hover_text("When the pointer moves faster than the screen updates, add points along a curve between samples this far apart; 0 leaves gaps as drawn");
+                        if (gap.lost_focus() || gap.drag_stopped())
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label("Export margin:");
+                        let margin = ui.add(egui::DragValue::new(&mut self.settings.export_margin)
+                            .range(0.0..=500.0)
+                            .suffix(" px"));
+                        if (margin.lost_focus() || margin.drag_stopped())
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        let mut wrap = self.settings.export_wrap_width.is_some();
+                        let mut changed = ui.checkbox(&mut wrap, "Wrap long text lines at").changed();
+                        let mut width = self.settings.export_wrap_width.unwrap_or(600.0);
+                        let width_response = ui.add_enabled(wrap, egui::DragValue::new(&mut width)
+                            .range(50.0..=5000.0)
+                            .suffix(" px"));
+                        changed |= width_response.lost_focus() || width_response.drag_stopped();
+                        self.settings.export_wrap_width = wrap.then_some(width);
+                        if changed
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        let mut smooth = self.settings.export_curve_spacing.is_some();
+                        let mut changed = ui.checkbox(&mut smooth, "Smooth exported curves, a point every")
+                            .on_hover_text("Fit sparse strokes with a curve when exporting; the drawing itself is unchanged")
+                            .changed();
+                        let mut spacing = self.settings.export_curve_spacing.unwrap_or(DEFAULT_EXPORT_CURVE_SPACING);
+                        let spacing_response = ui.add_enabled(smooth, egui::DragValue::new(&mut spacing)
+                            .range(0.5..=50.0)
+                            .suffix(" px"));
+                        changed |= spacing_response.lost_focus() || spacing_response.drag_stopped();
+                        self.settings.export_curve_spacing = smooth.then_some(spacing);
+                        if changed
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
                         }
-                        ui.close_menu();
-                    }
+                    });
                     
-                    ui.separator();
+                    if ui.checkbox(&mut self.settings.svg_curves, "Fit SVG strokes with curves")
+                        .on_hover_text("Write strokes as smooth Bézier paths through fewer points; off keeps a straight segment per captured point")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
                     
-                    if ui.button("📤 Export SVG").clicked() {
-                        if let Err(e) = self.export_svg() {
-                            eprintln!("SVG export error: {}", e);
+                    ui.horizontal(|ui| {
+                        ui.label("PNG text as:")
+                            .on_hover_text("PNG exports mark where text sits rather than drawing its letters");
+                        let mut changed = false;
+                        egui::ComboBox::from_id_source("png_text_style")
+                            .selected_text(self.settings.png_text_style.label())
+                            .show_ui(ui, |ui| {
+                                for style in export::PngTextStyle::ALL {
+                                    changed |= ui.selectable_value(&mut self.settings.png_text_style, style, style.label()).changed();
+                                }
+                            });
+                        if changed
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
                         }
-                        ui.close_menu();
-                    }
+                    });
                     
-                    if ui.button("📸 Export PNG").clicked() {
-                        if let Err(e) = self.export_png() {
-                            eprintln!("PNG export error: {}", e);
+                    ui.horizontal(|ui| {
+                        let mut changed = ui.checkbox(&mut self.settings.watermark, "Watermark")
+                            .on_hover_text("Stamp text into a corner of PNG, SVG and HTML exports; {date} becomes today's date and {page} the page name")
+                            .changed();
+                        let text_response = ui.add_enabled(self.settings.watermark, egui::TextEdit::singleline(&mut self.settings.watermark_text)
+                            .desired_width(140.0));
+                        changed |= text_response.lost_focus();
+                        ui.add_enabled_ui(self.settings.watermark, |ui| {
+                            egui::ComboBox::from_id_source("watermark_position")
+                                .selected_text(self.settings.watermark_position.label())
+                                .show_ui(ui, |ui| {
+                                    for position in export::WatermarkPosition::ALL {
+                                        changed |= ui.selectable_value(&mut self.settings.watermark_position, position, position.label()).changed();
+                                    }
+                                });
+                        });
+                        if changed
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
                         }
-                        ui.close_menu();
+                    });
+                    
+                    if ui.checkbox(&mut self.settings.export_visible_only, "Export visible only")
+                        .on_hover_text("While a tag filter is active, leave out the elements it dims or hides")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
                     }
                 });
                 
@@ -1333,30 +5595,272 @@ impl eframe::App for ScribbleApp {
                 
                 // Tool selection
                 ui.label("Tool:");
-                ui.selectable_value(&mut self.current_tool, Tool::Draw, "✏️ Draw");
-                ui.selectable_value(&mut self.current_tool, Tool::Text, "📝 Text");
-                ui.selectable_value(&mut self.current_tool, Tool::Select, "🔍 Select");
+                for tool in Tool::ALL {
+                    let label = tool.label();
+                    if ui.selectable_value(&mut self.current_tool, tool, label).changed() {
+                        // An unfinished curve is dropped rather than left behind as a preview
+                        self.cancel_curve();
+                        self.placing_stamp = None;
+                    }
+                }
                 
                 ui.separator();
                 
+                if self.current_tool == Tool::Draw || self.current_tool == Tool::Curve {
+                    ui.label("Stroke width:");
+                    ui.add(egui::Slider::new(&mut self.stroke_width, 1.0..=10.0));
+                    ui.label("Opacity:");
+                    ui.add(egui::Slider::new(&mut self.stroke_opacity, 0.1..=1.0))
+                        .on_hover_text("Lower it for a highlighter; overlapping strokes darken");
+                }
                 if self.current_tool == Tool::Draw {
+                    ui.selectable_value(&mut self.pen_mode, PenMode::Solid, "Solid");
+                    ui.selectable_value(&mut self.pen_mode, PenMode::Gradient, "🌈 Gradient")
+                        .on_hover_text("Cycle the hue along the stroke, starting from the current color");
+                    ui.selectable_value(&mut self.pen_mode, PenMode::Calligraphy, "🖋 Calligraphy")
+                        .on_hover_text("A broad nib: strokes are thick across the nib and thin along it");
+                    if self.pen_mode == PenMode::Calligraphy {
+                        ui.label("Nib:");
+                        let nib = ui.add(egui::DragValue::new(&mut self.settings.nib_angle_degrees).range(0.0..=180.0).suffix("°"))
+                            .on_hover_text("Angle of the nib's edge from horizontal");
+                        if (nib.lost_focus() || nib.drag_stopped())
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
+                        }
+                    }
+                    ui.label("Smoothing:");
+                    ui.add(egui::Slider::new(&mut self.smoothing_strength, 0.0..=1.0))
+                        .on_hover_text("Smooth new strokes when they are finished; existing strokes keep their shape");
+                    ui.label("Window:");
+                    let window = ui.add(egui::DragValue::new(&mut self.settings.smoothing_window).range(1..=32))
+                        .on_hover_text("Pointer positions averaged while drawing; larger is steadier but trails the pointer more");
+                    if (window.lost_focus() || window.drag_stopped())
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    ui.checkbox(&mut self.endpoint_snapping, "🧲 Snap ends")
+                        .on_hover_text("Connect the ends of new strokes to nearby stroke ends");
+                    ui.checkbox(&mut self.trace_mode, "🖋 Trace")
+                        .on_hover_text("Make the pen follow the nearest existing stroke, for inking over a rough sketch");
+                    ui.checkbox(&mut self.use_pressure, "✒ Pressure")
+                        .on_hover_text("Vary the width with stylus pressure; input without pressure keeps a constant width");
+                } else if self.current_tool.is_shape() {
                     ui.label("Stroke width:");
                     ui.add(egui::Slider::new(&mut self.stroke_width, 1.0..=10.0));
+                    ui.label("Opacity:");
+                    ui.add(egui::Slider::new(&mut self.stroke_opacity, 0.1..=1.0))
+                        .on_hover_text("Lower it for a highlighter; overlapping strokes darken");
+                    ui.label("Shift snaps to:");
+                    let previous_snap = self.settings.angle_snap_degrees;
+                    egui::ComboBox::from_id_source("angle_snap")
+                        .selected_text(format!("{:.0}°", self.settings.angle_snap_degrees))
+                        .show_ui(ui, |ui| {
+                            for degrees in ANGLE_SNAP_OPTIONS {
+                                ui.selectable_value(&mut self.settings.angle_snap_degrees, degrees, format!("{:.0}°", degrees));
+                            }
+                        });
+                    if self.settings.angle_snap_degrees != previous_snap
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    ui.checkbox(&mut self.endpoint_snapping, "🧲 Snap ends")
+                        .on_hover_text("Connect the ends of new lines to nearby stroke ends");
+                    if ui.checkbox(&mut self.settings.attach_shapes_to_text, "📎 Attach to text")
+                        .on_hover_text("Ends dropped on or near text move to its edge, pointing at its center; hold Shift to place them freely")
+                        .changed()
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
                 } else if self.current_tool == Tool::Text {
                     ui.label("Font size:");
                     ui.add(egui::Slider::new(&mut self.text_font_size, 10.0..=50.0));
+                    if self.is_notebook_mode {
+                        let mut per_notebook = self.notebook_font_size.is_some();
+                        if ui.checkbox(&mut per_notebook, "Notebook default")
+                            .on_hover_text("Save this font size with the notebook and use it whenever the notebook is opened")
+                            .changed()
+                        {
+                            self.notebook_font_size = per_notebook.then_some(self.text_font_size);
+                        }
+                    }
+                    ui.checkbox(&mut self.text_outline_enabled, "Outline")
+                        .on_hover_text("Draw a contrasting halo behind new text");
+                    if self.text_outline_enabled {
+                        ui.color_edit_button_srgba(&mut self.text_outline_color);
+                    }
+                    ui.checkbox(&mut self.text_shadow_enabled, "Shadow")
+                        .on_hover_text("Draw a drop shadow under new text so it stands out over busy drawings");
+                    if self.text_shadow_enabled {
+                        ui.color_edit_button_srgba(&mut self.text_shadow_color);
+                    }
+                    let mut grid_changed = ui.checkbox(&mut self.settings.snap_text_to_grid, "# Grid")
+                        .on_hover_text("Snap where new text is placed to a grid of points")
+                        .changed();
+                    if self.settings.snap_text_to_grid {
+                        let spacing = ui.add(egui::DragValue::new(&mut self.settings.text_grid_spacing)
+                            .range(5.0..=200.0)
+                            .suffix(" px"));
+                        grid_changed |= spacing.lost_focus() || spacing.drag_stopped();
+                    }
+                    if grid_changed
+                        && let Err(e) = self.settings.save()
+                    {
+                        self.notify(format!("Failed to save settings: {}", e), true);
+                    }
+                    ui.checkbox(&mut self.pin_new_text, "📌 Pin")
+                        .on_hover_text("Keep new text fixed on screen, unaffected by pan and zoom");
+                    orientation_combo(ui, "new_text_orientation", &mut self.text_orientation);
+                    ui.selectable_value(&mut self.single_line_text, true, "Label")
+                        .on_hover_text("A single line: Enter adds it");
+                    ui.selectable_value(&mut self.single_line_text, false, "Paragraph")
+                        .on_hover_text("Several lines: Enter starts a new line and Ctrl+Enter adds it");
                 } else if self.current_tool == Tool::Select {
                     ui.label("Selection tool active");
+                    ui.checkbox(&mut self.show_hover_highlight, "Highlight hover")
+                        .on_hover_text("Outline the element under the pointer");
+                    ui.checkbox(&mut self.smart_guides, "📐 Smart guides")
+                        .on_hover_text("Snap dragged elements into line with other elements' edges and centers");
+                    ui.checkbox(&mut self.focus_mode, "🔦 Focus")
+                        .on_hover_text("Dim everything except the selection");
+                    ui.checkbox(&mut self.enclosed_selection, "⬚ Enclosed only")
+                        .on_hover_text("Drag-select only elements entirely inside the rectangle; hold Alt while dragging for the other mode");
+                    ui.checkbox(&mut self.keep_proportions, "🔒 Keep proportions")
+                        .on_hover_text("Scale the selection's width and height alike from its corner handles, text included; hold Shift while dragging for the other mode");
+                    if self.has_selection() {
+                        ui.label("Build step:");
+                        ui.add(egui::DragValue::new(&mut self.build_order_input).range(1..=99));
+                        if ui.button("Set").on_hover_text("Reveal the selection at this step while presenting").clicked() {
+                            self.set_selection_build_order(Some(self.build_order_input));
+                        }
+                        if ui.button("Always").on_hover_text("Show the selection from the start").clicked() {
+                            self.set_selection_build_order(None);
+                        }
+                        if ui.button("📣 Pulse").on_hover_text("Fade the selection in and out to draw attention to it, including while presenting; any edit stops it").clicked() {
+                            self.toggle_selection_pulse();
+                        }
+                    }
+                    if !self.pulsing.is_empty() && ui.button("⏹ Stop pulsing").clicked() {
+                        self.pulsing.clear();
+                    }
+                    if self.has_selection() {
+                        ui.add(egui::TextEdit::singleline(&mut self.stamp_name_input)
+                            .hint_text("Stamp name")
+                            .desired_width(90.0));
+                        if ui.button("⭐ Save as stamp").on_hover_text("Keep the selection for placing again from the ⭐ Stamps menu, in any notebook").clicked() {
+                            self.save_selection_as_stamp();
+                        }
+                    }
                     if !self.selected_text_elements.is_empty() {
                         ui.label(format!("Selected: {} text element(s)", self.selected_text_elements.len()));
                         
                         // Copy button
                         if ui.button("📋 Copy").clicked() {
-                            if self.copy_selected_text_to_clipboard() {
-                                // Could add a status message here if needed
+                            // Could add a status message here if needed
+                            self.copy_selected_text_to_clipboard();
+                        }
+                        
+                        let all_pinned = self.selected_text_elements.iter()
+                            .all(|&idx| self.current_text_elements().get(idx).is_some_and(|t| t.pinned));
+                        if ui.button(if all_pinned { "📌 Unpin" } else { "📌 Pin" })
+                            .on_hover_text("Fix selected text to the screen, or return it to the canvas")
+                            .clicked()
+                        {
+                            self.set_selection_pinned(!all_pinned);
+                        }
+                        
+                        let mut orientation = self.selected_text_elements.first()
+                            .and_then(|&idx| self.current_text_elements().get(idx))
+                            .map_or(TextOrientation::Horizontal, |text_element| text_element.orientation);
+                        if orientation_combo(ui, "selection_orientation", &mut orientation) {
+                            self.set_selection_orientation(orientation);
+                        }
+                        
+                        if let Some((from, to)) = self.selected_text_pair() {
+                            if ui.button("🔗 Connect")
+                                .on_hover_text("Join the two selected text elements with a line that follows them when they move")
+                                .clicked()
+                            {
+                                self.connect_selected_text(false);
+                            }
+                            if ui.button("➡ Connect with arrow")
+                                .on_hover_text("Join them with an arrow pointing at the text selected second")
+                                .clicked()
+                            {
+                                self.connect_selected_text(true);
+                            }
+                            if self.current_page().connectors.iter().any(|connector| connector.joins(from, to))
+                                && ui.button("✂ Disconnect").clicked()
+                            {
+                                self.disconnect_selected_text();
+                            }
+                        }
+                        
+                        if ui.button("✏ To paths")
+                            .on_hover_text("Replace selected text with strokes tracing its letter outlines, which can be edited and recolored like drawings")
+                            .clicked()
+                        {
+                            self.convert_selection_to_paths();
+                        }
+                    }
+                    if !self.selected_strokes.is_empty() {
+                        ui.label(format!("{} stroke(s)", self.selected_strokes.len()));
+                        
+                        // Recolor every selected stroke at once, starting from the first one's color
+                        let mut selection_color = self.selected_strokes.first()
+                            .and_then(|&idx| self.current_strokes().get(idx))
+                            .map_or(self.stroke_color, |stroke| stroke.color);
+                        if ui.color_edit_button_srgba(&mut selection_color)
+                            .on_hover_text("Recolor selected strokes")
+                            .changed()
+                        {
+                            self.recolor_selection(selection_color);
+                        }
+                        
+                        if ui.button("➖").on_hover_text("Thin selected strokes").clicked() {
+                            self.scale_selection_width(1.0 / STROKE_WIDTH_STEP);
+                        }
+                        if ui.button("➕").on_hover_text("Thicken selected strokes").clicked() {
+                            self.scale_selection_width(STROKE_WIDTH_STEP);
+                        }
+                    }
+                } else if self.current_tool == Tool::Erase {
+                    egui::ComboBox::from_id_source("eraser_mode")
+                        .selected_text(self.eraser_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in EraserMode::ALL {
+                                ui.selectable_value(&mut self.eraser_mode, mode, mode.label());
                             }
+                        });
+                    if self.eraser_mode != EraserMode::Area {
+                        ui.label("Size:");
+                        let size_response = ui.add(egui::DragValue::new(&mut self.settings.eraser_radius)
+                            .range(2.0..=64.0)
+                            .suffix(" px"));
+                        let mut changed = size_response.lost_focus() || size_response.drag_stopped();
+                        changed |= ui.checkbox(&mut self.settings.show_eraser_cursor, "Show size")
+                            .on_hover_text("Outline what the eraser reaches around the pointer")
+                            .changed();
+                        if changed
+                            && let Err(e) = self.settings.save()
+                        {
+                            self.notify(format!("Failed to save settings: {}", e), true);
                         }
                     }
+                    ui.checkbox(&mut self.show_hover_highlight, "Highlight hover")
+                        .on_hover_text("Outline the stroke under the pointer");
+                }
+                
+                if matches!(self.current_tool, Tool::Line | Tool::Arrow | Tool::Curve | Tool::Text)
+                    && ui.checkbox(&mut self.settings.auto_select_after_create, "🔍 Then select")
+                        .on_hover_text("Switch to the Select tool with each new shape, curve or text selected, ready to adjust")
+                        .changed()
+                    && let Err(e) = self.settings.save()
+                {
+                    self.notify(format!("Failed to save settings: {}", e), true);
                 }
                 
                 ui.separator();
@@ -1374,10 +5878,74 @@ impl eframe::App for ScribbleApp {
                         (color[2] * 255.0) as u8,
                     );
                 }
+                self.show_color_presets(ui);
+                
+                ui.separator();
+                
+                // Tags for new elements and the view filter
+                ui.label("🏷");
+                ui.add(egui::TextEdit::singleline(&mut self.active_tag).desired_width(70.0).hint_text("tag"))
+                    .on_hover_text("Tag given to new strokes and text");
+                if self.current_tool == Tool::Select
+                    && self.has_selection()
+                    && ui.button("Apply").on_hover_text("Give the selection this tag (empty clears it)").clicked()
+                {
+                    self.tag_selection();
+                }
+                let tags = self.notebook_tags();
+                egui::ComboBox::from_id_source("tag_filter")
+                    .selected_text(self.tag_filter.as_deref().unwrap_or("All tags"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.tag_filter, None, "All tags");
+                        for tag in tags {
+                            let label = tag.clone();
+                            ui.selectable_value(&mut self.tag_filter, Some(tag), label);
+                        }
+                    });
+                if self.tag_filter.is_some() {
+                    ui.checkbox(&mut self.hide_filtered, "Hide others")
+                        .on_hover_text("Hide elements without the tag instead of dimming them");
+                }
                 
                 ui.separator();
                 
                 ui.label(format!("Strokes: {} | Text: {}", self.current_strokes().len(), self.current_text_elements().len()));
+                
+                // Session time is shown in whole minutes, so a slow repaint keeps it current
+                let session_minutes = self.session_start.elapsed().as_secs() / 60;
+                ui.label(format!("Session: {}h {:02}m | Ink: {:.0} px", session_minutes / 60, session_minutes % 60, self.session_ink))
+                    .on_hover_text("Time since launch and total length of strokes drawn this session");
+                ctx.request_repaint_after(std::time::Duration::from_secs(10));
+                
+                ui.separator();
+                
+                // View controls for the current page's camera
+                ui.label(format!("Zoom: {:.0}%", self.current_page().camera.zoom * 100.0));
+                if ui.button("🎯 Reset View").clicked() {
+                    self.current_page_mut().camera = Camera::default();
+                }
+                if ui.button("⟲").on_hover_text("Rotate the view a quarter turn counterclockwise").clicked() {
+                    let center = self.canvas_center;
+                    self.current_page_mut().camera.rotate_around(center, -1);
+                }
+                if ui.button("⟳").on_hover_text("Rotate the view a quarter turn clockwise").clicked() {
+                    let center = self.canvas_center;
+                    self.current_page_mut().camera.rotate_around(center, 1);
+                }
+                ui.checkbox(&mut self.show_origin, "📐 Origin")
+                    .on_hover_text("Show axes through the canvas origin and the pointer's canvas coordinates");
+                
+                ui.separator();
+                
+                if ui.selectable_label(self.show_help, "❓").on_hover_text("Shortcuts and tool tips").clicked() {
+                    self.show_help = !self.show_help;
+                }
+                if ui.selectable_label(self.show_log, "📜").on_hover_text("Message log").clicked() {
+                    self.show_log = !self.show_log;
+                }
+                if ui.selectable_label(self.presenting, "▶ Present").on_hover_text("Step through build orders (F5)").clicked() {
+                    self.set_presenting(!self.presenting);
+                }
             });
             
             // Search bar (only shown when search is enabled)
@@ -1391,18 +5959,83 @@ impl eframe::App for ScribbleApp {
                             .desired_width(200.0)
                     );
                     
-                    ui.checkbox(&mut self.regex_mode, "Regex");
+                    if ui.checkbox(&mut self.regex_mode, "Regex").changed() {
+                        self.perform_search();
+                    }
+                    if self.regex_mode
+                        && ui.checkbox(&mut self.regex_empty_matches, "Empty matches")
+                            .on_hover_text("Count zero-width matches, like `a*` between letters, as matches; off skips them")
+                            .changed()
+                    {
+                        self.perform_search();
+                    }
+                    ui.checkbox(&mut self.show_search_preview, "Preview")
+                        .on_hover_text("List the first matches on every page under the search field");
+                    if ui.selectable_label(self.show_results_panel, "📋 All results")
+                        .on_hover_text("List every match in the notebook in a side panel")
+                        .clicked()
+                    {
+                        self.show_results_panel = !self.show_results_panel;
+                        self.perform_search();
+                    }
                     
                     if search_response.changed() {
                         self.perform_search();
                     }
                     
+                    // Dropdown of matches while the field is being typed in. A press on an entry
+                    // takes focus from the field, so it stays up for that one frame to register.
+                    let field_active = search_response.has_focus() || search_response.lost_focus();
+                    if self.show_search_preview && field_active && !self.search_preview.is_empty() {
+                        let mut chosen = None;
+                        egui::Area::new(egui::Id::new("search_preview"))
+                            .order(egui::Order::Foreground)
+                            .fixed_pos(search_response.rect.left_bottom())
+                            .show(ui.ctx(), |ui| {
+                                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                                    for entry in &self.search_preview {
+                                        let label = format!("p.{}  {}", entry.page_index + 1, entry.snippet);
+                                        // Pressed rather than clicked: the dropdown is gone by the release
+                                        if ui.add(egui::Button::new(label).frame(false)).is_pointer_button_down_on() {
+                                            chosen = Some((entry.page_index, entry.element_index));
+                                        }
+                                    }
+                                });
+                            });
+                        if let Some((page_index, element_index)) = chosen {
+                            self.open_search_preview_entry(page_index, element_index, ctx.screen_rect().size());
+                        }
+                    }
+                    
                     if ui.button("Clear Search").clicked() {
                         self.search_query.clear();
                         self.search_results.clear();
+                        self.search_preview.clear();
                         self.search_error = None;
                     }
                     
+                    ui.separator();
+                    
+                    ui.label("Replace:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.replace_text)
+                            .hint_text(if self.regex_mode { "Replacement ($1 for groups)" } else { "Replacement" })
+                            .desired_width(150.0)
+                    );
+                    let can_replace = !self.search_results.is_empty();
+                    if ui.add_enabled(can_replace, egui::Button::new("Replace"))
+                        .on_hover_text("Replace the first match on this page")
+                        .clicked()
+                    {
+                        self.replace_matches(false);
+                    }
+                    if ui.add_enabled(can_replace, egui::Button::new("Replace All"))
+                        .on_hover_text("Replace every match on this page")
+                        .clicked()
+                    {
+                        self.replace_matches(true);
+                    }
+                    
                     // Show search results count
                     if !self.search_query.is_empty() {
                         if let Some(error) = &self.search_error {
@@ -1416,6 +6049,22 @@ impl eframe::App for ScribbleApp {
                         }
                     }
                 });
+                ui.horizontal(|ui| {
+                    ui.label("🎨 By color:");
+                    ui.color_edit_button_srgba(&mut self.color_search);
+                    if ui.small_button("Pen color").on_hover_text("Look for the current pen color").clicked() {
+                        self.color_search = self.stroke_color;
+                    }
+                    ui.label("Tolerance:");
+                    ui.add(egui::Slider::new(&mut self.color_search_tolerance, 0..=128))
+                        .on_hover_text("How far each of red, green and blue may differ and still match");
+                    if ui.button("Select matching")
+                        .on_hover_text("Select the strokes and text in this color on the current page; text counts as black or as its outline color")
+                        .clicked()
+                    {
+                        self.select_by_color();
+                    }
+                });
                 ui.separator();
             }
 
@@ -1427,6 +6076,9 @@ impl eframe::App for ScribbleApp {
             
             // Draw faded grey background
             let canvas_rect = response.rect;
+            self.canvas_origin = canvas_rect.min;
+            self.canvas_center = canvas_rect.center();
+            self.canvas_pointer = ctx.input(|i| i.pointer.latest_pos()).filter(|pos| canvas_rect.contains(*pos));
             painter.rect_filled(
                 canvas_rect,
                 egui::Rounding::ZERO,
@@ -1436,221 +6088,704 @@ impl eframe::App for ScribbleApp {
             // Detect arrow collisions before drawing
             self.detect_arrow_collisions(&painter);
             
-            // Handle mouse input based on selected tool
-            if self.current_tool == Tool::Draw {
-                // Drawing logic
-                if let Some(pointer_pos) = response.interact_pointer_pos() {
-                    if response.drag_started() {
+            // Pan with the middle mouse button or scroll wheel, zoom with Ctrl+scroll or pinch
+            if response.dragged_by(egui::PointerButton::Middle) {
+                self.current_page_mut().camera.pan(response.drag_delta());
+            }
+            if response.hovered() {
+                let (scroll_delta, zoom_delta, hover_pos) = ui.input(|i| {
+                    (i.smooth_scroll_delta, i.zoom_delta(), i.pointer.hover_pos())
+                });
+                let camera = &mut self.current_page_mut().camera;
+                if zoom_delta != 1.0
+                    && let Some(hover_pos) = hover_pos
+                {
+                    camera.zoom_around(hover_pos, zoom_delta);
+                }
+                camera.pan(scroll_delta);
+            }
+            
+            let scrollbar_thumbs = self.canvas_scrollbars(ui, canvas_rect);
+            
+            // All element positions are stored in canvas coordinates
+            let camera = self.current_page().camera;
+            let canvas_pointer_pos = response.interact_pointer_pos().map(|pos| camera.to_canvas(pos));
+            
+            // Handle mouse input based on selected tool; presenting replaces editing with stepping
+            if !self.presenting && self.handle_guide_drag(ctx, &response, canvas_rect) {
+                // A custom guide being dragged takes the pointer from the tool
+            } else if self.presenting {
+                if response.clicked() {
+                    self.advance_build();
+                } else if response.secondary_clicked() {
+                    self.retreat_build();
+                }
+            } else if let Some(stamp_idx) = self.placing_stamp {
+                // Each click drops a copy; the tool resumes once placing stops
+                if response.clicked()
+                    && let Some(pointer_pos) = canvas_pointer_pos
+                {
+                    self.place_stamp(stamp_idx, pointer_pos);
+                } else if response.secondary_clicked() || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    self.placing_stamp = None;
+                }
+            } else if self.current_tool == Tool::Draw {
+                // Drawing logic; stylus force arrives as touch events alongside the pointer
+                let force = if self.use_pressure {
+                    ui.input(|i| i.events.iter().rev().find_map(|event| match event {
+                        egui::Event::Touch { force, .. } => *force,
+                        _ => None,
+                    }))
+                } else {
+                    None
+                };
+                if let Some(pointer_pos) = canvas_pointer_pos {
+                    if response.drag_started_by(egui::PointerButton::Primary) {
                         self.is_drawing = true;
                         self.current_stroke.clear();
-                        self.current_stroke.push(pointer_pos);
-                    } else if self.is_drawing && response.dragged() {
-                        self.current_stroke.push(pointer_pos);
+                        self.current_stroke.push(if self.trace_mode { self.trace_snap(pointer_pos) } else { pointer_pos });
+                        self.current_pressures.clear();
+                        self.current_pressures.push(force);
+                        self.recent_input.clear();
+                        self.recent_input.push_back(pointer_pos);
+                    } else if self.is_drawing && response.dragged_by(egui::PointerButton::Primary) {
+                        // Each captured point averages the latest positions, trailing the pointer slightly
+                        self.recent_input.push_back(pointer_pos);
+                        while self.recent_input.len() > self.settings.smoothing_window.max(1) {
+                            self.recent_input.pop_front();
+                        }
+                        let sum = self.recent_input.iter().fold(egui::Vec2::ZERO, |sum, pos| sum + pos.to_vec2());
+                        let next = (sum / self.recent_input.len() as f32).to_pos2();
+                        let next = if self.trace_mode { self.trace_snap(next) } else { next };
+                        let pressure = force.or(self.current_pressures.last().copied().flatten());
+                        // A pointer moving faster than the frame rate leaves gaps, which are filled along a curve
+                        if let Some(&last) = self.current_stroke.last() {
+                            let before = self.current_stroke.len().checked_sub(2).map(|idx| self.current_stroke[idx]);
+                            let filler = fill_capture_gap(before, last, next, self.settings.max_capture_gap / camera.zoom);
+                            self.current_pressures.extend(std::iter::repeat_n(pressure, filler.len()));
+                            self.current_stroke.extend(filler);
+                        }
+                        self.current_stroke.push(next);
+                        self.current_pressures.push(pressure);
                     }
                 }
                 
-                if response.drag_stopped() {
-                    if self.is_drawing && self.current_stroke.len() > 1 {
-                        let stroke_points = self.current_stroke.clone();
-                        let stroke_color = self.stroke_color;
-                        let stroke_width = self.stroke_width;
-                        
-                        self.current_strokes_mut().push(Stroke {
-                            points: stroke_points,
-                            color: stroke_color,
-                            width: stroke_width,
-                        });
+                if response.drag_stopped_by(egui::PointerButton::Primary) {
+                    // Catch the averaged line up to where the pointer was released
+                    let last_input = self.recent_input.back().map(|&pos| if self.trace_mode { self.trace_snap(pos) } else { pos });
+                    if self.is_drawing
+                        && let Some(last_input) = last_input
+                        && self.current_stroke.last() != Some(&last_input)
+                    {
+                        self.current_stroke.push(last_input);
+                        self.current_pressures.push(self.current_pressures.last().copied().flatten());
+                    }
+                    self.recent_input.clear();
+                    // Jitter that never got far from where the press started is dropped, not kept as a speck
+                    let reach = self.current_stroke.first().map_or(0.0, |&start| {
+                        self.current_stroke.iter().map(|point| point.distance(start)).fold(0.0, f32::max)
+                    }) * camera.zoom;
+                    if self.is_drawing && self.current_stroke.len() > 1 && reach >= self.settings.min_stroke_distance {
+                        let mut raw_points = self.current_stroke.clone();
+                        if self.endpoint_snapping {
+                            let endpoints = self.stroke_endpoints();
+                            let last = raw_points.len() - 1;
+                            raw_points[0] = self.snap_to_endpoints(raw_points[0], &endpoints);
+                            raw_points[last] = self.snap_to_endpoints(raw_points[last], &endpoints);
+                        }
+                        let last = raw_points.len() - 1;
+                        raw_points[0] = self.snap_to_page_guides(raw_points[0]);
+                        raw_points[last] = self.snap_to_page_guides(raw_points[last]);
+                        // Smooth once here so the saved points are exactly what gets rendered and exported
+                        let smoothing = self.smoothing_strength;
+                        let stroke_points = smooth_points(&raw_points, smoothing);
+                        let stroke_colors = if self.pen_mode == PenMode::Gradient {
+                            self.gradient_colors(&stroke_points)
+                        } else {
+                            Vec::new()
+                        };
+                        let mut stroke_pressures = stroke_pressures(&self.current_pressures);
+                        if self.pen_mode == PenMode::Calligraphy {
+                            // The nib shapes the width on top of any stylus pressure
+                            let widths = calligraphy_widths(&stroke_points, self.settings.nib_angle_degrees);
+                            stroke_pressures = if stroke_pressures.is_empty() {
+                                widths
+                            } else {
+                                widths.iter().zip(&stroke_pressures).map(|(width, pressure)| width * pressure).collect()
+                            };
+                        }
+                        self.commit_stroke("Draw stroke", stroke_points, stroke_colors, stroke_pressures, smoothing);
                     }
                     self.current_stroke.clear();
+                    self.current_pressures.clear();
                     self.is_drawing = false;
                 }
-            } else if self.current_tool == Tool::Text {
-                // Text placement logic
-                if response.clicked() {
-                    if let Some(pointer_pos) = response.interact_pointer_pos() {
-                        self.active_text_position = Some(pointer_pos);
-                        self.text_input.clear();
-                        // Request focus for the text input that will appear
-                        ui.memory_mut(|mem| mem.request_focus(self.text_input_id));
+            } else if self.current_tool.is_shape() {
+                // Lines and arrows: the preview in current_stroke is rebuilt from the anchor each frame
+                if let Some(pointer_pos) = canvas_pointer_pos {
+                    if response.drag_started_by(egui::PointerButton::Primary) {
+                        let start = if self.endpoint_snapping {
+                            self.snap_to_endpoints(pointer_pos, &self.stroke_endpoints())
+                        } else {
+                            pointer_pos
+                        };
+                        let start = self.snap_to_page_guides(start);
+                        self.shape_start = Some(start);
+                        self.is_drawing = true;
+                    }
+                    if self.is_drawing
+                        && let Some(start) = self.shape_start
+                    {
+                        let shift = ctx.input(|i| i.modifiers.shift);
+                        let mut end = self.constrain_shape_end(start, pointer_pos, shift);
+                        if self.endpoint_snapping && !shift {
+                            end = self.snap_to_endpoints(end, &self.stroke_endpoints());
+                        }
+                        if !shift {
+                            end = self.snap_to_page_guides(end);
+                        }
+                        self.current_stroke = shape_points(&self.current_tool, start, end, self.stroke_width);
+                    }
+                }
+                
+                if response.drag_stopped_by(egui::PointerButton::Primary) {
+                    if self.is_drawing && self.current_stroke.len() > 1 && self.current_stroke[0] != self.current_stroke[1] {
+                        let label = if self.current_tool == Tool::Arrow { "Draw arrow" } else { "Draw line" };
+                        if self.settings.attach_shapes_to_text && !ctx.input(|i| i.modifiers.shift) {
+                            let (start, end) = self.attach_shape_to_text(self.current_stroke[0], self.current_stroke[1]);
+                            if start != end {
+                                self.current_stroke = shape_points(&self.current_tool, start, end, self.stroke_width);
+                            }
+                        }
+                        let points = std::mem::take(&mut self.current_stroke);
+                        self.commit_stroke(label, points, Vec::new(), Vec::new(), 0.0);
+                        self.auto_select_created(false);
+                    }
+                    self.current_stroke.clear();
+                    self.shape_start = None;
+                    self.is_drawing = false;
+                }
+            } else if self.current_tool == Tool::Curve {
+                // Clicks add corner points and drags pull out the new point's tangent handle
+                if response.double_clicked() {
+                    self.finish_curve();
+                } else if let Some(pointer_pos) = canvas_pointer_pos {
+                    if response.drag_started_by(egui::PointerButton::Primary) || response.clicked() {
+                        // A drag begins where the button went down, not where it was recognized
+                        let press_origin = ctx.input(|i| i.pointer.press_origin()).map_or(pointer_pos, |pos| camera.to_canvas(pos));
+                        let point = self.snap_to_page_guides(press_origin);
+                        self.curve_anchors.push(CurveAnchor { point, handle: egui::Vec2::ZERO });
+                    }
+                    if response.dragged_by(egui::PointerButton::Primary)
+                        && let Some(anchor) = self.curve_anchors.last_mut()
+                    {
+                        anchor.handle = pointer_pos - anchor.point;
+                    }
+                }
+                if !ctx.wants_keyboard_input() {
+                    if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        self.finish_curve();
+                    } else if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        self.cancel_curve();
                     }
                 }
+                
+                // Preview the placed curve plus a segment following the pointer
+                let mut preview = self.curve_anchors.clone();
+                if !response.dragged()
+                    && let Some(hover_pos) = response.hover_pos()
+                    && !preview.is_empty()
+                {
+                    preview.push(CurveAnchor { point: camera.to_canvas(hover_pos), handle: egui::Vec2::ZERO });
+                }
+                self.current_stroke = curve_points(&preview);
+            } else if self.current_tool == Tool::Text {
+                // Text placement logic; double-clicking existing text edits it instead
+                if response.double_clicked()
+                    && let Some(pointer_pos) = canvas_pointer_pos
+                    && let Some(idx) = self.get_text_element_at_position(pointer_pos)
+                {
+                    self.edit_text_element(ctx, idx, pointer_pos);
+                } else if response.clicked()
+                    && let Some(pointer_pos) = canvas_pointer_pos
+                {
+                    self.close_text_editor();
+                    let pointer_pos = if self.settings.snap_text_to_grid {
+                        snap_to_grid(pointer_pos, self.settings.text_grid_spacing)
+                    } else {
+                        self.snap_to_page_guides(pointer_pos)
+                    };
+                    let pointer_pos = self.keep_within_page(pointer_pos);
+                    self.active_text_position = Some(pointer_pos);
+                    // Request focus for the text input that will appear
+                    ui.memory_mut(|mem| mem.request_focus(self.text_input_id));
+                }
             } else if self.current_tool == Tool::Select {
                 // Text selection and dragging logic
-                if let Some(pointer_pos) = response.interact_pointer_pos() {
-                    if response.drag_started() {
-                        // Check if we clicked on a selected text element to start dragging
-                        let clicked_element = self.get_text_element_at_position(pointer_pos);
-                        if let Some(element_idx) = clicked_element {
-                            if self.selected_text_elements.contains(&element_idx) {
-                                // Start dragging selected elements, don't start selection
-                                self.selection_start = Some(pointer_pos);
-                                self.is_selecting_text = false;
-                            } else {
-                                // Clicked on unselected text, start new selection
-                                self.is_selecting_text = true;
-                                self.selection_start = Some(pointer_pos);
-                                self.selection_end = Some(pointer_pos);
-                                self.selected_text_elements.clear();
-                            }
+                if let Some(pointer_pos) = canvas_pointer_pos {
+                    let screen_pointer_pos = camera.to_screen(pointer_pos);
+                    // Handles are small, so test where the press began rather than where the drag was recognized
+                    let press_origin = ctx.input(|i| i.pointer.press_origin()).unwrap_or(screen_pointer_pos);
+                    if response.drag_started_by(egui::PointerButton::Primary)
+                        && (self.start_text_resize(ctx, press_origin) || self.start_selection_resize(ctx, press_origin))
+                    {
+                        // Corner handles take priority over moving or reselecting
+                    } else if self.text_resize.is_some() {
+                        if response.dragged_by(egui::PointerButton::Primary) {
+                            self.update_text_resize(screen_pointer_pos);
+                        }
+                    } else if self.selection_resize.is_some() {
+                        if response.dragged_by(egui::PointerButton::Primary) {
+                            self.update_selection_resize(screen_pointer_pos, self.keep_proportions != ctx.input(|i| i.modifiers.shift));
+                        }
+                    } else if response.drag_started_by(egui::PointerButton::Primary) {
+                        // Check if we clicked on a selected element to start dragging
+                        let on_selected_text = self.get_text_element_at_position(pointer_pos)
+                            .is_some_and(|idx| self.selected_text_elements.contains(&idx));
+                        let on_selected_stroke = self.get_stroke_at_position(pointer_pos)
+                            .is_some_and(|idx| self.selected_strokes.contains(&idx));
+                        if on_selected_text || on_selected_stroke {
+                            // Start dragging selected elements, don't start selection
+                            self.push_undo("Move selection");
+                            self.selection_start = Some(pointer_pos);
+                            self.guide_snap_residual = egui::Vec2::ZERO;
+                            self.is_selecting_text = false;
                         } else {
-                            // Clicked in empty space, start new selection
+                            // Clicked on unselected content or empty space, start new selection
                             self.is_selecting_text = true;
                             self.selection_start = Some(pointer_pos);
                             self.selection_end = Some(pointer_pos);
                             self.selected_text_elements.clear();
+                            self.selected_strokes.clear();
                         }
-                    } else if response.dragged() {
+                    } else if response.dragged_by(egui::PointerButton::Primary) {
                         if self.is_selecting_text {
                             // Update selection area
                             self.selection_end = Some(pointer_pos);
-                            self.update_text_selection();
-                        } else if !self.selected_text_elements.is_empty() {
-                            // Handle dragging of selected text
+                            self.update_text_selection(self.enclosed_selection != ctx.input(|i| i.modifiers.alt));
+                        } else if self.has_selection() {
+                            // Handle dragging of selected elements
                             self.drag_selected_text(pointer_pos);
                         }
                     }
                     
+                    if response.clicked() {
+                        let modifiers = ctx.input(|i| i.modifiers);
+                        if modifiers.alt {
+                            self.cycle_text_selection_at(pointer_pos);
+                        } else {
+                            self.click_select_at(pointer_pos, modifiers.shift);
+                        }
+                    }
+                    
                     // Clear selection on single click in empty space
-                    if response.clicked() && self.get_text_element_at_position(pointer_pos).is_none() {
+                    if response.clicked()
+                        && self.get_text_element_at_position(pointer_pos).is_none()
+                        && self.get_stroke_at_position(pointer_pos).is_none()
+                    {
                         self.selected_text_elements.clear();
+                        self.selected_strokes.clear();
+                    }
+                }
+                
+                if response.drag_stopped_by(egui::PointerButton::Primary) {
+                    self.text_resize = None;
+                    self.selection_resize = None;
+                    self.active_guides = [None, None];
+                    if self.is_selecting_text {
+                        self.is_selecting_text = false;
+                        self.update_text_selection(self.enclosed_selection != ctx.input(|i| i.modifiers.alt));
+                    }
+                }
+            } else if self.current_tool == Tool::Erase {
+                if response.drag_started_by(egui::PointerButton::Primary) || response.clicked() {
+                    self.erase_pass_recorded = false;
+                }
+                let radius = self.settings.eraser_radius / camera.zoom;
+                if let Some(pointer_pos) = canvas_pointer_pos {
+                    let erasing = response.clicked() || response.dragged_by(egui::PointerButton::Primary);
+                    match self.eraser_mode {
+                        EraserMode::Stroke if erasing => self.erase_strokes_at(pointer_pos, radius),
+                        EraserMode::Segment if erasing => self.erase_segments_at(pointer_pos, radius),
+                        EraserMode::Area => {
+                            if response.drag_started_by(egui::PointerButton::Primary) {
+                                self.erase_area = Some((pointer_pos, pointer_pos));
+                            } else if let Some((_, end)) = &mut self.erase_area {
+                                *end = pointer_pos;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if response.drag_stopped_by(egui::PointerButton::Primary) {
+                    if let Some((start, end)) = self.erase_area.take() {
+                        self.erase_in_rect(egui::Rect::from_two_pos(start, end));
+                    }
+                    self.erase_pass_recorded = false;
+                }
+            }
+            
+            // Show floating text input if active
+            if let Some(text_pos) = self.active_text_position {
+                let text_area = egui::Area::new(egui::Id::new("floating_text_area"))
+                    .fixed_pos(camera.to_screen(text_pos))
+                    .order(egui::Order::Foreground);
+                
+                // Text that already spans lines keeps the multiline editor even in label mode
+                let single_line = self.single_line_text && !self.text_input.contains('\n');
+                text_area.show(ctx, |ui| {
+                    ui.group(|ui| {
+                        ui.vertical(|ui| {
+                            ui.label(if self.editing_text.is_some() {
+                                "Edit text (empty it to delete):"
+                            } else if single_line {
+                                "Type a label:"
+                            } else {
+                                "Type your text (multiline supported):"
+                            });
+                            
+                            let text_edit_response = if single_line {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut self.text_input)
+                                        .id(self.text_input_id)
+                                        .desired_width(250.0)
+                                        .font(egui::TextStyle::Body)
+                                )
+                            } else {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut self.text_input)
+                                        .id(self.text_input_id)
+                                        .desired_width(250.0)
+                                        .desired_rows(5)
+                                        .font(egui::TextStyle::Body)
+                                )
+                            };
+                            
+                            // A single-line editor gives up focus when Enter is pressed
+                            if single_line && text_edit_response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                self.commit_text_editor(text_pos);
+                                return;
+                            }
+                            
+                            // Auto-focus the text input when it first appears
+                            if text_edit_response.gained_focus() {
+                                ui.memory_mut(|mem| mem.request_focus(self.text_input_id));
+                            }
+                            
+                            ui.horizontal(|ui| {
+                                if ui.button(if self.editing_text.is_some() { "✅ Done" } else { "✅ Add" }).clicked() {
+                                    self.commit_text_editor(text_pos);
+                                }
+                                
+                                if ui.button("❌ Cancel").clicked() || ui.input_mut(|i| i.consume_shortcut(&SHORTCUT_CANCEL_TEXT)) {
+                                    self.close_text_editor();
+                                }
+                            });
+                            
+                            ui.label(format!(
+                                "{} to add, {} to cancel",
+                                if single_line { "Enter".to_string() } else { ctx.format_shortcut(&SHORTCUT_ADD_TEXT) },
+                                ctx.format_shortcut(&SHORTCUT_CANCEL_TEXT),
+                            ));
+                            
+                            // Handle Ctrl+Enter to add text
+                            if ui.input_mut(|i| i.consume_shortcut(&SHORTCUT_ADD_TEXT)) {
+                                self.commit_text_editor(text_pos);
+                            }
+                        });
+                    });
+                });
+            }
+            
+            // Outline what the Select or Erase tool would act on under the pointer
+            let (hovered_text, hovered_stroke) = match response.hover_pos() {
+                Some(hover_pos) if self.show_hover_highlight && !ctx.input(|i| i.pointer.any_down()) => {
+                    let hover_pos = camera.to_canvas(hover_pos);
+                    match self.current_tool {
+                        Tool::Select => match self.get_text_element_at_position(hover_pos) {
+                            Some(idx) => (Some(idx), None),
+                            None => (None, self.get_stroke_at_position(hover_pos)),
+                        },
+                        Tool::Erase => (None, self.get_stroke_at_position(hover_pos)),
+                        _ => (None, None),
+                    }
+                }
+                _ => (None, None),
+            };
+            
+            // While a page transition runs, slide the page in from the side and fade it up.
+            // Only drawing is offset; input above already used the real camera.
+            let mut camera = camera;
+            let mut painter = painter;
+            if let Some(transition) = self.page_transition {
+                let t = transition.started.elapsed().as_secs_f32() / PAGE_TRANSITION_DURATION;
+                if t >= 1.0 || !self.animate_page_transitions {
+                    self.page_transition = None;
+                } else {
+                    // Ease out so the page settles gently
+                    let remaining = (1.0 - t).powi(3);
+                    camera.pan(egui::Vec2::new(transition.direction * canvas_rect.width() * PAGE_TRANSITION_DISTANCE * remaining, 0.0));
+                    painter.multiply_opacity(1.0 - remaining);
+                    ctx.request_repaint();
+                }
+            }
+            
+            // Pulsing elements animate for as long as they pulse
+            if !self.pulsing.is_empty() {
+                ctx.request_repaint();
+            }
+            
+            // Draw faint axes and a crosshair at the canvas origin
+            if self.show_origin {
+                let origin = camera.to_screen(egui::Pos2::ZERO);
+                let axis_color = egui::Color32::from_rgba_unmultiplied(200, 60, 60, 90);
+                painter.hline(canvas_rect.x_range(), origin.y, egui::Stroke::new(1.0, axis_color));
+                painter.vline(origin.x, canvas_rect.y_range(), egui::Stroke::new(1.0, axis_color));
+                painter.circle_stroke(origin, 6.0, egui::Stroke::new(1.5, axis_color));
+            }
+            
+            // Points new text snaps to, while placing text; too dense a grid is left undrawn
+            let grid_spacing = self.settings.text_grid_spacing * camera.zoom;
+            if self.current_tool == Tool::Text
+                && self.settings.snap_text_to_grid
+                && grid_spacing >= TEXT_GRID_MIN_SCREEN_SPACING
+            {
+                let grid_color = egui::Color32::from_rgba_unmultiplied(120, 120, 140, 90);
+                // Step back to the first grid point inside the view, wherever the rotation put the snapped one
+                let snapped = camera.to_screen(snap_to_grid(camera.to_canvas(canvas_rect.min), self.settings.text_grid_spacing));
+                let first = canvas_rect.min + egui::Vec2::new(
+                    (snapped.x - canvas_rect.min.x).rem_euclid(grid_spacing),
+                    (snapped.y - canvas_rect.min.y).rem_euclid(grid_spacing),
+                );
+                let mut y = first.y;
+                while y <= canvas_rect.max.y {
+                    let mut x = first.x;
+                    while x <= canvas_rect.max.x {
+                        painter.circle_filled(egui::Pos2::new(x, y), 1.0, grid_color);
+                        x += grid_spacing;
+                    }
+                    y += grid_spacing;
+                }
+            }
+            
+            // The page edge content is being kept inside
+            if self.settings.keep_within_page && !self.presenting {
+                painter.rect_stroke(
+                    camera.rect_to_screen(self.page_rect()),
+                    egui::Rounding::ZERO,
+                    egui::Stroke::new(1.0, egui::Color32::from_gray(150)),
+                );
+            }
+            
+            // Faint boundary around the page's content, with bars on view edges it runs past
+            if self.settings.show_content_extent
+                && !self.presenting
+                && let Some(extent) = self.content_extent()
+            {
+                let extent = camera.rect_to_screen(extent).expand(CONTENT_EXTENT_PADDING);
+                let extent_color = egui::Color32::from_rgba_unmultiplied(120, 120, 140, 70);
+                let corners = [extent.left_top(), extent.right_top(), extent.right_bottom(), extent.left_bottom(), extent.left_top()];
+                painter.extend(egui::Shape::dashed_line(&corners, egui::Stroke::new(1.0, extent_color), 6.0, 4.0));
+                // Content wholly off to a corner still marks both edges, at their nearest end
+                let view = canvas_rect.shrink(2.0);
+                let edge_stroke = egui::Stroke::new(3.0, extent_color);
+                let along = |range: egui::Rangef, edge: egui::Rangef| {
+                    let min = range.min.clamp(edge.min, edge.max - 12.0);
+                    egui::Rangef::new(min, range.max.clamp(edge.min + 12.0, edge.max).max(min + 12.0))
+                };
+                if extent.min.x < canvas_rect.min.x {
+                    painter.vline(view.min.x, along(extent.y_range(), view.y_range()), edge_stroke);
+                }
+                if extent.max.x > canvas_rect.max.x {
+                    painter.vline(view.max.x, along(extent.y_range(), view.y_range()), edge_stroke);
+                }
+                if extent.min.y < canvas_rect.min.y {
+                    painter.hline(along(extent.x_range(), view.x_range()), view.min.y, edge_stroke);
+                }
+                if extent.max.y > canvas_rect.max.y {
+                    painter.hline(along(extent.x_range(), view.x_range()), view.max.y, edge_stroke);
+                }
+            }
+            
+            // Draw completed strokes
+            for (index, stroke) in self.current_strokes().iter().enumerate() {
+                if self.hidden_by_build(stroke.build_order) {
+                    continue;
+                }
+                let Some(mut painter) = self.tag_filtered_painter(&painter, &stroke.tag) else {
+                    continue;
+                };
+                painter.multiply_opacity(stroke.opacity);
+                if self.dimmed_by_focus(self.selected_strokes.contains(&index)) {
+                    painter.multiply_opacity(FOCUS_DIM_OPACITY);
+                }
+                painter.multiply_opacity(self.pulse_opacity(ctx, ElementRef::Stroke(index)));
+                if stroke.is_drawable() {
+                    let points: Vec<egui::Pos2> = stroke.points.iter().map(|&p| camera.to_screen(p)).collect();
+                    let width = stroke.width * camera.zoom;
+                    
+                    // Draw selection highlight underneath selected strokes
+                    if self.selected_strokes.contains(&index) {
+                        painter.add(egui::Shape::line(
+                            points.clone(),
+                            egui::Stroke::new(width + 6.0, egui::Color32::from_rgba_premultiplied(100, 150, 255, 80)),
+                        ));
+                    } else if hovered_stroke == Some(index) {
+                        painter.add(egui::Shape::line(
+                            points.clone(),
+                            egui::Stroke::new(width + 4.0, egui::Color32::from_black_alpha(40)),
+                        ));
+                    }
+                    
+                    if stroke.is_gradient() || stroke.is_pressure_sensitive() {
+                        // Draw each segment in its own color and width, with round joints to hide seams
+                        for (i, segment) in points.windows(2).enumerate() {
+                            let width = stroke.segment_width(i) * camera.zoom;
+                            let color = stroke.segment_color(i);
+                            painter.line_segment([segment[0], segment[1]], egui::Stroke::new(width, color));
+                            painter.circle_filled(segment[1], width / 2.0, stroke.segment_color(i + 1));
+                        }
+                    } else if stroke.width >= export::OUTLINE_STROKE_MIN_WIDTH {
+                        painter.add(thick_stroke_mesh(&points, width, stroke.color));
+                    } else {
+                        painter.add(egui::Shape::line(
+                            points,
+                            egui::Stroke::new(width, stroke.color),
+                        ));
                     }
                 }
-                
-                if response.drag_stopped() {
-                    if self.is_selecting_text {
-                        self.is_selecting_text = false;
-                        self.update_text_selection();
+            }
+            
+            // Connectors follow the current bounds of the text they join
+            let connector_color = rgb_to_color(scribble_core::CONNECTOR_COLOR);
+            for connector in &self.current_page().connectors {
+                let screen_box = |idx: usize| {
+                    let text_element = self.current_text_elements().get(idx)?;
+                    if self.hidden_by_tag_filter(&text_element.tag) || self.hidden_by_build(text_element.build_order) {
+                        return None;
                     }
+                    let element_camera = if text_element.pinned { self.pinned_camera() } else { camera };
+                    let rect = Self::text_line_rects(text_element).into_iter().reduce(|a, b| a.union(b))?;
+                    let rect = element_camera.rect_to_screen(rect);
+                    Some([rect.min.x, rect.min.y, rect.width(), rect.height()])
+                };
+                if let (Some(from), Some(to)) = (screen_box(connector.from), screen_box(connector.to)) {
+                    let points: Vec<egui::Pos2> = scribble_core::connector_points(from, to, connector.arrow).into_iter()
+                        .map(|[x, y]| egui::Pos2::new(x, y))
+                        .collect();
+                    // In focus mode a connector stays bright only when both its ends are selected
+                    let both_selected = [connector.from, connector.to].iter().all(|idx| self.selected_text_elements.contains(idx));
+                    let color = if self.dimmed_by_focus(both_selected) { connector_color.gamma_multiply(FOCUS_DIM_OPACITY) } else { connector_color };
+                    painter.add(egui::Shape::line(points, egui::Stroke::new(scribble_core::CONNECTOR_WIDTH * camera.zoom, color)));
                 }
             }
             
-            // Show floating text input if active
-            if let Some(text_pos) = self.active_text_position {
-                let text_area = egui::Area::new(egui::Id::new("floating_text_area"))
-                    .fixed_pos(text_pos)
-                    .order(egui::Order::Foreground);
-                
-                text_area.show(ctx, |ui| {
-                    ui.group(|ui| {
-                        ui.vertical(|ui| {
-                            ui.label("Type your text (multiline supported):");
-                            
-                            let text_edit_response = ui.add(
-                                egui::TextEdit::multiline(&mut self.text_input)
-                                    .id(self.text_input_id)
-                                    .desired_width(250.0)
-                                    .desired_rows(5)
-                                    .font(egui::TextStyle::Body)
-                            );
-                            
-                            // Auto-focus the text input when it first appears
-                            if text_edit_response.gained_focus() {
-                                ui.memory_mut(|mem| mem.request_focus(self.text_input_id));
-                            }
-                            
-                            ui.horizontal(|ui| {
-                                if ui.button("✅ Add").clicked() {
-                                    if !self.text_input.trim().is_empty() {
-                                        let text_content = self.text_input.clone();
-                                        let font_size = self.text_font_size;
-                                        
-                                        self.current_text_elements_mut().push(TextElement {
-                                            position: text_pos,
-                                            text: text_content,
-                                            font_size,
-                                        });
-                                        self.text_input.clear();
-                                        self.active_text_position = None;
-                                    }
-                                }
-                                
-                                if ui.button("❌ Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
-                                    self.active_text_position = None;
-                                    self.text_input.clear();
-                                }
-                            });
-                            
-                            ui.label("Ctrl+Enter to add, Esc to cancel");
-                            
-                            // Handle Ctrl+Enter to add text
-                            if ui.input(|i| i.key_pressed(egui::Key::Enter) && i.modifiers.ctrl) {
-                                if !self.text_input.trim().is_empty() {
-                                    let text_content = self.text_input.clone();
-                                    let font_size = self.text_font_size;
-                                    
-                                    self.current_text_elements_mut().push(TextElement {
-                                        position: text_pos,
-                                        text: text_content,
-                                        font_size,
-                                    });
-                                    self.text_input.clear();
-                                    self.active_text_position = None;
-                                }
-                            }
-                        });
-                    });
-                });
+            // Custom guides, and the rulers they are dragged out of
+            if !self.presenting {
+                let ruler_color = egui::Color32::from_rgba_unmultiplied(120, 120, 140, 40);
+                painter.rect_filled(
+                    egui::Rect::from_min_max(canvas_rect.min, egui::Pos2::new(canvas_rect.max.x, canvas_rect.min.y + GUIDE_RULER_WIDTH)),
+                    egui::Rounding::ZERO,
+                    ruler_color,
+                );
+                painter.rect_filled(
+                    egui::Rect::from_min_max(egui::Pos2::new(canvas_rect.min.x, canvas_rect.min.y + GUIDE_RULER_WIDTH), egui::Pos2::new(canvas_rect.min.x + GUIDE_RULER_WIDTH, canvas_rect.max.y)),
+                    egui::Rounding::ZERO,
+                    ruler_color,
+                );
+                let page = self.current_page();
+                let guide_color = |dragged: bool| egui::Color32::from_rgb(0, 170, 210).gamma_multiply(if dragged { 1.0 } else { 0.6 });
+                for (idx, &x) in page.guides_x.iter().enumerate() {
+                    let stroke = egui::Stroke::new(1.0, guide_color(self.guide_drag == Some((true, idx))));
+                    paint_guide(&painter, camera, canvas_rect, true, x, stroke);
+                }
+                for (idx, &y) in page.guides_y.iter().enumerate() {
+                    let stroke = egui::Stroke::new(1.0, guide_color(self.guide_drag == Some((false, idx))));
+                    paint_guide(&painter, camera, canvas_rect, false, y, stroke);
+                }
             }
             
-            // Draw completed strokes
-            for stroke in self.current_strokes() {
-                if stroke.points.len() > 1 {
-                    let points: Vec<egui::Pos2> = stroke.points.iter().copied().collect();
-                    painter.add(egui::Shape::line(
-                        points,
-                        egui::Stroke::new(stroke.width, stroke.color),
-                    ));
-                }
+            // Guides the dragged selection has snapped to
+            let guide_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(230, 60, 160));
+            if let Some(x) = self.active_guides[0] {
+                paint_guide(&painter, camera, canvas_rect, true, x, guide_stroke);
+            }
+            if let Some(y) = self.active_guides[1] {
+                paint_guide(&painter, camera, canvas_rect, false, y, guide_stroke);
+            }
+            
+            // Draw the area eraser's rectangle while it is dragged
+            if let Some((start, end)) = self.erase_area {
+                let erase_rect = egui::Rect::from_two_pos(camera.to_screen(start), camera.to_screen(end));
+                painter.rect_filled(erase_rect, egui::Rounding::ZERO, egui::Color32::from_rgba_unmultiplied(220, 60, 60, 30));
+                painter.rect_stroke(erase_rect, egui::Rounding::ZERO, egui::Stroke::new(1.0, egui::Color32::from_rgb(220, 60, 60)));
+            }
+            
+            // Outline the eraser's reach at the pointer, dark with a light edge so it shows on any ink
+            if self.current_tool == Tool::Erase
+                && self.eraser_mode != EraserMode::Area
+                && self.settings.show_eraser_cursor
+                && let Some(pointer) = self.canvas_pointer
+            {
+                painter.circle_stroke(pointer, self.settings.eraser_radius + 1.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+                painter.circle_stroke(pointer, self.settings.eraser_radius, egui::Stroke::new(1.0, egui::Color32::from_gray(60)));
             }
             
             // Draw selection rectangle if actively selecting
-            if self.is_selecting_text {
-                if let (Some(start), Some(end)) = (self.selection_start, self.selection_end) {
-                    let selection_rect = egui::Rect::from_two_pos(start, end);
-                    painter.rect_stroke(
-                        selection_rect,
-                        egui::Rounding::ZERO,
-                        egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 150, 255)),
-                    );
-                    painter.rect_filled(
-                        selection_rect,
-                        egui::Rounding::ZERO,
-                        egui::Color32::from_rgba_premultiplied(100, 150, 255, 30),
-                    );
-                }
+            if self.is_selecting_text
+                && let (Some(start), Some(end)) = (self.selection_start, self.selection_end)
+            {
+                let selection_rect = egui::Rect::from_two_pos(camera.to_screen(start), camera.to_screen(end));
+                painter.rect_stroke(
+                    selection_rect,
+                    egui::Rounding::ZERO,
+                    egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 150, 255)),
+                );
+                painter.rect_filled(
+                    selection_rect,
+                    egui::Rounding::ZERO,
+                    egui::Color32::from_rgba_premultiplied(100, 150, 255, 30),
+                );
             }
             
             // Draw text elements
             for (index, text_element) in self.current_text_elements().iter().enumerate() {
+                // The element being edited is shown by the floating editor instead
+                if self.editing_text == Some(index) {
+                    continue;
+                }
+                if self.hidden_by_build(text_element.build_order) {
+                    continue;
+                }
+                let Some(mut painter) = self.tag_filtered_painter(&painter, &text_element.tag) else {
+                    continue;
+                };
+                if self.dimmed_by_focus(self.selected_text_elements.contains(&index)) {
+                    painter.multiply_opacity(FOCUS_DIM_OPACITY);
+                }
+                painter.multiply_opacity(self.pulse_opacity(ctx, ElementRef::Text(index)));
+                
+                // Pinned text ignores pan, zoom and page transitions
+                let camera = if text_element.pinned { self.pinned_camera() } else { camera };
                 let is_search_result = self.search_results.contains(&index);
                 let has_collision = self.text_collisions.contains(&index);
                 let is_selected = self.selected_text_elements.contains(&index);
                 
                 // Draw selection background if selected
                 if is_selected {
-                    let lines: Vec<&str> = text_element.text.lines().collect();
-                    let font_size = text_element.font_size;
-                    let line_height = font_size * 1.2;
-                    
-                    for (line_idx, line) in lines.iter().enumerate() {
-                        if line.trim().is_empty() {
-                            continue;
-                        }
-                        
-                        let line_y = text_element.position.y + (line_idx as f32) * line_height;
-                        let estimated_text_width = line.len() as f32 * font_size * 0.6;
-                        
-                        let selection_rect = egui::Rect::from_min_size(
-                            egui::Pos2::new(text_element.position.x - 2.0, line_y - 2.0),
-                            egui::Vec2::new(estimated_text_width + 4.0, font_size + 4.0),
-                        );
-                        
+                    for line_rect in Self::text_line_rects(text_element) {
+                        let selection_rect = camera.rect_to_screen(line_rect).expand(2.0);
                         painter.rect_filled(
                             selection_rect,
                             egui::Rounding::same(3.0),
                             egui::Color32::from_rgba_premultiplied(100, 150, 255, 80), // Light blue selection
                         );
                     }
+                } else if hovered_text == Some(index) {
+                    for line_rect in Self::text_line_rects(text_element) {
+                        let hover_rect = camera.rect_to_screen(line_rect).expand(2.0);
+                        painter.rect_stroke(hover_rect, egui::Rounding::same(3.0), egui::Stroke::new(1.0, egui::Color32::from_black_alpha(90)));
+                    }
                 }
                 
                 // Text is always black, but may be semi-transparent if there's a collision
@@ -1660,43 +6795,221 @@ impl eframe::App for ScribbleApp {
                     egui::Color32::BLACK // Always black for text
                 };
                 
-                // Draw the text in its original form
-                painter.text(
-                    text_element.position,
-                    egui::Align2::LEFT_TOP,
-                    &text_element.text,
-                    egui::FontId::proportional(text_element.font_size),
-                    text_color,
-                );
+                let screen_pos = camera.to_screen(text_element.position);
+                let font_id = egui::FontId::proportional(text_element.font_size * camera.zoom);
+                
+                // Vertical text is painted one character at a time down its columns
+                let glyphs = if text_element.orientation.is_vertical() {
+                    scribble_core::vertical_char_offsets(&text_element.text, text_element.font_size, text_element.orientation)
+                } else {
+                    Vec::new()
+                };
+                
+                // Drop shadow first, so the outline and the text sit on top of it
+                if let Some((offset, shadow_color)) = text_element.shadow {
+                    let shadow_color = if has_collision { shadow_color.gamma_multiply(0.5) } else { shadow_color };
+                    let shadow_pos = screen_pos + camera.vec_to_screen(offset);
+                    if text_element.orientation.is_vertical() {
+                        for (ch, [dx, dy]) in &glyphs {
+                            paint_view_text(&painter, camera, shadow_pos + camera.vec_to_screen(egui::vec2(*dx, *dy)), egui::Align2::CENTER_TOP, ch, font_id.clone(), shadow_color);
+                        }
+                    } else {
+                        paint_view_text(&painter, camera, shadow_pos, egui::Align2::LEFT_TOP, &text_element.text, font_id.clone(), shadow_color);
+                    }
+                }
+                
+                if text_element.orientation.is_vertical() {
+                    if let Some(outline_color) = text_element.outline {
+                        let outline_color = if has_collision { outline_color.gamma_multiply(0.5) } else { outline_color };
+                        let radius = (text_element.font_size * camera.zoom * TEXT_OUTLINE_RATIO).max(1.0);
+                        for step in 0..8 {
+                            let shift = egui::Vec2::angled(step as f32 * std::f32::consts::TAU / 8.0) * radius;
+                            for (ch, [dx, dy]) in &glyphs {
+                                paint_view_text(&painter, camera, screen_pos + camera.vec_to_screen(egui::vec2(*dx, *dy)) + shift, egui::Align2::CENTER_TOP, ch, font_id.clone(), outline_color);
+                            }
+                        }
+                    }
+                    for (ch, [dx, dy]) in &glyphs {
+                        paint_view_text(&painter, camera, screen_pos + camera.vec_to_screen(egui::vec2(*dx, *dy)), egui::Align2::CENTER_TOP, ch, font_id.clone(), text_color);
+                    }
+                    continue;
+                }
+                
+                // Draw the outline halo by painting the text at small offsets underneath
+                if let Some(outline_color) = text_element.outline {
+                    let outline_color = if has_collision { outline_color.gamma_multiply(0.5) } else { outline_color };
+                    let radius = (text_element.font_size * camera.zoom * TEXT_OUTLINE_RATIO).max(1.0);
+                    for step in 0..8 {
+                        let angle = step as f32 * std::f32::consts::TAU / 8.0;
+                        paint_view_text(
+                            &painter,
+                            camera,
+                            screen_pos + egui::Vec2::angled(angle) * radius,
+                            egui::Align2::LEFT_TOP,
+                            &text_element.text,
+                            font_id.clone(),
+                            outline_color,
+                        );
+                    }
+                }
+                
+                // Draw the text in its original form, either painted or as a selectable label; labels
+                // can't turn, so a rotated view paints its text
+                if self.settings.selectable_text && camera.quarter_turns == 0 {
+                    let clip_rect = painter.clip_rect();
+                    let opacity = painter.opacity();
+                    egui::Area::new(egui::Id::new(("text_widget", index)))
+                        .order(egui::Order::Background)
+                        .fixed_pos(screen_pos)
+                        .constrain(false)
+                        .interactable(self.current_tool == Tool::Select && !self.presenting)
+                        .show(ctx, |ui| {
+                            ui.set_clip_rect(clip_rect);
+                            ui.multiply_opacity(opacity);
+                            ui.spacing_mut().item_spacing = egui::Vec2::ZERO;
+                            ui.add(egui::Label::new(egui::RichText::new(&text_element.text).font(font_id).color(text_color))
+                                .extend()
+                                .selectable(true));
+                        });
+                } else {
+                    paint_view_text(
+                        &painter,
+                        camera,
+                        screen_pos,
+                        egui::Align2::LEFT_TOP,
+                        &text_element.text,
+                        font_id,
+                        text_color,
+                    );
+                }
                 
                 // Draw arrows pointing to matches
                 if is_search_result && !self.search_query.is_empty() {
                     self.draw_arrows_for_matches(
                         &painter,
-                        text_element.position,
+                        camera,
+                        camera.to_screen(text_element.position),
+                        &text_element.text,
+                        text_element.font_size * camera.zoom,
+                    );
+                }
+            }
+            
+            // Corner handles for resizing a lone text element or scaling any other selection
+            if self.current_tool == Tool::Select
+                && self.page_transition.is_none()
+                && let Some(rect) = self.resizable_text_rect(ctx).map(|(_, rect)| rect).or_else(|| self.scalable_selection_rect(ctx))
+            {
+                painter.rect_stroke(rect, egui::Rounding::ZERO, egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 150, 255)));
+                for corner in [rect.left_top(), rect.right_top(), rect.left_bottom(), rect.right_bottom()] {
+                    let handle = egui::Rect::from_center_size(corner, egui::Vec2::splat(TEXT_HANDLE_SIZE));
+                    painter.rect_filled(handle, egui::Rounding::ZERO, egui::Color32::WHITE);
+                    painter.rect_stroke(handle, egui::Rounding::ZERO, egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 150, 255)));
+                }
+            }
+            
+            // Faint preview of the stamp being placed, with its top-left at the pointer
+            if let Some(stamp) = self.placing_stamp.and_then(|idx| self.settings.stamps.get(idx))
+                && let Some(pointer) = self.canvas_pointer
+            {
+                let placed = stamp.placed_at(camera.to_canvas(pointer).into());
+                let mut ghost_painter = painter.clone();
+                ghost_painter.multiply_opacity(STAMP_PREVIEW_OPACITY);
+                for stroke in &placed.strokes {
+                    let points: Vec<egui::Pos2> = stroke.points.iter().map(|&[x, y]| camera.to_screen(egui::Pos2::new(x, y))).collect();
+                    ghost_painter.add(egui::Shape::line(points, egui::Stroke::new(stroke.width * camera.zoom, rgb_to_color(stroke.color))));
+                }
+                for text_element in &placed.text_elements {
+                    let [x, y] = text_element.position;
+                    paint_view_text(
+                        &ghost_painter,
+                        camera,
+                        camera.to_screen(egui::Pos2::new(x, y)),
+                        egui::Align2::LEFT_TOP,
                         &text_element.text,
-                        text_element.font_size,
+                        egui::FontId::proportional(text_element.font_size * camera.zoom),
+                        egui::Color32::BLACK,
                     );
                 }
             }
             
-            // Draw current stroke being drawn
+            // Draw current stroke being drawn, in the colors and opacity it will be committed with
             if self.current_stroke.len() > 1 {
-                let points: Vec<egui::Pos2> = self.current_stroke.iter().copied().collect();
-                painter.add(egui::Shape::line(
-                    points,
-                    egui::Stroke::new(self.stroke_width, egui::Color32::LIGHT_BLUE),
-                ));
+                let points: Vec<egui::Pos2> = self.current_stroke.iter().map(|&p| camera.to_screen(p)).collect();
+                let width = self.stroke_width * camera.zoom;
+                let mut preview_painter = painter.clone();
+                preview_painter.multiply_opacity(self.stroke_opacity);
+                if self.settings.translucent_stroke_preview {
+                    preview_painter.multiply_opacity(STROKE_PREVIEW_OPACITY);
+                }
+                if self.current_tool == Tool::Draw && self.pen_mode == PenMode::Gradient {
+                    let colors = self.gradient_colors(&self.current_stroke);
+                    for (i, segment) in points.windows(2).enumerate() {
+                        preview_painter.line_segment([segment[0], segment[1]], egui::Stroke::new(width, colors[i]));
+                    }
+                } else if self.current_tool == Tool::Draw && self.pen_mode == PenMode::Calligraphy {
+                    let widths = calligraphy_widths(&self.current_stroke, self.settings.nib_angle_degrees);
+                    for (i, segment) in points.windows(2).enumerate() {
+                        let segment_width = width * widths[i].max(scribble_core::MIN_PRESSURE_WIDTH_RATIO);
+                        preview_painter.line_segment([segment[0], segment[1]], egui::Stroke::new(segment_width, self.stroke_color));
+                        preview_painter.circle_filled(segment[1], segment_width / 2.0, self.stroke_color);
+                    }
+                } else {
+                    preview_painter.add(egui::Shape::line(points, egui::Stroke::new(width, self.stroke_color)));
+                }
+            }
+            
+            // Curve anchors with their tangent handles
+            let handle_stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(100, 150, 255));
+            for anchor in &self.curve_anchors {
+                let point = camera.to_screen(anchor.point);
+                if anchor.handle != egui::Vec2::ZERO {
+                    let (out, back) = (camera.to_screen(anchor.point + anchor.handle), camera.to_screen(anchor.point - anchor.handle));
+                    painter.line_segment([back, out], handle_stroke);
+                    painter.circle_filled(out, 3.0, handle_stroke.color);
+                    painter.circle_filled(back, 3.0, handle_stroke.color);
+                }
+                painter.rect_filled(egui::Rect::from_center_size(point, egui::Vec2::splat(6.0)), egui::Rounding::ZERO, egui::Color32::WHITE);
+                painter.rect_stroke(egui::Rect::from_center_size(point, egui::Vec2::splat(6.0)), egui::Rounding::ZERO, handle_stroke);
+            }
+            
+            if self.presenting {
+                let total_steps = self.build_orders().collect::<std::collections::BTreeSet<_>>();
+                let shown_steps = total_steps.range(..=self.build_step).count();
+                painter.text(
+                    canvas_rect.right_top() + egui::Vec2::new(-8.0, 8.0),
+                    egui::Align2::RIGHT_TOP,
+                    format!("Presenting: step {} of {} (Esc to stop)", shown_steps, total_steps.len()),
+                    egui::FontId::proportional(14.0),
+                    egui::Color32::DARK_GRAY,
+                );
+            }
+            
+            // Angle and length of the line or arrow being dragged
+            if let Some(start) = self.shape_start
+                && let Some(&end) = self.current_stroke.get(1)
+            {
+                let delta = end - start;
+                // Screen y grows downward; report angles counter-clockwise from the x axis
+                let degrees = (-delta.angle()).to_degrees().rem_euclid(360.0);
+                painter.text(
+                    canvas_rect.left_bottom() + egui::Vec2::new(8.0, if self.show_origin { -26.0 } else { -8.0 }),
+                    egui::Align2::LEFT_BOTTOM,
+                    format!("Angle: {:.0}°  Length: {:.0}", degrees, delta.length()),
+                    egui::FontId::monospace(12.0),
+                    egui::Color32::DARK_GRAY,
+                );
+            }
+            
+            for (thumb, active) in scrollbar_thumbs {
+                let alpha = if active { 140 } else { 70 };
+                painter.rect_filled(thumb.shrink(2.0), egui::Rounding::same(3.0), egui::Color32::from_black_alpha(alpha));
             }
             
             // Draw instructions if no content
             if self.current_strokes().is_empty() && self.current_text_elements().is_empty() && !self.is_drawing && self.active_text_position.is_none() {
                 let text_pos = response.rect.center();
-                let instruction_text = match self.current_tool {
-                    Tool::Draw => "Click and drag to draw!",
-                    Tool::Text => "Click to place text!",
-                    Tool::Select => "Drag to select text, then drag selected text to move!\nUse the Copy button to copy selected text.",
-                };
+                let instruction_text = self.empty_canvas_hint(ctx);
                 painter.text(
                     text_pos,
                     egui::Align2::CENTER_CENTER,
@@ -1706,6 +7019,20 @@ impl eframe::App for ScribbleApp {
                 );
             }
             
+            // Live readout of the pointer's canvas coordinates
+            if self.show_origin
+                && let Some(hover_pos) = response.hover_pos()
+            {
+                let canvas_pos = camera.to_canvas(hover_pos);
+                painter.text(
+                    canvas_rect.left_bottom() + egui::Vec2::new(8.0, -8.0),
+                    egui::Align2::LEFT_BOTTOM,
+                    format!("x: {:.0}  y: {:.0}", canvas_pos.x, canvas_pos.y),
+                    egui::FontId::monospace(12.0),
+                    egui::Color32::DARK_GRAY,
+                );
+            }
+            
             // Draw drag and drop overlay when files are hovered
             if self.is_file_hovered {
                 // Semi-transparent overlay
@@ -1719,7 +7046,7 @@ impl eframe::App for ScribbleApp {
                 painter.text(
                     response.rect.center(),
                     egui::Align2::CENTER_CENTER,
-                    "📄 Drop .scribble file to open\n(Supports both single pages and notebooks)",
+                    "📄 Drop a notebook to open it, a text file to add it, or a CSV/TSV file to add it as a table\n(Supports both single pages and notebooks, under any file name)",
                     egui::FontId::proportional(24.0),
                     egui::Color32::WHITE,
                 );
@@ -1733,23 +7060,284 @@ impl eframe::App for ScribbleApp {
             }
         });
         
+        // Help overlay listing shortcuts and tool tips
+        if self.show_help {
+            egui::Window::new("❓ Help")
+                .open(&mut self.show_help)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.heading("Keyboard shortcuts");
+                    egui::Grid::new("help_shortcuts").striped(true).show(ui, |ui| {
+                        for (shortcut, description) in SHORTCUTS {
+                            ui.monospace(ctx.format_shortcut(shortcut));
+                            ui.label(*description);
+                            ui.end_row();
+                        }
+                    });
+                    
+                    ui.separator();
+                    ui.heading("Mouse");
+                    egui::Grid::new("help_mouse").striped(true).show(ui, |ui| {
+                        for (gesture, description) in MOUSE_CONTROLS {
+                            ui.monospace(*gesture);
+                            ui.label(*description);
+                            ui.end_row();
+                        }
+                    });
+                    
+                    ui.separator();
+                    ui.heading("Tools");
+                    egui::Grid::new("help_tools").striped(true).show(ui, |ui| {
+                        for tool in Tool::ALL {
+                            ui.label(tool.label());
+                            ui.label(tool.instructions());
+                            ui.end_row();
+                        }
+                    });
+                });
+        }
+        
+        // Scrollable history of everything reported this session
+        if self.show_log {
+            let mut clear_log = false;
+            egui::Window::new("📜 Log")
+                .open(&mut self.show_log)
+                .default_size([480.0, 240.0])
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} message(s)", self.log_entries.len()));
+                        if ui.button("Copy All").clicked() {
+                            ui.ctx().copy_text(self.log_entries.join("\n"));
+                        }
+                        clear_log = ui.button("Clear").clicked();
+                    });
+                    ui.separator();
+                    egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                        if self.log_entries.is_empty() {
+                            ui.weak("Nothing logged yet.");
+                        }
+                        for entry in &self.log_entries {
+                            ui.monospace(entry);
+                        }
+                    });
+                });
+            if clear_log {
+                self.log_entries.clear();
+            }
+        }
+        
+        // Every recorded action, oldest first, with undone ones greyed out after the current state
+        if self.show_history {
+            let mut jump = None;
+            egui::Window::new("🕘 History")
+                .open(&mut self.show_history)
+                .default_size([240.0, 320.0])
+                .show(ctx, |ui| {
+                    ui.weak("Click an entry to return to the state just after it.");
+                    ui.separator();
+                    egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                        let applied = self.undo_stack.len();
+                        if ui.selectable_label(applied == 0, "Start").clicked() {
+                            jump = Some(0);
+                        }
+                        for (idx, entry) in self.undo_stack.iter().enumerate() {
+                            if ui.selectable_label(idx + 1 == applied, format!("{}. {}", idx + 1, entry.label)).clicked() {
+                                jump = Some(idx + 1);
+                            }
+                        }
+                        for (idx, entry) in self.redo_stack.iter().rev().enumerate() {
+                            let label = egui::RichText::new(format!("{}. {}", applied + idx + 1, entry.label)).weak();
+                            if ui.selectable_label(false, label).on_hover_text("Undone; click to redo up to here").clicked() {
+                                jump = Some(applied + idx + 1);
+                            }
+                        }
+                    });
+                });
+            if let Some(applied) = jump {
+                self.jump_to_history(applied);
+            }
+        }
+        
+        // Notebook metadata dialog
+        if self.show_notebook_info {
+            egui::Window::new("Notebook Info")
+                .open(&mut self.show_notebook_info)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    egui::Grid::new("notebook_info").num_columns(2).show(ui, |ui| {
+                        ui.label("Title:");
+                        if ui.text_edit_singleline(&mut self.notebook_meta.title).changed() {
+                            self.dirty = true;
+                        }
+                        ui.end_row();
+                        
+                        ui.label("Author:");
+                        if ui.text_edit_singleline(&mut self.notebook_meta.author).changed() {
+                            self.dirty = true;
+                        }
+                        ui.end_row();
+                        
+                        ui.label("Created:");
+                        ui.label(scribble_core::format_timestamp(self.notebook_meta.created));
+                        ui.end_row();
+                        
+                        ui.label("Modified:");
+                        ui.label(scribble_core::format_timestamp(self.notebook_meta.modified));
+                        ui.end_row();
+                    });
+                    ui.weak("Modified is updated each time the notebook is saved.");
+                });
+        }
+        
+        if self.show_exit_confirm {
+            egui::Window::new("Unsaved Changes")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    ui.label("Save your changes before closing?");
+                    ui.horizontal(|ui| {
+                        if ui.button("💾 Save").clicked() {
+                            match self.save_project() {
+                                // Still dirty means the file dialog was cancelled
+                                Ok(()) if !self.dirty => {
+                                    self.close_confirmed = true;
+                                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                                }
+                                Ok(()) => {}
+                                Err(e) => self.notify(format!("Save error: {}", e), true),
+                            }
+                        }
+                        if ui.button("Discard").clicked() {
+                            self.close_confirmed = true;
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.show_exit_confirm = false;
+                        }
+                    });
+                });
+        }
+        
+        if self.show_go_to_page {
+            let mut open = true;
+            egui::Window::new("Go to Page")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("Page number (1-{}) or name:", self.pages.len()));
+                    let input = ui.text_edit_singleline(&mut self.go_to_page_input);
+                    if !input.has_focus() && !input.lost_focus() {
+                        input.request_focus();
+                    }
+                    let target = self.find_page(&self.go_to_page_input);
+                    match target {
+                        Some(idx) => ui.weak(format!("{}. {}", idx + 1, self.pages[idx].name)),
+                        None if self.go_to_page_input.trim().is_empty() => ui.weak("Type to find a page"),
+                        None => ui.colored_label(egui::Color32::RED, "No matching page"),
+                    };
+                    
+                    let submitted = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (submitted || ui.add_enabled(target.is_some(), egui::Button::new("Go")).clicked())
+                        && let Some(idx) = target
+                    {
+                        if idx != self.current_page_index {
+                            let direction = if idx > self.current_page_index { 1.0 } else { -1.0 };
+                            self.go_to_page(idx);
+                            self.start_page_transition(direction);
+                        }
+                        self.show_go_to_page = false;
+                    }
+                    if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                        self.show_go_to_page = false;
+                    }
+                });
+            if !open {
+                self.show_go_to_page = false;
+            }
+        }
+        
+        if self.show_animation_export {
+            let mut open = true;
+            egui::Window::new("Export Animation")
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("Strokes appear in the order they were drawn, then text.");
+                    ui.horizontal(|ui| {
+                        ui.label("Frame rate:");
+                        ui.add(egui::DragValue::new(&mut self.animation_fps).range(1..=50).suffix(" fps"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Duration:");
+                        ui.add(egui::DragValue::new(&mut self.animation_seconds).range(0.5..=60.0).speed(0.1).suffix(" s"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut self.animation_as_frames, false, "Animated GIF");
+                        ui.radio_value(&mut self.animation_as_frames, true, "PNG frames");
+                    });
+                    ui.weak(format!("{} frames", self.animation_frame_count()));
+                    
+                    if ui.button("Export...").clicked() {
+                        if let Err(e) = self.export_animation() {
+                            self.notify(format!("Animation export error: {}", e), true);
+                        }
+                        self.show_animation_export = false;
+                    }
+                });
+            if !open {
+                self.show_animation_export = false;
+            }
+        }
+        
+        // Show the notebook title in the title bar
+        let window_title = if self.is_notebook_mode && !self.notebook_meta.title.trim().is_empty() {
+            format!("{} - Scribble", self.notebook_meta.title.trim())
+        } else {
+            WINDOW_TITLE.to_string()
+        };
+        if window_title != self.window_title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(window_title.clone()));
+            self.window_title = window_title;
+        }
+        
+        // Notifications stack up from the bottom-right corner until they expire
+        self.notifications.retain(|n| n.created.elapsed().as_secs_f32() < NOTIFICATION_DURATION);
+        if !self.notifications.is_empty() {
+            egui::Area::new(egui::Id::new("notifications"))
+                .order(egui::Order::Tooltip)
+                .anchor(egui::Align2::RIGHT_BOTTOM, egui::Vec2::new(-12.0, -12.0))
+                .show(ctx, |ui| {
+                    for notification in &self.notifications {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            let color = if notification.is_error { egui::Color32::RED } else { ui.visuals().text_color() };
+                            ui.colored_label(color, &notification.message);
+                        });
+                    }
+                });
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+        
         // Create notebook dialog
         if self.show_create_notebook_dialog {
             egui::Window::new("Create Notebook")
                 .collapsible(false)
                 .resizable(false)
                 .show(ctx, |ui| {
-                    ui.label("Number of pages:");
+                    ui.label(format!("Number of pages (1-{}):", MAX_PAGES));
                     ui.text_edit_singleline(&mut self.new_notebook_pages_input);
                     
                     ui.horizontal(|ui| {
-                        if ui.button("Create").clicked() {
-                            if let Ok(page_count) = self.new_notebook_pages_input.parse::<usize>() {
-                                if page_count > 0 && page_count <= 100 { // Reasonable limit
-                                    self.create_notebook(page_count);
-                                    self.show_create_notebook_dialog = false;
-                                }
-                            }
+                        if ui.button("Create").clicked()
+                            && let Ok(page_count) = self.new_notebook_pages_input.parse::<usize>()
+                            && (1..=MAX_PAGES).contains(&page_count)
+                        {
+                            self.create_notebook(page_count);
+                            self.show_create_notebook_dialog = false;
                         }
                         
                         if ui.button("Cancel").clicked() {
@@ -1760,3 +7348,56 @@ impl eframe::App for ScribbleApp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn zero_width_regex_matches_are_skipped_unless_asked_for() {
+        let regex = Regex::new("a*").unwrap();
+        assert_eq!(regex_match_ranges(&regex, "banana", false), vec![(1, 2), (3, 4), (5, 6)]);
+        assert_eq!(regex_match_ranges(&regex, "banana", true), vec![(0, 0), (1, 2), (3, 4), (5, 6)]);
+        
+        assert_eq!(replace_regex_matches(&regex, "banana", 0, "o", false, false), "bonono");
+        assert_eq!(replace_regex_matches(&regex, "banana", 2, "o", false, false), "bonona");
+        assert_eq!(replace_regex_matches(&regex, "banana", 0, "o", false, true), "obonono");
+    }
+    
+    #[test]
+    fn literal_matches_stay_on_character_boundaries() {
+        assert_eq!(literal_match_ranges("é", "éé"), vec![(0, 2), (2, 4)]);
+        assert_eq!(literal_match_ranges("É", "café é"), vec![(3, 5), (6, 8)]);
+        // "İ" grows by a byte when lowercased; the ranges still index the original text
+        let text = "İstanbul or istanbul";
+        let ranges = literal_match_ranges("stan", text);
+        assert_eq!(ranges.len(), 2);
+        for (start, end) in ranges {
+            assert_eq!(&text[start..end], "stan");
+        }
+    }
+    
+    #[test]
+    fn colors_round_trip_through_the_document_model() {
+        for (rgb, opacity) in [([255, 128, 0], 1.0), ([30, 200, 90], 0.5), ([0, 0, 255], 0.25), ([200, 10, 60], 0.8)] {
+            let color = rgb_opacity_to_color(rgb, opacity);
+            assert_eq!(color_opacity(color), scribble_core::alpha_to_opacity(scribble_core::opacity_to_alpha(opacity)));
+            // egui stores colors premultiplied, which can shift a translucent channel by one step
+            let back = color_to_rgb(color);
+            assert!(rgb.iter().zip(back).all(|(&a, b)| a.abs_diff(b) <= 1), "{:?} came back as {:?}", rgb, back);
+        }
+        
+        let mut text_element = TextElement::from(scribble_core::TextElement::new([0.0, 0.0], "Hi"));
+        text_element.outline = Some(rgb_opacity_to_color([255, 0, 0], 0.5));
+        text_element.shadow = Some((egui::vec2(2.0, 2.0), rgb_opacity_to_color([0, 0, 0], 0.3)));
+        let saved = scribble_core::TextElement::from(&text_element);
+        assert_eq!(saved.outline, Some([255, 0, 0]));
+        assert_eq!(saved.outline_opacity, scribble_core::alpha_to_opacity(128));
+        assert_eq!(saved.shadow.map(|shadow| (shadow.color, shadow.opacity)), Some(([0, 0, 0], scribble_core::alpha_to_opacity(77))));
+        
+        let json = serde_json::to_string(&saved).unwrap();
+        let loaded = TextElement::from(serde_json::from_str::<scribble_core::TextElement>(&json).unwrap());
+        assert_eq!(loaded.outline, text_element.outline);
+        assert_eq!(loaded.shadow, text_element.shadow);
+    }
+}
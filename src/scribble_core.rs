@@ -0,0 +1,959 @@
+// Document model, builders and save/load for .scribble files.
+//
+// Nothing here depends on egui: positions are plain `[f32; 2]` canvas coordinates and
+// colors are `[u8; 3]` RGB, so files can be generated and inspected from scripts.
+//
+// Every field that was added after the first release has a serde default so older
+// files keep loading, and `migrate` repairs values that older versions allowed.
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// Bumped whenever the file format changes in a way that needs migration
+pub const FORMAT_VERSION: u32 = 1;
+
+pub const DEFAULT_STROKE_WIDTH: f32 = 2.0;
+pub const DEFAULT_FONT_SIZE: f32 = 20.0;
+pub const DEFAULT_CANVAS_SIZE: [f32; 2] = [800.0, 600.0];
+// Lightest pressure still draws at this fraction of the stroke width
+pub const MIN_PRESSURE_WIDTH_RATIO: f32 = 0.2;
+
+// Fewest points a stroke needs to draw anything; shorter ones are left out of saves, bounds and exports
+pub const MIN_DRAWABLE_POINTS: usize = 2;
+
+// Width of a stroke's segment starting at the given point, scaled by the pressure at its ends.
+// Points without a pressure reading count as full pressure.
+pub fn segment_width(width: f32, pressures: &[f32], point_index: usize) -> f32 {
+    let pressure_at = |i: usize| pressures.get(i).copied().unwrap_or(1.0);
+    let pressure = (pressure_at(point_index) + pressure_at(point_index + 1)) / 2.0;
+    width * pressure.max(MIN_PRESSURE_WIDTH_RATIO)
+}
+
+fn default_stroke_width() -> f32 {
+    DEFAULT_STROKE_WIDTH
+}
+
+fn default_font_size() -> f32 {
+    DEFAULT_FONT_SIZE
+}
+
+fn default_canvas_size() -> [f32; 2] {
+    DEFAULT_CANVAS_SIZE
+}
+
+fn migrate_canvas_size(canvas_size: &mut [f32; 2]) {
+    if !canvas_size.iter().all(|side| side.is_finite() && *side > 0.0) {
+        *canvas_size = DEFAULT_CANVAS_SIZE;
+    }
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn is_opaque(value: &f32) -> bool {
+    *value >= 1.0
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+// Colors are saved as sRGB bytes with straight alpha, never premultiplied: the color is a plain
+// `[r, g, b]`, and where an element can be see-through its transparency is a separate 0..=1 opacity.
+
+pub fn alpha_to_opacity(alpha: u8) -> f32 {
+    alpha as f32 / 255.0
+}
+
+pub fn opacity_to_alpha(opacity: f32) -> u8 {
+    (opacity.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// `color` laid over `background` at `opacity`, for outputs that can't keep transparency
+pub fn blend_rgb(color: [u8; 3], background: [u8; 3], opacity: f32) -> [u8; 3] {
+    let opacity = opacity.clamp(0.0, 1.0);
+    std::array::from_fn(|i| (color[i] as f32 * opacity + background[i] as f32 * (1.0 - opacity)).round() as u8)
+}
+
+fn clamp_opacity(opacity: &mut f32) {
+    *opacity = if opacity.is_finite() { opacity.clamp(0.0, 1.0) } else { 1.0 };
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stroke {
+    pub points: Vec<[f32; 2]>,
+    #[serde(default)]
+    pub color: [u8; 3],
+    #[serde(default = "default_stroke_width")]
+    pub width: f32,
+    // Optional per-point colors, empty for single-color strokes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub colors: Vec<[u8; 3]>,
+    // Optional per-point stylus pressure from 0 to 1, empty when drawn without pressure
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pressures: Vec<f32>,
+    // Points are stored already smoothed; this only records how strongly
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub smoothing: f32,
+    // 1.0 is opaque; translucent strokes darken what they cross, like a highlighter
+    #[serde(default = "default_opacity", skip_serializing_if = "is_opaque")]
+    pub opacity: f32,
+    // Category label used to filter what is shown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    // Presentation step at which the stroke appears; None shows it from the start
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_order: Option<u32>,
+}
+
+impl Stroke {
+    // A black stroke of the default width through the given points
+    pub fn new(points: Vec<[f32; 2]>) -> Self {
+        Self {
+            points,
+            color: [0, 0, 0],
+            width: DEFAULT_STROKE_WIDTH,
+            colors: Vec::new(),
+            pressures: Vec::new(),
+            smoothing: 0.0,
+            opacity: 1.0,
+            tag: None,
+            build_order: None,
+        }
+    }
+    
+    pub fn with_color(mut self, color: [u8; 3]) -> Self {
+        self.color = color;
+        self
+    }
+    
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+    
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+    
+    pub fn with_pressures(mut self, pressures: Vec<f32>) -> Self {
+        self.pressures = pressures;
+        self
+    }
+    
+    pub fn segment_width(&self, point_index: usize) -> f32 {
+        segment_width(self.width, &self.pressures, point_index)
+    }
+    
+    pub fn with_tag(mut self, tag: Option<String>) -> Self {
+        self.tag = tag;
+        self
+    }
+    
+    pub fn with_build_order(mut self, build_order: Option<u32>) -> Self {
+        self.build_order = build_order;
+        self
+    }
+    
+    pub fn is_drawable(&self) -> bool {
+        self.points.len() >= MIN_DRAWABLE_POINTS
+    }
+    
+    fn migrate(&mut self) {
+        self.points.retain(|[x, y]| x.is_finite() && y.is_finite());
+        if !self.width.is_finite() || self.width <= 0.0 {
+            self.width = DEFAULT_STROKE_WIDTH;
+        }
+        if !self.smoothing.is_finite() {
+            self.smoothing = 0.0;
+        }
+        self.smoothing = self.smoothing.clamp(0.0, 1.0);
+        if !self.opacity.is_finite() {
+            self.opacity = 1.0;
+        }
+        self.opacity = self.opacity.clamp(0.0, 1.0);
+        // Per-point colors only make sense when they line up with the points
+        if self.colors.len() != self.points.len() {
+            self.colors.clear();
+        }
+        if self.pressures.len() != self.points.len() || self.pressures.iter().any(|p| !p.is_finite()) {
+            self.pressures.clear();
+        }
+        for pressure in &mut self.pressures {
+            *pressure = pressure.clamp(0.0, 1.0);
+        }
+    }
+}
+
+// Direction text runs in. Vertical text stacks each line's characters top to bottom, with
+// successive lines as columns to the left (right-to-left, as in CJK) or to the right.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextOrientation {
+    #[default]
+    Horizontal,
+    VerticalRl,
+    VerticalLr,
+}
+
+impl TextOrientation {
+    pub fn is_vertical(&self) -> bool {
+        *self != TextOrientation::Horizontal
+    }
+    
+    fn is_horizontal(&self) -> bool {
+        !self.is_vertical()
+    }
+}
+
+// Width of a column of vertical text and the distance between stacked characters, relative to the font size
+pub const VERTICAL_COLUMN_RATIO: f32 = 1.2;
+pub const VERTICAL_ADVANCE_RATIO: f32 = 1.1;
+
+// Where each character of vertically laid out text goes, as its top-center offset from the element's
+// position. Lines become columns; the whole block's top-left stays at the position.
+pub fn vertical_char_offsets(text: &str, font_size: f32, orientation: TextOrientation) -> Vec<(char, [f32; 2])> {
+    let column_width = font_size * VERTICAL_COLUMN_RATIO;
+    let columns = text.lines().count();
+    let mut offsets = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let column = match orientation {
+            TextOrientation::VerticalRl => columns - 1 - line_idx,
+            _ => line_idx,
+        };
+        let x = column as f32 * column_width + column_width / 2.0;
+        for (char_idx, c) in line.chars().enumerate() {
+            offsets.push((c, [x, char_idx as f32 * font_size * VERTICAL_ADVANCE_RATIO]));
+        }
+    }
+    offsets
+}
+
+// Size of vertically laid out text: a column per line, as tall as the longest line
+pub fn vertical_text_size(text: &str, font_size: f32) -> [f32; 2] {
+    let columns = text.lines().count();
+    let longest = text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+    [columns as f32 * font_size * VERTICAL_COLUMN_RATIO, longest as f32 * font_size * VERTICAL_ADVANCE_RATIO]
+}
+
+// A copy of the text drawn underneath in another color, offset by canvas units
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextShadow {
+    pub offset: [f32; 2],
+    pub color: [u8; 3],
+    #[serde(default = "default_opacity", skip_serializing_if = "is_opaque")]
+    pub opacity: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TextElement {
+    pub position: [f32; 2],
+    #[serde(default)]
+    pub text: String,
+    #[serde(default = "default_font_size")]
+    pub font_size: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<[u8; 3]>,
+    #[serde(default = "default_opacity", skip_serializing_if = "is_opaque")]
+    pub outline_opacity: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<TextShadow>,
+    // Pinned text is positioned relative to the top-left of the view, not the canvas
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_order: Option<u32>,
+    #[serde(default, skip_serializing_if = "TextOrientation::is_horizontal")]
+    pub orientation: TextOrientation,
+    // Files from before multiline text stored each line separately
+    #[serde(default, skip_serializing)]
+    lines: Vec<String>,
+}
+
+impl TextElement {
+    pub fn new(position: [f32; 2], text: impl Into<String>) -> Self {
+        Self {
+            position,
+            text: text.into(),
+            font_size: DEFAULT_FONT_SIZE,
+            outline: None,
+            outline_opacity: 1.0,
+            shadow: None,
+            pinned: false,
+            tag: None,
+            build_order: None,
+            orientation: TextOrientation::Horizontal,
+            lines: Vec::new(),
+        }
+    }
+    
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size;
+        self
+    }
+    
+    pub fn with_outline(mut self, outline: Option<[u8; 3]>) -> Self {
+        self.outline = outline;
+        self
+    }
+    
+    pub fn with_outline_opacity(mut self, opacity: f32) -> Self {
+        self.outline_opacity = opacity;
+        self
+    }
+    
+    pub fn with_shadow(mut self, shadow: Option<TextShadow>) -> Self {
+        self.shadow = shadow;
+        self
+    }
+    
+    pub fn with_pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+    
+    pub fn with_tag(mut self, tag: Option<String>) -> Self {
+        self.tag = tag;
+        self
+    }
+    
+    pub fn with_build_order(mut self, build_order: Option<u32>) -> Self {
+        self.build_order = build_order;
+        self
+    }
+    
+    pub fn with_orientation(mut self, orientation: TextOrientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+    
+    // Nothing but whitespace, so nothing would show
+    pub fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+    
+    fn migrate(&mut self) {
+        if self.text.is_empty() && !self.lines.is_empty() {
+            self.text = self.lines.join("\n");
+        }
+        self.lines.clear();
+        // Text written on Windows may carry carriage returns the renderer would show
+        self.text = self.text.replace("\r\n", "\n");
+        if !self.font_size.is_finite() || self.font_size <= 0.0 {
+            self.font_size = DEFAULT_FONT_SIZE;
+        }
+        if self.shadow.is_some_and(|shadow| !shadow.offset.iter().all(|v| v.is_finite())) {
+            self.shadow = None;
+        }
+        clamp_opacity(&mut self.outline_opacity);
+        if let Some(shadow) = &mut self.shadow {
+            clamp_opacity(&mut shadow.opacity);
+        }
+    }
+}
+
+// A line joining two text elements on the same page by index, so it follows them as they move.
+// Endpoints are worked out from the elements' current bounds whenever it is drawn or exported.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Connector {
+    pub from: usize,
+    pub to: usize,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub arrow: bool, // Arrowhead at the `to` end
+}
+
+impl Connector {
+    pub fn new(from: usize, to: usize) -> Self {
+        Self { from, to, arrow: false }
+    }
+    
+    pub fn with_arrow(mut self) -> Self {
+        self.arrow = true;
+        self
+    }
+    
+    pub fn joins(&self, a: usize, b: usize) -> bool {
+        (self.from == a && self.to == b) || (self.from == b && self.to == a)
+    }
+}
+
+// Keep connectors pointing at the same elements after the text elements were rearranged.
+// `remap` gives each old index's new index, or None if that element is gone, which drops
+// every connector attached to it.
+pub fn remap_connectors(connectors: &mut Vec<Connector>, remap: impl Fn(usize) -> Option<usize>) {
+    connectors.retain_mut(|connector| match (remap(connector.from), remap(connector.to)) {
+        (Some(from), Some(to)) => {
+            connector.from = from;
+            connector.to = to;
+            true
+        }
+        _ => false,
+    });
+}
+
+// Where the element at `idx` ends up once the elements at `removed` are deleted, None if it was one of them
+pub fn index_after_removal(idx: usize, removed: &[usize]) -> Option<usize> {
+    (!removed.contains(&idx)).then(|| idx - removed.iter().filter(|&&gone| gone < idx).count())
+}
+
+// How connectors are drawn: a thin dark line stopping short of the elements it joins
+pub const CONNECTOR_WIDTH: f32 = 2.0;
+pub const CONNECTOR_COLOR: [u8; 3] = [90, 90, 90];
+pub const CONNECTOR_GAP: f32 = 4.0;
+pub const CONNECTOR_HEAD_LENGTH: f32 = 10.0;
+
+// Polyline of a connector between two boxes ([x, y, width, height]): along the line between their
+// centers, from where it leaves the first box to where it enters the second. Arrows then run out
+// to one barb and back and out to the other. Empty when the boxes overlap, leaving nothing to draw.
+pub fn connector_points(from: [f32; 4], to: [f32; 4], arrow: bool) -> Vec<[f32; 2]> {
+    let center = |[x, y, width, height]: [f32; 4]| [x + width / 2.0, y + height / 2.0];
+    let (start, end) = (center(from), center(to));
+    let delta = [end[0] - start[0], end[1] - start[1]];
+    // Fraction of the center-to-center line spent inside a box centered on one end
+    let exit = |[_, _, width, height]: [f32; 4]| {
+        let tx = if delta[0] == 0.0 { f32::INFINITY } else { (width / 2.0 + CONNECTOR_GAP) / delta[0].abs() };
+        let ty = if delta[1] == 0.0 { f32::INFINITY } else { (height / 2.0 + CONNECTOR_GAP) / delta[1].abs() };
+        tx.min(ty)
+    };
+    let (t_start, t_end) = (exit(from), 1.0 - exit(to));
+    if t_start >= t_end {
+        return Vec::new();
+    }
+    let at = |t: f32| [start[0] + delta[0] * t, start[1] + delta[1] * t];
+    let (line_start, line_end) = (at(t_start), at(t_end));
+    if !arrow {
+        return vec![line_start, line_end];
+    }
+    let length = (delta[0] * delta[0] + delta[1] * delta[1]).sqrt() * (t_end - t_start);
+    let head_length = CONNECTOR_HEAD_LENGTH.min(length);
+    let back_angle = (-delta[1]).atan2(-delta[0]);
+    let barb = |angle: f32| [
+        line_end[0] + (back_angle + angle).cos() * head_length,
+        line_end[1] + (back_angle + angle).sin() * head_length,
+    ];
+    vec![line_start, line_end, barb(0.5), line_end, barb(-0.5)]
+}
+
+// Where a page was being viewed: the canvas point at the screen origin, the zoom and the rotation
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Camera {
+    pub offset: [f32; 2],
+    pub zoom: f32,
+    #[serde(default)] // Clockwise quarter turns, 0 to 3; exports come out turned the same way
+    pub quarter_turns: u8,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            zoom: 1.0,
+            quarter_turns: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Page {
+    #[serde(default)]
+    pub strokes: Vec<Stroke>,
+    #[serde(default)]
+    pub text_elements: Vec<TextElement>,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)] // Older notebooks have no saved camera
+    pub camera: Camera,
+    // Consecutive pages with the same section are grouped together in the page list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connectors: Vec<Connector>,
+    // Canvas x of each vertical guide and canvas y of each horizontal guide
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub guides_x: Vec<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub guides_y: Vec<f32>,
+}
+
+impl Page {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            strokes: Vec::new(),
+            text_elements: Vec::new(),
+            name: name.into(),
+            camera: Camera::default(),
+            section: None,
+            connectors: Vec::new(),
+            guides_x: Vec::new(),
+            guides_y: Vec::new(),
+        }
+    }
+    
+    pub fn with_section(mut self, section: impl Into<String>) -> Self {
+        self.section = Some(section.into());
+        self
+    }
+    
+    pub fn add_stroke(&mut self, stroke: Stroke) -> &mut Self {
+        self.strokes.push(stroke);
+        self
+    }
+    
+    pub fn add_text(&mut self, text_element: TextElement) -> &mut Self {
+        self.text_elements.push(text_element);
+        self
+    }
+    
+    pub fn add_connector(&mut self, connector: Connector) -> &mut Self {
+        self.connectors.push(connector);
+        self
+    }
+    
+    fn migrate(&mut self, page_number: usize) {
+        if self.name.trim().is_empty() {
+            self.name = format!("Page {}", page_number);
+        }
+        for stroke in &mut self.strokes {
+            stroke.migrate();
+        }
+        for text_element in &mut self.text_elements {
+            text_element.migrate();
+        }
+        if !self.camera.zoom.is_finite() || self.camera.zoom <= 0.0 {
+            self.camera = Camera::default();
+        }
+        self.camera.quarter_turns %= 4;
+        migrate_connectors(&mut self.connectors, self.text_elements.len());
+        self.guides_x.retain(|x| x.is_finite());
+        self.guides_y.retain(|y| y.is_finite());
+    }
+}
+
+// A reusable group of elements kept in the app's settings rather than any notebook. Positions
+// are relative to the group's top-left corner, so copies can be placed anywhere.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stamp {
+    pub name: String,
+    #[serde(default)]
+    pub strokes: Vec<Stroke>,
+    #[serde(default)]
+    pub text_elements: Vec<TextElement>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connectors: Vec<Connector>, // Between the stamp's own text elements
+}
+
+impl Stamp {
+    // Gather the elements into a stamp, moving them so the top-left of their points and text
+    // positions is at the origin. Pinned text is unpinned, since a stamp belongs on the canvas.
+    pub fn new(name: impl Into<String>, mut strokes: Vec<Stroke>, mut text_elements: Vec<TextElement>, mut connectors: Vec<Connector>) -> Self {
+        let origin = strokes.iter()
+            .flat_map(|stroke| stroke.points.iter().copied())
+            .chain(text_elements.iter().map(|text_element| text_element.position))
+            .reduce(|[ax, ay], [bx, by]| [ax.min(bx), ay.min(by)])
+            .unwrap_or([0.0, 0.0]);
+        for stroke in &mut strokes {
+            for point in &mut stroke.points {
+                *point = [point[0] - origin[0], point[1] - origin[1]];
+            }
+        }
+        for text_element in &mut text_elements {
+            text_element.position = [text_element.position[0] - origin[0], text_element.position[1] - origin[1]];
+            text_element.pinned = false;
+        }
+        migrate_connectors(&mut connectors, text_elements.len());
+        Self { name: name.into(), strokes, text_elements, connectors }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty() && self.text_elements.is_empty()
+    }
+    
+    // A copy of the stamp with its top-left corner moved to `position`
+    pub fn placed_at(&self, position: [f32; 2]) -> Stamp {
+        let mut placed = self.clone();
+        for stroke in &mut placed.strokes {
+            for point in &mut stroke.points {
+                *point = [point[0] + position[0], point[1] + position[1]];
+            }
+        }
+        for text_element in &mut placed.text_elements {
+            text_element.position = [text_element.position[0] + position[0], text_element.position[1] + position[1]];
+        }
+        // Stamps are read back from a hand-editable settings file
+        migrate_connectors(&mut placed.connectors, placed.text_elements.len());
+        placed
+    }
+}
+
+// Drop strokes too short to draw and text with nothing to show, keeping connectors on the text that remains
+fn drop_degenerate(strokes: &mut Vec<Stroke>, text_elements: &mut Vec<TextElement>, connectors: &mut Vec<Connector>) {
+    strokes.retain(Stroke::is_drawable);
+    let blank: Vec<usize> = text_elements.iter().enumerate()
+        .filter(|(_, text_element)| text_element.is_blank())
+        .map(|(idx, _)| idx)
+        .collect();
+    if !blank.is_empty() {
+        text_elements.retain(|text_element| !text_element.is_blank());
+        remap_connectors(connectors, |idx| index_after_removal(idx, &blank));
+    }
+}
+
+// Drop connectors to elements that don't exist, or from an element to itself
+fn migrate_connectors(connectors: &mut Vec<Connector>, text_count: usize) {
+    connectors.retain(|connector| connector.from != connector.to && connector.from < text_count && connector.to < text_count);
+}
+
+// Descriptive information about a notebook. Times are Unix seconds, 0 when unknown.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct NotebookMeta {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub created: u64,
+    #[serde(default)]
+    pub modified: u64,
+}
+
+impl NotebookMeta {
+    // Metadata for a notebook created right now
+    pub fn new() -> Self {
+        let now = unix_now();
+        Self {
+            title: String::new(),
+            author: String::new(),
+            created: now,
+            modified: now,
+        }
+    }
+    
+    // Record a save, filling in the creation time for notebooks that predate metadata
+    pub fn touch(&mut self) {
+        self.modified = unix_now();
+        if self.created == 0 {
+            self.created = self.modified;
+        }
+    }
+}
+
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// "YYYY-MM-DD HH:MM UTC", or "unknown" for 0
+pub fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "unknown".to_string();
+    }
+    
+    let seconds_of_day = timestamp % 86_400;
+    format!("{} {:02}:{:02} UTC", format_date(timestamp), seconds_of_day / 3600, seconds_of_day % 3600 / 60)
+}
+
+// The UTC day a timestamp falls on, as "YYYY-MM-DD"
+pub fn format_date(timestamp: u64) -> String {
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (timestamp / 86_400) as i64;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// `pages` and `strokes` stay required: they are how the two formats are told apart
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Notebook {
+    #[serde(default)] // Files saved before versioning count as version 0
+    pub version: u32,
+    pub pages: Vec<Page>,
+    #[serde(default)]
+    pub current_page_index: usize,
+    #[serde(default = "default_canvas_size")]
+    pub canvas_size: [f32; 2],
+    // Font size new text in this notebook starts with, if the notebook remembers its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_font_size: Option<f32>,
+    #[serde(default)] // Notebooks saved before metadata existed have none
+    pub meta: NotebookMeta,
+}
+
+impl Default for Notebook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notebook {
+    // An empty notebook; add pages with `add_page`
+    pub fn new() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            pages: Vec::new(),
+            current_page_index: 0,
+            canvas_size: DEFAULT_CANVAS_SIZE,
+            text_font_size: None,
+            meta: NotebookMeta::new(),
+        }
+    }
+    
+    // Append a page and return it for filling in
+    pub fn add_page(&mut self, name: impl Into<String>) -> &mut Page {
+        self.pages.push(Page::new(name));
+        self.pages.last_mut().unwrap()
+    }
+    
+    // Read a notebook, converting a single-page project into a one-page notebook
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(Document::load(path)?.into_notebook())
+    }
+    
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+    
+    // Write the notebook, stamping its modified time. Degenerate elements are dropped first.
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        for page in &mut self.pages {
+            drop_degenerate(&mut page.strokes, &mut page.text_elements, &mut page.connectors);
+        }
+        self.meta.touch();
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+    
+    fn migrate(&mut self) {
+        if self.pages.is_empty() {
+            self.pages.push(Page::new(String::new()));
+        }
+        for (index, page) in self.pages.iter_mut().enumerate() {
+            page.migrate(index + 1);
+        }
+        self.current_page_index = self.current_page_index.min(self.pages.len() - 1);
+        migrate_canvas_size(&mut self.canvas_size);
+        self.text_font_size = self.text_font_size.filter(|size| size.is_finite() && *size > 0.0);
+        self.version = FORMAT_VERSION;
+    }
+}
+
+// The original single-page format, still written when not in notebook mode
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Project {
+    #[serde(default)]
+    pub version: u32,
+    pub strokes: Vec<Stroke>,
+    #[serde(default)] // The earliest files had drawings only
+    pub text_elements: Vec<TextElement>,
+    #[serde(default = "default_canvas_size")]
+    pub canvas_size: [f32; 2],
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connectors: Vec<Connector>,
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Project {
+    pub fn new() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            strokes: Vec::new(),
+            text_elements: Vec::new(),
+            canvas_size: DEFAULT_CANVAS_SIZE,
+            connectors: Vec::new(),
+        }
+    }
+    
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+    
+    // Write the project, dropping degenerate elements first
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        drop_degenerate(&mut self.strokes, &mut self.text_elements, &mut self.connectors);
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+    
+    fn migrate(&mut self) {
+        for stroke in &mut self.strokes {
+            stroke.migrate();
+        }
+        for text_element in &mut self.text_elements {
+            text_element.migrate();
+        }
+        migrate_connectors(&mut self.connectors, self.text_elements.len());
+        migrate_canvas_size(&mut self.canvas_size);
+        self.version = FORMAT_VERSION;
+    }
+}
+
+// Either kind of .scribble file, already migrated to the current format
+pub enum Document {
+    Notebook(Notebook),
+    Project(Project),
+}
+
+impl Document {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let json = fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+    
+    // The content decides the format, whatever the file is called: a notebook has `pages`,
+    // a single-page project has `strokes`
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn Error>> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| format!("Not a Scribble file: the content isn't valid JSON ({})", e))?;
+        if value.get("pages").is_some() {
+            let mut notebook: Notebook = serde_json::from_value(value)
+                .map_err(|e| format!("Invalid notebook: {}", e))?;
+            notebook.migrate();
+            Ok(Document::Notebook(notebook))
+        } else if value.get("strokes").is_some() {
+            let mut project: Project = serde_json::from_value(value)
+                .map_err(|e| format!("Invalid single-page project: {}", e))?;
+            project.migrate();
+            Ok(Document::Project(project))
+        } else {
+            Err("Not a Scribble file: it has neither a notebook's pages nor a project's strokes".into())
+        }
+    }
+    
+    pub fn into_notebook(self) -> Notebook {
+        match self {
+            Document::Notebook(notebook) => notebook,
+            Document::Project(project) => {
+                let mut notebook = Notebook::new();
+                let page = notebook.add_page("Imported Page");
+                page.strokes = project.strokes;
+                page.text_elements = project.text_elements;
+                page.connectors = project.connectors;
+                notebook.canvas_size = project.canvas_size;
+                notebook
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // A notebook as the first release wrote it: no version, no camera or metadata, multiline text
+    // split into `lines`, and values later versions no longer allow
+    const LEGACY_NOTEBOOK: &str = r#"{
+        "pages": [
+            {
+                "strokes": [
+                    { "points": [[0, 0], [10, 5], [20, 0]], "color": [200, 0, 0], "width": 0, "pressures": [0.5] }
+                ],
+                "text_elements": [
+                    { "position": [40, 60], "lines": ["first line", "second line"], "font_size": -3 },
+                    { "position": [5, 5], "text": "windows\r\ntext" }
+                ],
+                "connectors": [{ "from": 0, "to": 1 }, { "from": 1, "to": 1 }, { "from": 0, "to": 7 }]
+            },
+            { "strokes": [], "name": "Ideas", "camera": { "offset": [0, 0], "zoom": 0 } }
+        ],
+        "current_page_index": 9,
+        "canvas_size": [-1, 600]
+    }"#;
+    
+    fn load_notebook(json: &str) -> Notebook {
+        match Document::from_json(json).expect("legacy notebook should load") {
+            Document::Notebook(notebook) => notebook,
+            Document::Project(_) => panic!("a file with pages is a notebook"),
+        }
+    }
+    
+    #[test]
+    fn legacy_notebooks_migrate_and_round_trip() {
+        let notebook = load_notebook(LEGACY_NOTEBOOK);
+        assert_eq!(notebook.version, FORMAT_VERSION);
+        assert_eq!(notebook.canvas_size, DEFAULT_CANVAS_SIZE);
+        assert_eq!(notebook.current_page_index, 1);
+        
+        let page = &notebook.pages[0];
+        assert_eq!(page.name, "Page 1");
+        assert_eq!(page.strokes[0].width, DEFAULT_STROKE_WIDTH);
+        assert!(page.strokes[0].pressures.is_empty(), "pressures that don't match the points are dropped");
+        assert_eq!(page.text_elements[0].text, "first line\nsecond line");
+        assert_eq!(page.text_elements[0].font_size, DEFAULT_FONT_SIZE);
+        assert_eq!(page.text_elements[1].text, "windows\ntext");
+        assert_eq!(page.connectors.len(), 1);
+        assert_eq!((page.connectors[0].from, page.connectors[0].to), (0, 1));
+        assert_eq!(notebook.pages[1].name, "Ideas");
+        assert_eq!(notebook.pages[1].camera.zoom, 1.0);
+        
+        // Saving the migrated notebook and loading it back changes nothing
+        let json = notebook.to_json().unwrap();
+        assert!(!json.contains("\"lines\""), "legacy fields aren't written back");
+        let reloaded = load_notebook(&json);
+        assert_eq!(reloaded.to_json().unwrap(), json);
+    }
+    
+    #[test]
+    fn legacy_projects_load_as_projects() {
+        let json = r#"{ "strokes": [{ "points": [[1, 2], [3, 4]] }] }"#;
+        let Document::Project(project) = Document::from_json(json).unwrap() else {
+            panic!("a file with strokes and no pages is a project");
+        };
+        assert_eq!(project.version, FORMAT_VERSION);
+        assert!(project.text_elements.is_empty());
+        assert_eq!(project.strokes[0].color, [0, 0, 0]);
+        assert_eq!(project.strokes[0].width, DEFAULT_STROKE_WIDTH);
+    }
+    
+    #[test]
+    fn saved_files_have_no_degenerate_elements() {
+        let mut notebook = Notebook::new();
+        let page = notebook.add_page("Page 1");
+        page.add_stroke(Stroke::new(vec![[0.0, 0.0], [10.0, 10.0]]))
+            .add_stroke(Stroke::new(vec![[5.0, 5.0]]))
+            .add_stroke(Stroke::new(Vec::new()))
+            .add_text(TextElement::new([0.0, 0.0], "kept"))
+            .add_text(TextElement::new([10.0, 0.0], "  \n "))
+            .add_text(TextElement::new([20.0, 0.0], "also kept"))
+            .add_connector(Connector::new(0, 2))
+            .add_connector(Connector::new(1, 2));
+        
+        let path = std::env::temp_dir().join(format!("scribble-degenerate-{}.scribble", std::process::id()));
+        notebook.save(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        
+        let Document::Notebook(loaded) = Document::from_json(&saved).unwrap() else {
+            panic!("a saved notebook loads as a notebook");
+        };
+        let page = &loaded.pages[0];
+        assert_eq!(page.strokes.len(), 1);
+        assert!(page.strokes.iter().all(Stroke::is_drawable));
+        let texts: Vec<&str> = page.text_elements.iter().map(|text_element| text_element.text.as_str()).collect();
+        assert_eq!(texts, ["kept", "also kept"]);
+        // The connector between kept text follows it to its new index; the one to blank text goes
+        assert_eq!(page.connectors.iter().map(|connector| (connector.from, connector.to)).collect::<Vec<_>>(), [(0, 1)]);
+    }
+}
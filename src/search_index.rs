@@ -0,0 +1,162 @@
+// Trigram index over a notebook's text, so literal searches only verify the elements that can match
+// instead of scanning every one. Regex searches don't go through it.
+
+use std::collections::{BTreeSet, HashMap};
+
+type Trigram = [char; 3];
+
+// Where a text element lives: (page index, element index)
+pub type TextLocation = (usize, usize);
+
+// An element's text as last indexed, kept as given so unchanged text is spotted without lowercasing it again
+struct IndexedText {
+    text: String,
+    lower: String,
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+    // Each element's text, by page
+    texts: Vec<Vec<IndexedText>>,
+    postings: HashMap<Trigram, BTreeSet<TextLocation>>,
+}
+
+fn trigrams(text: &str) -> BTreeSet<Trigram> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(3).map(|window| [window[0], window[1], window[2]]).collect()
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    // Bring one page up to date, re-indexing only the elements whose text changed
+    pub fn sync_page<'a>(&mut self, page_index: usize, texts: impl IntoIterator<Item = &'a str>) {
+        if self.texts.len() <= page_index {
+            self.texts.resize_with(page_index + 1, Vec::new);
+        }
+        let mut count = 0;
+        for (element_index, text) in texts.into_iter().enumerate() {
+            count += 1;
+            if self.texts[page_index].get(element_index).is_some_and(|indexed| indexed.text == text) {
+                continue;
+            }
+            self.remove_element((page_index, element_index));
+            let indexed = IndexedText { text: text.to_string(), lower: text.to_lowercase() };
+            for trigram in trigrams(&indexed.lower) {
+                self.postings.entry(trigram).or_default().insert((page_index, element_index));
+            }
+            let page = &mut self.texts[page_index];
+            if element_index < page.len() {
+                page[element_index] = indexed;
+            } else {
+                page.push(indexed);
+            }
+        }
+        while self.texts[page_index].len() > count {
+            let element_index = self.texts[page_index].len() - 1;
+            self.remove_element((page_index, element_index));
+            self.texts[page_index].pop();
+        }
+    }
+    
+    // Forget pages past the end of the notebook
+    pub fn truncate_pages(&mut self, page_count: usize) {
+        while self.texts.len() > page_count {
+            let page_index = self.texts.len() - 1;
+            for element_index in (0..self.texts[page_index].len()).rev() {
+                self.remove_element((page_index, element_index));
+            }
+            self.texts.pop();
+        }
+    }
+    
+    fn remove_element(&mut self, element: TextLocation) {
+        let Some(indexed) = self.texts.get(element.0).and_then(|page| page.get(element.1)) else {
+            return;
+        };
+        for trigram in trigrams(&indexed.lower) {
+            if let Some(elements) = self.postings.get_mut(&trigram) {
+                elements.remove(&element);
+                if elements.is_empty() {
+                    self.postings.remove(&trigram);
+                }
+            }
+        }
+    }
+    
+    // Every element containing the query, ignoring case, in page then element order. Queries too
+    // short to have a trigram fall back to checking each indexed text.
+    pub fn find(&self, query: &str) -> Vec<TextLocation> {
+        let query = query.to_lowercase();
+        let query_trigrams = trigrams(&query);
+        let contains = |&(page_index, element_index): &TextLocation| self.texts[page_index][element_index].lower.contains(&query);
+        if query_trigrams.is_empty() {
+            return self.texts.iter().enumerate()
+                .flat_map(|(page_index, page)| (0..page.len()).map(move |element_index| (page_index, element_index)))
+                .filter(contains)
+                .collect();
+        }
+        
+        // Start from the rarest trigram and keep the elements that have all the others
+        let mut lists = Vec::new();
+        for trigram in &query_trigrams {
+            match self.postings.get(trigram) {
+                Some(elements) => lists.push(elements),
+                None => return Vec::new(),
+            }
+        }
+        lists.sort_by_key(|elements| elements.len());
+        lists[0].iter()
+            .filter(|element| lists[1..].iter().all(|elements| elements.contains(element)))
+            .copied()
+            .filter(contains)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn index_of(pages: &[&[&str]]) -> SearchIndex {
+        let mut index = SearchIndex::new();
+        for (page_index, page) in pages.iter().enumerate() {
+            index.sync_page(page_index, page.iter().copied());
+        }
+        index
+    }
+    
+    #[test]
+    fn finds_every_element_containing_the_query() {
+        let index = index_of(&[&["grocery list", "call the bank"], &["bank holiday", "nothing here"]]);
+        assert_eq!(index.find("bank"), vec![(0, 1), (1, 0)]);
+        assert_eq!(index.find("list"), vec![(0, 0)]);
+        assert_eq!(index.find("missing"), Vec::<TextLocation>::new());
+        // Too short for a trigram, so every text is checked
+        assert_eq!(index.find("ba"), vec![(0, 1), (1, 0)]);
+    }
+    
+    #[test]
+    fn lookups_ignore_case() {
+        let index = index_of(&[&["Quarterly REPORT", "report draft"]]);
+        assert_eq!(index.find("report"), vec![(0, 0), (0, 1)]);
+        assert_eq!(index.find("REPORT Draft"), vec![(0, 1)]);
+    }
+    
+    #[test]
+    fn syncing_picks_up_edits_and_removals() {
+        let mut index = index_of(&[&["first note", "second note"], &["other page"]]);
+        index.sync_page(0, ["first memo", "second note", "third note"]);
+        assert_eq!(index.find("note"), vec![(0, 1), (0, 2)]);
+        assert_eq!(index.find("memo"), vec![(0, 0)]);
+        
+        index.sync_page(0, ["first memo"]);
+        assert_eq!(index.find("note"), Vec::<TextLocation>::new());
+        
+        index.truncate_pages(1);
+        assert_eq!(index.find("page"), Vec::<TextLocation>::new());
+        assert!(index.postings.values().all(|elements| elements.iter().all(|&(page_index, _)| page_index == 0)));
+    }
+}